@@ -0,0 +1,238 @@
+//! A minimal ELF reader that only knows how to list sections.
+//!
+//! This is all that is required for extracting the device metadata out of a linked image, so it
+//! does not deserve pulling a full-featured object file parser.
+
+/// Defines the errors when reading an ELF image.
+pub mod error {
+    #[doc(hidden)]
+    pub type Result<T, E = Error> = ::core::result::Result<T, E>;
+
+    #[doc(hidden)]
+    #[derive(Debug, PartialEq, Eq, thiserror::Error)]
+    pub enum Error {
+        #[error("not an ELF image")]
+        InvalidMagic,
+
+        #[error("unsupported ELF class {0}")]
+        UnsupportedClass(u8),
+
+        #[error("unsupported ELF data encoding {0}")]
+        UnsupportedEncoding(u8),
+
+        #[error("truncated ELF image")]
+        Truncated,
+    }
+}
+
+pub use error::{Error, Result};
+
+const ELF_MAGIC: &[u8; 4] = b"\x7fELF";
+
+const ELFCLASS32: u8 = 1;
+const ELFCLASS64: u8 = 2;
+
+const ELFDATA2LSB: u8 = 1;
+const ELFDATA2MSB: u8 = 2;
+
+const SHT_NOBITS: u32 = 8;
+
+/// A section of an ELF image.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Section<'a> {
+    /// The name of the section.
+    pub name: &'a str,
+
+    /// The content of the section, which is empty for sections that occupy no file space.
+    pub data: &'a [u8],
+}
+
+/// A cursor over the raw bytes of the ELF image that handles the class and endianness.
+#[derive(Clone, Copy)]
+struct Reader<'a> {
+    bytes: &'a [u8],
+    wide: bool,
+    big: bool,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes<const N: usize>(&self, offset: usize) -> Result<[u8; N]> {
+        self.bytes
+            .get(offset..offset + N)
+            .and_then(|x| x.try_into().ok())
+            .ok_or(Error::Truncated)
+    }
+
+    fn u16(&self, offset: usize) -> Result<u16> {
+        let b = self.bytes(offset)?;
+        Ok(if self.big {
+            u16::from_be_bytes(b)
+        } else {
+            u16::from_le_bytes(b)
+        })
+    }
+
+    fn u32(&self, offset: usize) -> Result<u32> {
+        let b = self.bytes(offset)?;
+        Ok(if self.big {
+            u32::from_be_bytes(b)
+        } else {
+            u32::from_le_bytes(b)
+        })
+    }
+
+    /// Read an address-sized word, which is 32 or 64 bits depending on the ELF class.
+    fn word(&self, offset: usize) -> Result<usize> {
+        if self.wide {
+            let b = self.bytes(offset)?;
+            let x = if self.big {
+                u64::from_be_bytes(b)
+            } else {
+                u64::from_le_bytes(b)
+            };
+            usize::try_from(x).map_err(|_| Error::Truncated)
+        } else {
+            self.u32(offset).map(|x| x as usize)
+        }
+    }
+
+    fn slice(&self, offset: usize, size: usize) -> Result<&'a [u8]> {
+        offset
+            .checked_add(size)
+            .and_then(|end| self.bytes.get(offset..end))
+            .ok_or(Error::Truncated)
+    }
+}
+
+/// List the sections of the given ELF image.
+pub fn sections<'a>(bytes: &'a [u8]) -> Result<Vec<Section<'a>>> {
+    if bytes.get(..4) != Some(ELF_MAGIC) {
+        return Err(Error::InvalidMagic);
+    }
+
+    let wide = match bytes.get(4).copied().ok_or(Error::Truncated)? {
+        ELFCLASS32 => false,
+        ELFCLASS64 => true,
+        x => return Err(Error::UnsupportedClass(x)),
+    };
+
+    let big = match bytes.get(5).copied().ok_or(Error::Truncated)? {
+        ELFDATA2LSB => false,
+        ELFDATA2MSB => true,
+        x => return Err(Error::UnsupportedEncoding(x)),
+    };
+
+    let r = Reader { bytes, wide, big };
+
+    // Offsets of the section header table fields in the ELF header.
+    let (shoff, shentsize, shnum, shstrndx) = if wide {
+        (0x28, 0x3a, 0x3c, 0x3e)
+    } else {
+        (0x20, 0x2e, 0x30, 0x32)
+    };
+
+    let shoff = r.word(shoff)?;
+    let shentsize = r.u16(shentsize)? as usize;
+    let shnum = r.u16(shnum)? as usize;
+    let shstrndx = r.u16(shstrndx)? as usize;
+
+    // Offsets of the fields in a section header entry.
+    let (offset, size) = if wide { (0x18, 0x20) } else { (0x10, 0x14) };
+
+    let header = move |index: usize| -> Result<(u32, u32, &'a [u8])> {
+        let base = shoff + index * shentsize;
+        let name = r.u32(base)?;
+        let kind = r.u32(base + 4)?;
+
+        let data = if kind == SHT_NOBITS {
+            &[][..]
+        } else {
+            r.slice(r.word(base + offset)?, r.word(base + size)?)?
+        };
+
+        Ok((name, kind, data))
+    };
+
+    let (_, _, names) = header(shstrndx)?;
+
+    (0..shnum)
+        .map(|index| {
+            let (name, _, data) = header(index)?;
+            let name = names.get(name as usize..).ok_or(Error::Truncated)?;
+            let name = name.split(|&b| b == 0).next().unwrap_or_default();
+            let name = core::str::from_utf8(name).map_err(|_| Error::Truncated)?;
+            Ok(Section { name, data })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use googletest::prelude::*;
+
+    use super::*;
+
+    /// Build a little-endian 64-bit ELF image that only contains the given sections.
+    pub(crate) fn image(sections: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut names = vec![0u8];
+        let mut offsets = Vec::new();
+        for (name, _) in sections.iter().chain([(".shstrtab", &[][..])].iter()) {
+            offsets.push(names.len() as u32);
+            names.extend_from_slice(name.as_bytes());
+            names.push(0);
+        }
+
+        let mut out = vec![0u8; 0x40];
+        out[..4].copy_from_slice(ELF_MAGIC);
+        out[4] = ELFCLASS64;
+        out[5] = ELFDATA2LSB;
+
+        let mut headers = vec![[0u8; 0x40]];
+        let datas = sections.iter().map(|(_, d)| *d).chain([names.as_slice()]);
+        for (data, name) in datas.zip(offsets) {
+            let mut header = [0u8; 0x40];
+            header[..4].copy_from_slice(&name.to_le_bytes());
+            header[4..8].copy_from_slice(&1u32.to_le_bytes());
+            header[0x18..0x20].copy_from_slice(&(out.len() as u64).to_le_bytes());
+            header[0x20..0x28].copy_from_slice(&(data.len() as u64).to_le_bytes());
+            headers.push(header);
+            out.extend_from_slice(data);
+        }
+
+        let shoff = out.len() as u64;
+        out[0x28..0x30].copy_from_slice(&shoff.to_le_bytes());
+        out[0x3a..0x3c].copy_from_slice(&0x40u16.to_le_bytes());
+        out[0x3c..0x3e].copy_from_slice(&(headers.len() as u16).to_le_bytes());
+        out[0x3e..0x40].copy_from_slice(&(headers.len() as u16 - 1).to_le_bytes());
+        headers.iter().for_each(|h| out.extend_from_slice(h));
+
+        out
+    }
+
+    #[test]
+    fn it_should_list_sections() -> googletest::Result<()> {
+        let bytes = image(&[(".text", b"abc"), (".dedrv.meta", b"def")]);
+        let sections = sections(&bytes)?;
+
+        verify_that!(
+            sections,
+            contains(eq(&Section {
+                name: ".dedrv.meta",
+                data: b"def"
+            }))
+        )?;
+
+        verify_that!(sections.len(), eq(4))
+    }
+
+    #[test]
+    fn it_should_reject_non_elf() -> googletest::Result<()> {
+        verify_that!(sections(b"not an elf"), err(eq(&Error::InvalidMagic)))
+    }
+
+    #[test]
+    fn it_should_reject_truncated_image() -> googletest::Result<()> {
+        let bytes = image(&[(".text", b"abc")]);
+        verify_that!(sections(&bytes[..0x50]), err(eq(&Error::Truncated)))
+    }
+}
@@ -0,0 +1,30 @@
+#![deny(missing_docs)]
+
+//! This crate provides the host-side build support of `dedrv`.
+//!
+//! The `device` attribute records some metadata about each registered device into the linked
+//! image. This crate reads these records back in order to verify the device registry (i.e. path
+//! format and uniqueness) and to emit a human-readable `dedrv.map` file, which lists every device
+//! with its path, driver and linker section.
+
+use std::fs;
+use std::path::Path;
+
+pub mod elf;
+pub mod map;
+
+pub use map::{DeviceMap, Entry};
+
+/// Build and validate the device map of the given ELF image, then write it to `output`.
+///
+/// The map file is written even if the validation fails, so one can inspect the offending
+/// entries.
+pub fn generate_map(elf: impl AsRef<Path>, output: impl AsRef<Path>) -> anyhow::Result<DeviceMap> {
+    let bytes = fs::read(elf.as_ref())?;
+
+    let map = DeviceMap::from_elf(&bytes)?;
+    fs::write(output.as_ref(), map.to_string())?;
+
+    map.validate()?;
+    Ok(map)
+}
@@ -0,0 +1,27 @@
+use std::env;
+use std::path::PathBuf;
+
+use anyhow::Context;
+
+fn main() -> anyhow::Result<()> {
+    let mut args = env::args_os().skip(1);
+
+    let elf = PathBuf::from(args.next().context("usage: dedrv-map <ELF> [OUTPUT]")?);
+
+    // By default, the map file is written next to the ELF image.
+    let output = args
+        .next()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| elf.with_file_name("dedrv.map"));
+
+    let map = dedrv_build::generate_map(&elf, &output)
+        .with_context(|| format!("failed to generate device map for {}", elf.display()))?;
+
+    println!(
+        "wrote {} devices to {}",
+        map.entries().len(),
+        output.display()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,387 @@
+//! The device map, which lists every device that is registered in a linked image.
+//!
+//! The map is built from the metadata records that the `device` attribute stores in the
+//! `.dedrv.meta.*` sections. Each record is a sequence of NUL-terminated strings:
+//!
+//! ```text
+//! <version> <path> <device> <driver> <section>
+//! ```
+//!
+//! The linker script gathers these records into a non-allocated `.dedrv.meta` section, so they
+//! do not occupy any space on the target.
+
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+use crate::elf;
+
+/// Defines the errors when building and validating a device map.
+pub mod error {
+    #[doc(hidden)]
+    pub type Result<T, E = Error> = ::core::result::Result<T, E>;
+
+    #[doc(hidden)]
+    #[derive(Debug, PartialEq, Eq, thiserror::Error)]
+    pub enum Error {
+        #[error(transparent)]
+        Elf(#[from] crate::elf::Error),
+
+        #[error("unsupported metadata record version '{0}'")]
+        UnsupportedVersion(String),
+
+        #[error("truncated metadata record")]
+        Truncated,
+
+        #[error("invalid path '{path}' for device {device}: {reason}")]
+        InvalidPath {
+            path: String,
+            device: String,
+            reason: &'static str,
+        },
+
+        #[error("duplicate path '{path}' for devices {first} and {second}")]
+        DuplicatePath {
+            path: String,
+            first: String,
+            second: String,
+        },
+
+        #[error("colliding path identifier {id:#010x} for paths '{first}' and '{second}'")]
+        PathCollision {
+            id: u32,
+            first: String,
+            second: String,
+        },
+    }
+}
+
+pub use error::{Error, Result};
+
+/// The version of the metadata record layout.
+pub const RECORD_VERSION: &str = "1";
+
+/// The name prefix of the sections that hold the metadata records.
+pub const SECTION_PREFIX: &str = ".dedrv.meta";
+
+/// A device entry of the map.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// The path of the device.
+    pub path: String,
+
+    /// The name of the static device instance.
+    pub device: String,
+
+    /// The type of the static device instance.
+    pub driver: String,
+
+    /// The linker section that holds the device descriptor.
+    pub section: String,
+}
+
+impl Entry {
+    /// Check whether the device is a weak default one, which is overridden by any other device at
+    /// the same path.
+    pub fn is_weak(&self) -> bool {
+        self.section.ends_with(".weak")
+    }
+}
+
+/// The map of all devices that are registered in a linked image.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DeviceMap {
+    entries: Vec<Entry>,
+}
+
+impl DeviceMap {
+    /// Build the device map out of the metadata sections of an ELF image.
+    pub fn from_elf(bytes: &[u8]) -> Result<Self> {
+        let mut map = DeviceMap::default();
+
+        for section in elf::sections(bytes)? {
+            if section.name == SECTION_PREFIX
+                || section
+                    .name
+                    .strip_prefix(SECTION_PREFIX)
+                    .is_some_and(|x| x.starts_with('.'))
+            {
+                map.entries.extend(parse(section.data)?);
+            }
+        }
+
+        map.entries.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(map)
+    }
+
+    /// The device entries, sorted by path.
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// Verify that every device path is well-formed and unique, except that a weak default device
+    /// may share its path with the device that overrides it.
+    ///
+    /// The identifiers of the paths (i.e. `dedrv::PathId`) are also verified to be unique, so the
+    /// lookups by identifier never resolve a collision.
+    pub fn validate(&self) -> Result<()> {
+        let mut seen = BTreeMap::new();
+        let mut ids = BTreeMap::new();
+
+        for entry in &self.entries {
+            if let Err(reason) = check_path(&entry.path) {
+                return Err(Error::InvalidPath {
+                    path: entry.path.clone(),
+                    device: entry.device.clone(),
+                    reason,
+                });
+            }
+
+            let key = (entry.path.as_str(), entry.is_weak());
+            if let Some(first) = seen.insert(key, entry.device.as_str()) {
+                return Err(Error::DuplicatePath {
+                    path: entry.path.clone(),
+                    first: first.to_string(),
+                    second: entry.device.clone(),
+                });
+            }
+
+            let id = path_id(&entry.path);
+            match ids.insert(id, entry.path.as_str()) {
+                Some(first) if first != entry.path => {
+                    return Err(Error::PathCollision {
+                        id,
+                        first: first.to_string(),
+                        second: entry.path.clone(),
+                    })
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl FromIterator<Entry> for DeviceMap {
+    fn from_iter<T: IntoIterator<Item = Entry>>(iter: T) -> Self {
+        let mut entries: Vec<_> = iter.into_iter().collect();
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+        DeviceMap { entries }
+    }
+}
+
+impl Display for DeviceMap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        const HEADER: [&str; 4] = ["PATH", "DEVICE", "DRIVER", "SECTION"];
+
+        let width = |n: usize, get: fn(&Entry) -> &str| {
+            self.entries
+                .iter()
+                .map(|e| get(e).len())
+                .fold(HEADER[n].len(), usize::max)
+        };
+
+        let w0 = width(0, |e| &e.path);
+        let w1 = width(1, |e| &e.device);
+        let w2 = width(2, |e| &e.driver);
+
+        writeln!(f, "# dedrv device map ({} devices)", self.entries.len())?;
+        writeln!(
+            f,
+            "{:w0$}  {:w1$}  {:w2$}  {}",
+            HEADER[0], HEADER[1], HEADER[2], HEADER[3]
+        )?;
+
+        for e in &self.entries {
+            writeln!(
+                f,
+                "{:w0$}  {:w1$}  {:w2$}  {}",
+                e.path, e.device, e.driver, e.section
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Parse the metadata records that are concatenated in `data`.
+fn parse(data: &[u8]) -> Result<Vec<Entry>> {
+    let mut fields = data
+        .split(|&b| b == 0)
+        .map(|x| String::from_utf8_lossy(x).into_owned());
+
+    let mut entries = Vec::new();
+
+    // Records may be padded with zeroes, so skip empty fields before each record.
+    while let Some(version) = fields.by_ref().find(|x| !x.is_empty()) {
+        if version != RECORD_VERSION {
+            return Err(Error::UnsupportedVersion(version));
+        }
+
+        let mut next = || fields.next().ok_or(Error::Truncated);
+        entries.push(Entry {
+            path: next()?,
+            device: next()?,
+            driver: next()?,
+            section: next()?,
+        });
+    }
+
+    Ok(entries)
+}
+
+/// Compute the identifier of a device path, which is its 32-bit FNV-1a hash (i.e. the one of
+/// `dedrv::PathId`).
+pub fn path_id(path: &str) -> u32 {
+    path.bytes().fold(0x811c_9dc5, |hash, b| {
+        (hash ^ b as u32).wrapping_mul(0x0100_0193)
+    })
+}
+
+/// Check that a device path is well-formed, with the same rules as the `device` attribute.
+fn check_path(path: &str) -> ::core::result::Result<(), &'static str> {
+    dedrv_path::check(path).map_err(|e| e.message())
+}
+
+#[cfg(test)]
+mod tests {
+    use googletest::prelude::*;
+
+    use super::*;
+
+    fn entry(path: &str, device: &str) -> Entry {
+        Entry {
+            path: path.into(),
+            device: device.into(),
+            driver: "Device<Driver>".into(),
+            section: format!(".dedrv.device.{}", device.to_lowercase()),
+        }
+    }
+
+    #[test]
+    fn it_should_parse_records_from_elf() -> googletest::Result<()> {
+        let bytes = elf::tests::image(&[
+            (".text", b"\x00\x01"),
+            (
+                ".dedrv.meta",
+                b"1\0/uart0\0UART0\0Device<Uart>\0.dedrv.device.uart0\0\0\0\
+                  1\0/gpio0\0GPIO0\0Device<Gpio>\0.dedrv.device.gpio0\0",
+            ),
+        ]);
+
+        let map = DeviceMap::from_elf(&bytes)?;
+
+        verify_that!(
+            map.entries(),
+            elements_are![
+                eq(&Entry {
+                    path: "/gpio0".into(),
+                    device: "GPIO0".into(),
+                    driver: "Device<Gpio>".into(),
+                    section: ".dedrv.device.gpio0".into(),
+                }),
+                field!(Entry.path, eq("/uart0")),
+            ]
+        )
+    }
+
+    #[test]
+    fn it_should_reject_unknown_record_version() -> googletest::Result<()> {
+        let bytes = elf::tests::image(&[(".dedrv.meta", b"2\0/gpio0\0GPIO0\0D\0S\0")]);
+
+        verify_that!(
+            DeviceMap::from_elf(&bytes),
+            err(eq(&Error::UnsupportedVersion("2".into())))
+        )
+    }
+
+    #[test]
+    fn it_should_reject_truncated_record() -> googletest::Result<()> {
+        let bytes = elf::tests::image(&[(".dedrv.meta", b"1\0/gpio0\0GPIO0\0")]);
+        verify_that!(DeviceMap::from_elf(&bytes), err(eq(&Error::Truncated)))
+    }
+
+    #[test]
+    fn it_should_reject_duplicate_paths() -> googletest::Result<()> {
+        let map: DeviceMap = [entry("/gpio0", "GPIO0"), entry("/gpio0", "GPIO1")]
+            .into_iter()
+            .collect();
+
+        verify_that!(
+            map.validate(),
+            err(eq(&Error::DuplicatePath {
+                path: "/gpio0".into(),
+                first: "GPIO0".into(),
+                second: "GPIO1".into(),
+            }))
+        )
+    }
+
+    #[test]
+    fn it_should_accept_overridden_weak_device() -> googletest::Result<()> {
+        let mut weak = entry("/gpio0", "GPIO0");
+        weak.section.push_str(".weak");
+
+        let map: DeviceMap = [weak.clone(), entry("/gpio0", "APP_GPIO0")]
+            .into_iter()
+            .collect();
+        verify_that!(map.validate(), ok(eq(&())))?;
+
+        let map: DeviceMap = [weak.clone(), weak].into_iter().collect();
+        verify_that!(
+            map.validate(),
+            err(matches_pattern!(Error::DuplicatePath { .. }))
+        )
+    }
+
+    #[test]
+    fn it_should_reject_colliding_path_ids() -> googletest::Result<()> {
+        // These paths share the same FNV-1a hash.
+        let map: DeviceMap = [entry("/dev1399086", "DEV0"), entry("/dev64059", "DEV1")]
+            .into_iter()
+            .collect();
+
+        verify_that!(path_id("/dev1399086"), eq(path_id("/dev64059")))?;
+        verify_that!(
+            map.validate(),
+            err(matches_pattern!(Error::PathCollision { .. }))
+        )
+    }
+
+    #[test]
+    fn it_should_reject_malformed_paths() -> googletest::Result<()> {
+        for path in ["", "gpio0", "/gpio0/", "/gpio//0", "/gpio 0"] {
+            let map: DeviceMap = [entry(path, "GPIO0")].into_iter().collect();
+            verify_that!(
+                map.validate(),
+                err(matches_pattern!(Error::InvalidPath { .. }))
+            )?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_render_map() -> googletest::Result<()> {
+        let map: DeviceMap = [entry("/uart0", "UART0"), entry("/gpio0", "GPIO0")]
+            .into_iter()
+            .collect();
+
+        let text = map.to_string();
+        let mut lines = text.lines();
+
+        verify_that!(lines.next(), some(eq("# dedrv device map (2 devices)")))?;
+        verify_that!(
+            lines.next(),
+            some(eq("PATH    DEVICE  DRIVER          SECTION"))
+        )?;
+        verify_that!(
+            lines.next(),
+            some(eq("/gpio0  GPIO0   Device<Driver>  .dedrv.device.gpio0"))
+        )?;
+        verify_that!(
+            lines.next(),
+            some(eq("/uart0  UART0   Device<Driver>  .dedrv.device.uart0"))
+        )
+    }
+}
@@ -0,0 +1,348 @@
+use std::collections::HashMap;
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{
+    bracketed, Attribute, Expr, ExprLit, Ident, Item, ItemMod, Lit, LitInt, Meta, Token, Type,
+    Visibility,
+};
+
+use crate::helpers::{error, token_stream_with_error};
+
+/// A device of the board, i.e. a static instance with the `device` attribute.
+struct Device {
+    ident: Ident,
+    vis: Visibility,
+    ty: Type,
+    path: String,
+}
+
+/// A pin assignment, e.g. `LED_GREEN = GPIOB[0]`.
+struct Pin {
+    ident: Ident,
+    device: Ident,
+    line: LitInt,
+}
+
+impl Parse for Pin {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let ident = input.parse()?;
+        input.parse::<Token![=]>()?;
+        let device = input.parse()?;
+        let content;
+        bracketed!(content in input);
+
+        Ok(Pin {
+            ident,
+            device,
+            line: content.parse()?,
+        })
+    }
+}
+
+/// An alias of a device, e.g. `CONSOLE = USART3`.
+struct Alias {
+    ident: Ident,
+    device: Ident,
+}
+
+impl Parse for Alias {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let ident = input.parse()?;
+        input.parse::<Token![=]>()?;
+
+        Ok(Alias {
+            ident,
+            device: input.parse()?,
+        })
+    }
+}
+
+pub fn run(item: TokenStream) -> TokenStream {
+    let mut errors = TokenStream::new();
+
+    let mut board: ItemMod = match syn::parse2(item.clone()) {
+        Ok(x) => x,
+        Err(e) => return token_stream_with_error(item, e),
+    };
+
+    let Some((_, items)) = board.content.take() else {
+        error(&mut errors, &board.ident, "a board must have a body");
+        return quote!(#board #errors);
+    };
+
+    // The devices of the board are the static instances with the `device` attribute, whose path
+    // is read from the attribute itself.
+    let devices: Vec<Device> = items
+        .iter()
+        .filter_map(|item| match item {
+            Item::Static(var) => device_path(&var.attrs).map(|path| Device {
+                ident: var.ident.clone(),
+                vis: var.vis.clone(),
+                ty: (*var.ty).clone(),
+                path,
+            }),
+            _ => None,
+        })
+        .collect();
+    let find = |ident: &Ident, errors: &mut TokenStream| {
+        let device = devices.iter().find(|x| x.ident == *ident);
+        if device.is_none() {
+            error(
+                errors,
+                ident,
+                format!("unknown device `{ident}` on the board"),
+            );
+        }
+        device
+    };
+
+    // The `pins!` and `aliases!` items are replaced by their declarations.
+    let mut content = Vec::new();
+    let mut pins = Vec::new();
+    let mut aliases = Vec::new();
+    let mut lines = HashMap::new();
+
+    for item in items {
+        let Item::Macro(mac) = &item else {
+            content.push(quote!(#item));
+            continue;
+        };
+
+        if mac.mac.path.is_ident("pins") {
+            let list = match mac
+                .mac
+                .parse_body_with(Punctuated::<Pin, Token![,]>::parse_terminated)
+            {
+                Ok(x) => x,
+                Err(e) => {
+                    errors.extend(e.into_compile_error());
+                    continue;
+                }
+            };
+
+            for pin in list {
+                let Some(device) = find(&pin.device, &mut errors) else {
+                    continue;
+                };
+
+                let line = match pin.line.base10_parse::<u32>() {
+                    Ok(x) => x,
+                    Err(e) => {
+                        errors.extend(e.into_compile_error());
+                        continue;
+                    }
+                };
+
+                // A line of a device is assigned to a single pin.
+                let key = (pin.device.to_string(), line);
+                if let Some(other) = lines.insert(key, pin.ident.clone()) {
+                    error(
+                        &mut errors,
+                        &pin.line,
+                        format!(
+                            "line {line} of `{}` is already assigned to `{other}`",
+                            pin.device
+                        ),
+                    );
+                    continue;
+                }
+
+                let (ident, device_ident, ty, path) =
+                    (&pin.ident, &device.ident, &device.ty, &device.path);
+                let doc = format!("The pin on line {line} of [`{device_ident}`].");
+                content.push(quote! {
+                    #[doc = #doc]
+                    pub const #ident: ::dedrv::board::Pin<<#ty as ::dedrv::DeviceType>::Driver> =
+                        ::dedrv::board::Pin::new(& #device_ident, #line);
+                });
+                pins.push(quote!((stringify!(#ident), #path, #line)));
+            }
+        } else if mac.mac.path.is_ident("aliases") {
+            let list = match mac
+                .mac
+                .parse_body_with(Punctuated::<Alias, Token![,]>::parse_terminated)
+            {
+                Ok(x) => x,
+                Err(e) => {
+                    errors.extend(e.into_compile_error());
+                    continue;
+                }
+            };
+
+            for alias in list {
+                let Some(device) = find(&alias.device, &mut errors) else {
+                    continue;
+                };
+
+                // The alias has the visibility of its device, which it re-exports.
+                let (ident, device_ident, vis, path) =
+                    (&alias.ident, &device.ident, &device.vis, &device.path);
+                content.push(quote!(#vis use self:: #device_ident as #ident;));
+                aliases.push(quote!((stringify!(#ident), #path)));
+            }
+        } else {
+            content.push(quote!(#item));
+        }
+    }
+
+    // The inventory refers to every device instance, so a board that is used by the application
+    // is linked into it as a whole.
+    let name = board.ident.to_string();
+    let paths = devices.iter().map(|x| &x.path);
+    let idents = devices.iter().map(|x| &x.ident);
+
+    let ItemMod {
+        attrs,
+        vis,
+        unsafety,
+        mod_token,
+        ident,
+        ..
+    } = &board;
+
+    quote! {
+        #(#attrs)*
+        #vis #unsafety #mod_token #ident {
+            #(#content)*
+
+            /// The device inventory of the board.
+            pub const BOARD: ::dedrv::board::Board =
+                ::dedrv::board::Board::new(#name, &[#(#paths),*], __dedrv_board_link)
+                    .with_aliases(&[#(#aliases),*])
+                    .with_pins(&[#(#pins),*]);
+
+            // Refer to every device of the board.
+            fn __dedrv_board_link() {
+                ::core::hint::black_box((#(& #idents,)*));
+            }
+        }
+
+        // Compilation errors.
+        #errors
+    }
+}
+
+/// Get the path of a device from its `device` attribute, if any.
+fn device_path(attrs: &[Attribute]) -> Option<String> {
+    let attr = attrs.iter().find(|x| {
+        x.path()
+            .segments
+            .last()
+            .is_some_and(|x| x.ident == "device")
+    })?;
+
+    attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)
+        .ok()?
+        .into_iter()
+        .find_map(|meta| match meta {
+            Meta::NameValue(x) if x.path.is_ident("path") => match x.value {
+                Expr::Lit(ExprLit {
+                    lit: Lit::Str(lit), ..
+                }) => Some(lit.value()),
+                _ => None,
+            },
+            _ => None,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use googletest::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn it_should_declare_board() -> googletest::Result<()> {
+        let code = run(quote! {
+            pub mod nucleo {
+                #[dedrv::device(path = "/soc/usart3", early)]
+                pub static USART3: Device<UartDriver> = Device::new();
+
+                #[device(path = "/soc/gpiob")]
+                pub static GPIOB: Device<GpioDriver> = Device::new();
+
+                pins! {
+                    LED_GREEN = GPIOB[0],
+                }
+
+                aliases! {
+                    CONSOLE = USART3,
+                }
+            }
+        });
+
+        let result = code.to_string();
+        verify_that!(result, not(contains_substring("error")))?;
+
+        verify_that!(
+            result,
+            contains_substring(
+                quote!(
+                    pub use self::USART3 as CONSOLE;
+                )
+                .to_string()
+            )
+        )?;
+
+        verify_that!(
+            result,
+            contains_substring(quote!(::dedrv::board::Pin::new(&GPIOB, 0u32)).to_string())
+        )?;
+
+        verify_that!(
+            result,
+            contains_substring(
+                quote!(Board::new(
+                    "nucleo",
+                    &["/soc/usart3", "/soc/gpiob"],
+                    __dedrv_board_link
+                ))
+                .to_string()
+            )
+        )?;
+
+        verify_that!(
+            result,
+            contains_substring(
+                quote!(.with_aliases(&[(stringify!(CONSOLE), "/soc/usart3")])).to_string()
+            )
+        )
+    }
+
+    #[test]
+    fn it_should_check_pins_and_aliases() -> googletest::Result<()> {
+        let code = run(quote! {
+            mod board {
+                #[device(path = "/gpio0")]
+                static GPIO0: Device<GpioDriver> = Device::new();
+
+                pins! {
+                    LED = GPIO0[1],
+                    BUTTON = GPIO0[1],
+                    BUZZER = GPIO1[0],
+                }
+
+                aliases! {
+                    CONSOLE = UART0,
+                }
+            }
+        });
+
+        let result = code.to_string();
+        verify_that!(
+            result,
+            contains_substring("line 1 of `GPIO0` is already assigned to `LED`")
+        )?;
+        verify_that!(
+            result,
+            contains_substring("unknown device `GPIO1` on the board")
+        )?;
+        verify_that!(
+            result,
+            contains_substring("unknown device `UART0` on the board")
+        )
+    }
+}
@@ -1,26 +1,99 @@
-use proc_macro2::TokenStream;
+use darling::export::NestedMeta;
+use darling::util::SpannedValue;
+use darling::FromMeta;
+use proc_macro2::{Ident, Span, TokenStream};
+
+use quote::{format_ident, quote, ToTokens};
+use syn::punctuated::Punctuated;
+use syn::visit::{self, Visit};
+use syn::visit_mut::{self, VisitMut};
+use syn::{
+    parse_quote, Attribute, FnArg, GenericArgument, GenericParam, Generics, ItemTrait, Lifetime,
+    Pat, PathArguments, PathSegment, ReturnType, Token, TraitItem, TraitItemFn, Type,
+    TypeReference, WherePredicate,
+};
+
+use crate::helpers::{pascal_case, snake_case, token_stream_with_error};
+
+#[derive(Debug, Default, FromMeta)]
+struct Args {
+    #[darling(default)]
+    driver_mod: Option<SpannedValue<String>>,
+
+    #[darling(default)]
+    tag: Option<SpannedValue<String>>,
+
+    #[darling(default)]
+    checked: bool,
+}
+
+/// The names of the items that are generated for a device class.
+struct Names {
+    /// The identifier of the driver module (i.e. `driver` by default).
+    driver_mod: Ident,
+
+    /// The identifier of the tag type, which is the one of the class by default.
+    tag: Ident,
+
+    /// Whether the tag type is nested into a `tag` module (i.e. it has not been renamed).
+    nested_tag: bool,
 
-use quote::quote;
-use syn::{FnArg, ItemTrait, Pat, TraitItem, TraitItemFn};
+    /// The class identifier.
+    class: Ident,
+
+    /// The generics of the class trait (e.g. `<Word: Copy>` for `Dac<Word>`), which are carried
+    /// through the driver trait, the tag and the accessor implementation.
+    generics: Generics,
+}
+
+impl Names {
+    /// The path to the tag type from the class site, with the class generic arguments.
+    fn tag_path(&self) -> TokenStream {
+        let tag = &self.tag;
+        let (_, args, _) = self.generics.split_for_impl();
+        if self.nested_tag {
+            quote!(tag:: #tag #args)
+        } else {
+            quote!(#tag #args)
+        }
+    }
 
-use crate::helpers::{error, token_stream_with_error};
+    /// The driver trait of the class, with the class generic arguments (e.g. `driver::Dac<Word>`).
+    fn driver_path(&self) -> TokenStream {
+        let driver_mod = &self.driver_mod;
+        let class = &self.class;
+        let (_, args, _) = self.generics.split_for_impl();
+        quote!(#driver_mod :: #class #args)
+    }
 
-pub type Result<T, E = Error> = ::core::result::Result<T, E>;
+    /// The generics of an implementation over the drivers `D` of the class, which follow the
+    /// lifetimes of the class generics.
+    fn driver_generics(&self) -> Generics {
+        let mut generics = self.generics.clone();
+        let driver = self.driver_path();
+        let at = generics.lifetimes().count();
+        generics.params.insert(at, parse_quote!(D: #driver));
+        generics
+    }
+}
 
 #[derive(Debug, Default, PartialEq, thiserror::Error)]
 #[non_exhaustive]
 pub enum Error {
-    #[error("class trait must not be generic")]
-    InvalidClassGenerics,
+    #[error("the `D` generic parameter of a class trait is reserved for the driver")]
+    ReservedClassGeneric,
 
-    #[error("class trait must not have a where clause")]
-    InvalidClassWhereClause,
+    #[error("a device class has at most 32 capabilities")]
+    TooManyCapabilities,
 
     #[error("class method must have a self receiver")]
     MissingReceiver,
 
-    #[error("class method must not be async")]
-    AsyncNotSupported,
+    #[error("a transfer method must take `&mut self`, then its buffer as last argument")]
+    InvalidTransfer,
+
+    #[error("a transfer method requires the `dma` feature of dedrv")]
+    DmaDisabled,
 
     #[default]
     #[error("undefined error")]
@@ -30,25 +103,49 @@ pub enum Error {
 pub fn run(args: TokenStream, item: TokenStream) -> TokenStream {
     let mut errors = TokenStream::new();
 
-    if !args.is_empty() {
-        error(&mut errors, &args, "no attribute options supported");
-    }
-
     let t: ItemTrait = match syn::parse2(item.clone()) {
         Ok(x) => x,
         Err(e) => return token_stream_with_error(item, e),
     };
 
-    let driver = match class_driver_quote(&t) {
+    // Parse the macro arguments.
+    let args = match NestedMeta::parse_meta_list(args.clone()) {
+        Ok(x) => x,
+        Err(e) => return token_stream_with_error(args, e),
+    };
+
+    let args = match Args::from_list(&args) {
+        Ok(x) => x,
+        Err(e) => {
+            errors.extend(e.write_errors());
+            Args::default()
+        }
+    };
+
+    let checked = args.checked;
+    let names = class_names(&t, args, &mut errors);
+
+    let capabilities = match class_capabilities(&t) {
+        Ok(x) => x,
+        Err(e) => {
+            errors.extend(e.into_compile_error());
+            Vec::new()
+        }
+    };
+
+    let driver = match class_driver_quote(&t, &names, &capabilities) {
         Ok(d) => d,
         Err(e) => {
-            error(&mut errors, &t, e);
+            errors.extend(e.into_compile_error());
             quote!()
         }
     };
 
-    let tag = class_tag_quote(&t);
-    let impls = class_accessor_impl_quote(&t);
+    let tag = class_tag_quote(&t, &names);
+    let impls = class_accessor_impl_quote(&t, &names, checked);
+    let ext = class_device_ext_quote(&t, &names);
+    let capability = class_capability_quote(&t, &names, &capabilities);
+    let item = class_trait_quote(&t);
 
     quote! {
         // The original device class trait.
@@ -63,12 +160,86 @@ pub fn run(args: TokenStream, item: TokenStream) -> TokenStream {
         // The device accessor implementation for device class trait.
         #impls
 
+        // The device extension for getting a typed accessor.
+        #ext
+
+        // The optional capabilities of the device class.
+        #capability
+
         // The errors returned by the present macro.
         #errors
     }
 }
 
-fn class_driver_quote(t: &ItemTrait) -> Result<TokenStream> {
+/// Get the names of the generated items, which may be renamed by the macro arguments.
+fn class_names(t: &ItemTrait, args: Args, errors: &mut TokenStream) -> Names {
+    // An invalid name points at the macro argument, then the default name is used instead, so
+    // the error is not followed by unrelated ones.
+    let mut parse = |name: Option<SpannedValue<String>>| {
+        let name = name?;
+        match syn::parse_str::<Ident>(&name) {
+            Ok(x) => Some(x),
+            Err(_) => {
+                let msg = format!("'{}' is not a valid identifier", *name);
+                errors.extend(syn::Error::new(name.span(), msg).into_compile_error());
+                None
+            }
+        }
+    };
+
+    let driver_mod = parse(args.driver_mod);
+    let tag = parse(args.tag);
+
+    Names {
+        driver_mod: driver_mod.unwrap_or_else(|| format_ident!("driver")),
+        nested_tag: tag.is_none(),
+        tag: tag.unwrap_or_else(|| t.ident.clone()),
+        class: t.ident.clone(),
+        generics: t.generics.clone(),
+    }
+}
+
+/// Get the device class trait, where the typestate transitions are rewritten.
+fn class_trait_quote(t: &ItemTrait) -> TokenStream {
+    let mut t = t.clone();
+
+    // The `capability` attribute only drives the code generation.
+    t.attrs.retain(|x| !is_capability(x));
+
+    // The invalid methods are left out, so they are only reported by the macro.
+    t.items
+        .retain(|x| !matches!(x, TraitItem::Fn(f) if validate_method(f).is_err()));
+
+    // The futures of async methods are not required to be `Send`, since the executors of the
+    // targets are mostly single-threaded.
+    if has_async(&t) {
+        t.attrs.push(parse_quote!(#[allow(async_fn_in_trait)]));
+    }
+
+    for item in t.items.iter_mut() {
+        if let TraitItem::Fn(f) = item {
+            // The `no_lock` attribute only drives the code generation.
+            f.attrs.retain(|x| !is_no_lock(x));
+
+            if let Some((_, out, predicate)) = typestate_transition(f) {
+                f.sig.output = out;
+                f.sig
+                    .generics
+                    .make_where_clause()
+                    .predicates
+                    .push(predicate);
+            }
+        }
+    }
+
+    quote!(#t)
+}
+
+fn class_driver_quote(
+    t: &ItemTrait,
+    names: &Names,
+    capabilities: &[Ident],
+) -> syn::Result<TokenStream> {
     validate_trait(t)?;
 
     let mut errors = TokenStream::new();
@@ -82,13 +253,52 @@ fn class_driver_quote(t: &ItemTrait) -> Result<TokenStream> {
 
     let ident = t.ident.clone();
     let visibility = t.vis.clone();
+    let driver_mod = names.driver_mod.clone();
+    let generics = &t.generics;
+    let r#where = &t.generics.where_clause;
+
+    let allow = if has_async(t) {
+        quote!(#[allow(async_fn_in_trait)])
+    } else {
+        quote!()
+    };
+
+    let mod_doc = format!("The driver side of the `{ident}` device class.");
+    let doc = format!("The device class `{ident}` to be implemented by the drivers.");
+
+    // Requesting an accessor for an unsupported class is reported at the accessor creation site.
+    let message = format!("the driver `{{Self}}` does not implement the `{ident}` device class");
+    let note = format!("implement `{driver_mod}::{ident}` for `{{Self}}`");
+
+    // The drivers advertise the capabilities they support, if the class has any.
+    let capabilities = if capabilities.is_empty() {
+        quote!()
+    } else {
+        let doc = format!(
+            "The capabilities of the driver, as a set of [`{ident}Capability`] bits (e.g. \
+             `{ident}Capability::{}.bit()`). By default, no capability is supported.",
+            pascal_case(&capabilities[0].to_string())
+        );
+
+        quote! {
+            #[doc = #doc]
+            const CAPABILITIES: u32 = 0;
+        }
+    };
+
+    // The drivers of a class with split transactions keep the completion of their transfers.
+    let supertraits = if cfg!(feature = "dma") && fns.iter().any(|f| transfer_buffer(f).is_some()) {
+        quote!(Driver + ::dedrv::dma::Transferred)
+    } else {
+        quote!(Driver)
+    };
 
     let fns: Vec<_> = fns
         .iter()
         .map(|&f| match class_driver_method_quote(f) {
             Ok(m) => m,
             Err(e) => {
-                error(&mut errors, f, e);
+                errors.extend(e.into_compile_error());
                 quote!()
             }
         })
@@ -97,11 +307,21 @@ fn class_driver_quote(t: &ItemTrait) -> Result<TokenStream> {
     Ok(quote! {
         // The driver module for isolating the device class trait from the driver point of view.
         // Then apply the same visibility as for the original device class trait.
-        #visibility mod driver {
+        #[doc = #mod_doc]
+        #visibility mod #driver_mod {
             use ::dedrv::{Device, Driver, StateLock};
             use super::*;
 
-            pub trait #ident : Driver {
+            #[doc = #doc]
+            #[diagnostic::on_unimplemented(
+                message = #message,
+                label = "unsupported device class",
+                note = #note
+            )]
+            #allow
+            pub trait #ident #generics : #supertraits #r#where {
+                #capabilities
+
                 #(#fns)*
             }
         }
@@ -111,22 +331,128 @@ fn class_driver_quote(t: &ItemTrait) -> Result<TokenStream> {
     })
 }
 
-fn class_driver_method_quote(m: &TraitItemFn) -> Result<TokenStream> {
+/// Get the capabilities that are declared by the `capability` attributes of the class trait (e.g.
+/// `#[capability(pull_up, open_drain)]`).
+fn class_capabilities(t: &ItemTrait) -> syn::Result<Vec<Ident>> {
+    let mut capabilities = Vec::new();
+
+    for attr in t.attrs.iter().filter(|x| is_capability(x)) {
+        let idents = attr.parse_args_with(Punctuated::<Ident, Token![,]>::parse_terminated)?;
+
+        if capabilities.len() + idents.len() > u32::BITS as usize {
+            return Err(syn::Error::new_spanned(attr, Error::TooManyCapabilities));
+        }
+
+        capabilities.extend(idents);
+    }
+
+    Ok(capabilities)
+}
+
+/// Get the capability enumeration of the class (e.g. `GpioCapability`), which is mapped to the
+/// capability set of each driver through the class tag.
+fn class_capability_quote(t: &ItemTrait, names: &Names, capabilities: &[Ident]) -> TokenStream {
+    if capabilities.is_empty() {
+        return quote!();
+    }
+
+    let class = &t.ident;
+    let visibility = &t.vis;
+    let ident = format_ident!("{}Capability", class);
+    let tag = names.tag_path();
+    let driver = names.driver_path();
+
+    let variants = capabilities.iter().map(|x| {
+        let doc = format!("The `{x}` capability.");
+        let variant = Ident::new(&pascal_case(&x.to_string()), x.span());
+        quote!(#[doc = #doc] #variant)
+    });
+
+    let doc = format!("The optional capabilities of the [`{class}`] device class.");
+
+    let driver_generics = names.driver_generics();
+    let (impl_generics, _, r#where) = driver_generics.split_for_impl();
+
+    quote! {
+        #[doc = #doc]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        #visibility enum #ident {
+            #(#variants,)*
+        }
+
+        impl #ident {
+            /// Get the bit of the capability in the capability set of a driver.
+            pub const fn bit(self) -> u32 {
+                1 << self as u32
+            }
+        }
+
+        impl ::dedrv::capability::Capability for #ident {
+            fn bit(self) -> u32 {
+                #ident::bit(self)
+            }
+        }
+
+        impl #impl_generics ::dedrv::capability::Capabilities<D> for #tag #r#where {
+            type Capability = #ident;
+            const CAPABILITIES: u32 = <D as #driver>::CAPABILITIES;
+        }
+    }
+}
+
+fn class_driver_method_quote(m: &TraitItemFn) -> syn::Result<TokenStream> {
     validate_method(m)?;
 
     let ident = m.sig.ident.clone();
-    let out = m.sig.output.clone();
+    let mut out = m.sig.output.clone();
+
+    // The driver only performs the mode change of a typestate transition, while the accessor is
+    // retagged by the accessor implementation.
+    if typestate_transition(m).is_some() {
+        out = ReturnType::Default;
+    }
+
+    // The driver only starts a transfer, so it borrows the buffer, while the transfer is handed
+    // to the caller by the accessor implementation.
+    let mut inputs = method_inputs(m);
+    if transfer_buffer(m).is_some() {
+        out = parse_quote!(-> ::dedrv::Result<()>);
+        if let Some((_, ty)) = inputs.last_mut() {
+            *ty = parse_quote!(&mut #ty);
+        }
+    }
 
-    let args: Vec<_> = m.sig.inputs.iter().skip(1).collect();
+    let args: Vec<_> = inputs
+        .into_iter()
+        .map(|(ident, ty)| quote!(#ident: #ty))
+        .collect();
+
+    let mut params = m.sig.generics.params.clone();
+    let r#where = m.sig.generics.where_clause.clone();
 
-    let args = if args.is_empty() {
-        quote!(state: &StateLock<Self>)
+    // In the class trait, the elided lifetimes of the output are bound to the receiver. As the
+    // receiver is replaced by the driver state, these lifetimes are bound to the state instead,
+    // otherwise they would be ambiguous as soon as another argument is a reference (e.g. a buffer).
+    let state = if has_ref_receiver(m) && elides_lifetime(&out) && !has_no_lock(m) {
+        let lifetime = Lifetime::new(STATE_LIFETIME, Span::call_site());
+        ElidedLifetimes(&lifetime).visit_return_type_mut(&mut out);
+        params.insert(0, parse_quote!(#lifetime));
+        quote!(&#lifetime StateLock<Self>)
+    } else if let Some(lifetime) = receiver_lifetime(m) {
+        // A named lifetime of the receiver (e.g. `&'cs self`) is bound to the state in the same way.
+        quote!(&#lifetime StateLock<Self>)
     } else {
-        quote!(state: &StateLock<Self>, #(#args),*)
+        quote!(&StateLock<Self>)
     };
 
-    let params = m.sig.generics.params.clone();
-    let r#where = m.sig.generics.where_clause.clone();
+    // A method without lock never touches the driver state, so it is not given the state.
+    let args = if has_no_lock(m) {
+        quote!(#(#args),*)
+    } else if args.is_empty() {
+        quote!(state: #state)
+    } else {
+        quote!(state: #state, #(#args),*)
+    };
 
     let generics = if params.is_empty() {
         quote!()
@@ -134,23 +460,63 @@ fn class_driver_method_quote(m: &TraitItemFn) -> Result<TokenStream> {
         quote!(< #params >)
     };
 
+    let asyncness = m.sig.asyncness;
+    let docs = m.attrs.iter().filter(|x| x.path().is_ident("doc"));
+
     Ok(quote! {
-        fn #ident #generics (#args) #out #r#where;
+        #(#docs)*
+        #asyncness fn #ident #generics (#args) #out #r#where;
     })
 }
 
-fn class_tag_quote(t: &ItemTrait) -> TokenStream {
-    let ident = t.ident.clone();
+fn class_tag_quote(t: &ItemTrait, names: &Names) -> TokenStream {
+    let ident = names.tag.clone();
     let visibility = t.vis.clone();
 
+    let class = t.ident.clone();
+    let tag = names.tag_path();
+
+    let doc = format!("The tag of the `{class}` device class.");
+    let item = tag_struct_quote(&ident, &t.generics);
+    let decl = if names.nested_tag {
+        quote! {
+            /// The tags of the device classes of this module.
+            pub mod tag {
+                #[doc = #doc]
+                #visibility #item
+            }
+        }
+    } else {
+        quote! {
+            #[doc = #doc]
+            #visibility #item
+        }
+    };
+
+    let driver_generics = names.driver_generics();
+    let (impl_generics, _, r#where) = driver_generics.split_for_impl();
+    let (class_generics, _, class_where) = t.generics.split_for_impl();
+
     quote! {
-        pub mod tag {
-            #visibility struct #ident;
+        #decl
+
+        // Only the drivers of the class may be accessed with the tag.
+        impl #impl_generics ::dedrv::ClassTag<D> for #tag #r#where {}
+
+        // Only the tags of the classes may tag an accessor.
+        impl #class_generics ::dedrv::sealed::Tag for #tag #class_where {}
+
+        // The class is identified at runtime by its fully qualified name, which is shared by every
+        // instantiation of a generic class.
+        impl #class_generics ::dedrv::Class for #tag #class_where {
+            const ID: ::dedrv::ClassId =
+                ::dedrv::ClassId::new(concat!(module_path!(), "::", stringify!(#class)));
+            const NAME: &'static str = stringify!(#class);
         }
     }
 }
 
-fn class_accessor_impl_quote(t: &ItemTrait) -> TokenStream {
+fn class_accessor_impl_quote(t: &ItemTrait, names: &Names, checked: bool) -> TokenStream {
     let mut errors = TokenStream::new();
 
     let fns = t.items.iter().fold(Vec::new(), |mut acc, x| {
@@ -161,65 +527,205 @@ fn class_accessor_impl_quote(t: &ItemTrait) -> TokenStream {
     });
 
     let ident = t.ident.clone();
+    let tag = names.tag_path();
+
+    let driver_generics = names.driver_generics();
+    let (impl_generics, _, r#where) = driver_generics.split_for_impl();
+    let (_, class_args, _) = t.generics.split_for_impl();
+
+    // The class is also implemented for references to an accessor, so generic functions taking
+    // `impl Class` can be handed a borrow of a long-lived accessor. A shared reference can only
+    // forward `&self` methods and no reference can be consumed (e.g. by a typestate transition),
+    // so a reference is only given the classes whose methods it can all forward.
+    let shared = fns.iter().all(|f| has_shared_receiver(f));
+    let borrowed = fns.iter().all(|f| has_ref_receiver(f));
 
     let fns: Vec<_> = fns
         .iter()
-        .map(|&f| match class_accessor_impl_method_quote(f) {
-            Ok(m) => m,
-            Err(e) => {
-                error(&mut errors, f, e);
+        .map(|f| {
+            class_accessor_impl_method_quote(f, checked).unwrap_or_else(|e| {
+                errors.extend(e.into_compile_error());
                 quote!()
+            })
+        })
+        .collect();
+
+    let mut refs = TokenStream::new();
+    if shared {
+        refs.extend(quote! {
+            impl #impl_generics #ident #class_args for &Accessor<'_, D, #tag> #r#where {
+                #(#fns)*
+            }
+        });
+    }
+    if borrowed {
+        refs.extend(quote! {
+            impl #impl_generics #ident #class_args for &mut Accessor<'_, D, #tag> #r#where {
+                #(#fns)*
             }
+        });
+    }
+
+    // An accessor without tag is given the class with an impossible bound, so calling a class
+    // method on it points at the missing tag.
+    let untagged: Vec<_> = t
+        .items
+        .iter()
+        .filter_map(|x| match x {
+            TraitItem::Fn(f) => class_never_method_quote(
+                f,
+                quote!(match <D as ::dedrv::sealed::NeedsTag<#tag>>::never() {}),
+            ),
+            _ => None,
         })
         .collect();
 
+    let mut untagged_where = r#where.cloned();
+    untagged_where
+        .get_or_insert_with(|| parse_quote!(where))
+        .predicates
+        .push(parse_quote!(D: ::dedrv::sealed::NeedsTag<#tag>));
+
     quote! {
-        impl<D: driver:: #ident> #ident for Accessor<'_, D, tag:: #ident> {
+        impl #impl_generics #ident #class_args for Accessor<'_, D, #tag> #r#where {
             #(#fns)*
         }
+
+        #refs
+
+        #[allow(unused_variables)]
+        impl #impl_generics #ident #class_args for Accessor<'_, D, ::dedrv::tag::NoTag>
+            #untagged_where
+        {
+            #(#untagged)*
+        }
+    }
+}
+
+/// Get a class method that may never be called, with the given body (e.g. for the accessors without
+/// tag, see `dedrv::sealed::NeedsTag`).
+fn class_never_method_quote(m: &TraitItemFn, body: TokenStream) -> Option<TokenStream> {
+    validate_method(m).ok()?;
+
+    let ident = &m.sig.ident;
+    let receiver = m.sig.inputs.first();
+    let args = method_inputs(m)
+        .into_iter()
+        .map(|(ident, ty)| quote!(#ident: #ty));
+
+    let generics = &m.sig.generics;
+    let mut out = m.sig.output.clone();
+    let mut r#where = m.sig.generics.where_clause.clone();
+
+    if let Some((_, transition, predicate)) = typestate_transition(m) {
+        out = transition;
+        r#where
+            .get_or_insert_with(|| parse_quote!(where))
+            .predicates
+            .push(predicate);
+    }
+
+    let asyncness = m.sig.asyncness;
+
+    Some(quote! {
+        #asyncness fn #ident #generics (#receiver, #(#args),*) #out #r#where {
+            #body
+        }
+    })
+}
+
+fn class_device_ext_quote(t: &ItemTrait, names: &Names) -> TokenStream {
+    let ident = t.ident.clone();
+    let visibility = t.vis.clone();
+    let driver_mod = names.driver_mod.clone();
+    let tag = names.tag_path();
+
+    let ext = format_ident!("{}Ext", ident);
+    let method = format_ident!("{}", snake_case(&ident.to_string()));
+    let ext_doc = format!("Extension of a device for the [`{ident}`] class.");
+    let doc = format!("Get a new accessor for the [`{ident}`] class from this device.");
+
+    // The generic arguments of a generic class are given to the accessor method instead (e.g.
+    // `device.dac::<u16>()`), since a device may implement many instantiations of the class.
+    if !t.generics.params.is_empty() {
+        let driver = names.driver_path();
+        let generics = &t.generics;
+        let mut r#where = t.generics.where_clause.clone();
+        r#where
+            .get_or_insert_with(|| parse_quote!(where))
+            .predicates
+            .push(parse_quote!(D: #driver));
+
+        return quote! {
+            #[doc = #ext_doc]
+            #visibility trait #ext<D: ::dedrv::Driver> {
+                #[doc = #doc]
+                fn #method #generics (&self) -> ::dedrv::Accessor<'_, D, #tag> #r#where;
+            }
+
+            impl<D: ::dedrv::Driver> #ext<D> for ::dedrv::Device<D> {
+                #[inline(always)]
+                fn #method #generics (&self) -> ::dedrv::Accessor<'_, D, #tag> #r#where {
+                    self.accessor::<#tag>()
+                }
+            }
+        };
+    }
+
+    quote! {
+        #[doc = #ext_doc]
+        #visibility trait #ext<D: #driver_mod :: #ident> {
+            #[doc = #doc]
+            fn #method(&self) -> ::dedrv::Accessor<'_, D, #tag>;
+        }
+
+        impl<D: #driver_mod :: #ident> #ext<D> for ::dedrv::Device<D> {
+            #[inline(always)]
+            fn #method(&self) -> ::dedrv::Accessor<'_, D, #tag> {
+                self.accessor::<#tag>()
+            }
+        }
     }
 }
 
-fn class_accessor_impl_method_quote(m: &TraitItemFn) -> Result<TokenStream> {
+fn class_accessor_impl_method_quote(m: &TraitItemFn, checked: bool) -> syn::Result<TokenStream> {
     validate_method(m)?;
 
     let ident = m.sig.ident.clone();
-    let out = m.sig.output.clone();
+    let mut out = m.sig.output.clone();
 
-    // These are input arguments, which a simple copy from the trait.
-    let args = m.sig.inputs.clone();
+    // These are input arguments, which a simple copy from the trait, except that patterns are
+    // replaced by plain identifiers (e.g. `_` or `(a, b)`).
+    let inputs = method_inputs(m);
+    let receiver = m.sig.inputs.first();
+    let args: Vec<_> = inputs
+        .iter()
+        .map(|(ident, ty)| quote!(#ident: #ty))
+        .collect();
+    let args = quote!(#receiver, #(#args),*);
 
     // Then, these inputs are converted to a list of identifier to pass through the driver
     // implementation.
-    let argv: Vec<_> = m
-        .sig
-        .inputs
-        .iter()
-        // First, skip the function receiver.
-        .skip(1)
-        // Then, map each input argument to its identifier.
-        .map(|x| {
-            if let FnArg::Typed(t) = x {
-                if let Pat::Ident(x) = &*t.pat {
-                    return x.ident.clone();
-                }
-            }
-            // Because we skipped the first receiver argument, others must be typed ones.
-            unreachable!()
-        })
-        .collect();
+    let argv: Vec<_> = inputs.into_iter().map(|(ident, _)| ident).collect();
+    let idents = argv.clone();
 
     // Replace the receiver argument with the driver internal state, which is behind a
     // `Mutex<RefCell<D::StateType>>`. So, thanks to internior mutability of the `RefCell`, we can
     // pass the argument as an immutable reference.
-    let argv = if argv.is_empty() {
+    //
+    // A method without lock is called as is, so it neither enters a critical section nor touches
+    // the `RefCell`.
+    let no_lock = has_no_lock(m);
+    let argv = if no_lock {
+        quote!(#(#argv),*)
+    } else if argv.is_empty() {
         quote!(&self.inner().state)
     } else {
         quote!(&self.inner().state, #(#argv),*)
     };
 
     let params = m.sig.generics.params.clone();
-    let r#where = m.sig.generics.where_clause.clone();
+    let mut r#where = m.sig.generics.where_clause.clone();
 
     let generics = if params.is_empty() {
         quote!()
@@ -227,80 +733,511 @@ fn class_accessor_impl_method_quote(m: &TraitItemFn) -> Result<TokenStream> {
         quote!(< #params >)
     };
 
+    // The type and const parameters are forwarded explicitly, because they may not be inferred
+    // from the arguments (e.g. `fn page<const N: usize>(&self) -> [u8; N]`). Lifetimes are left
+    // out, since they cannot be given explicitly when they are late-bound.
+    let forwarded: Vec<_> = params
+        .iter()
+        .filter_map(|x| match x {
+            GenericParam::Type(t) => Some(t.ident.clone()),
+            GenericParam::Const(c) => Some(c.ident.clone()),
+            GenericParam::Lifetime(_) => None,
+        })
+        .collect();
+
+    let turbofish = if forwarded.is_empty() {
+        quote!()
+    } else {
+        quote!(::< #(#forwarded),* >)
+    };
+
+    // A transfer is started by the driver with a borrow of the buffer, which is owned by the
+    // transfer until its completion. It is neither profiled nor recorded in the error history,
+    // since the driver only starts it.
+    if transfer_buffer(m).is_some() {
+        let (buf, argv) = idents
+            .split_last()
+            .ok_or_else(|| syn::Error::new_spanned(&m.sig, Error::InvalidTransfer))?;
+        let argv = quote!(state, #(#argv,)* #buf);
+
+        return Ok(quote! {
+            fn #ident #generics (#args) #out #r#where {
+                // Start the transfer with the driver implementation of the device class trait.
+                ::dedrv::dma::Transfer::start(self.inner(), #buf, move |state, #buf| {
+                    D:: #ident #turbofish (#argv)
+                })
+            }
+        });
+    }
+
+    // A typestate transition consumes the accessor, then gives it back with the target tag once
+    // the driver has performed the mode change.
+    //
+    // The driver future of an async method is awaited in place, but it is not profiled, since
+    // the duration would include the time spent waiting for other tasks.
+    let asyncness = m.sig.asyncness;
+    let invoke = if asyncness.is_some() {
+        quote!(D:: #ident #turbofish (#argv).await)
+    } else {
+        quote!(D:: #ident #turbofish (#argv))
+    };
+
+    let measured = if asyncness.is_some() {
+        invoke.clone()
+    } else {
+        quote!(::dedrv::profile::measure(self.inner(), || #invoke))
+    };
+
+    // A checked class reports the framework failures (i.e. a device that is not initialized or a
+    // state that is already borrowed) through the error type of the method, which must implement
+    // `From<dedrv::Error>`, instead of letting the driver panic. The check is best-effort, since the
+    // state is released before the driver borrows it again.
+    let measured = if checked && returns_result(&out) {
+        let exclusive = !has_shared_receiver(m);
+        quote! {
+            match ::dedrv::precheck(self.inner(), #exclusive) {
+                Ok(()) => #measured,
+                Err(e) => Err(::core::convert::From::from(e)),
+            }
+        }
+    } else {
+        measured
+    };
+
+    let call = if no_lock {
+        invoke
+    } else if cfg!(feature = "error-history") && returns_result(&out) {
+        // The errors are recorded in the history of the device, if they are framework errors.
+        quote! {{
+            let result = #measured;
+            if let Err(e) = &result {
+                #[allow(unused_imports)]
+                use ::dedrv::history::{FrameworkError as _, OtherError as _};
+
+                let error = (&::dedrv::history::Recorded(e)).error();
+                ::dedrv::history::record(self.inner(), stringify!(#ident), error);
+            }
+            result
+        }}
+    } else {
+        measured
+    };
+
+    let body = match typestate_transition(m) {
+        Some((tag, transition, predicate)) => {
+            out = transition;
+            r#where
+                .get_or_insert_with(|| parse_quote!(where))
+                .predicates
+                .push(predicate);
+
+            quote! {
+                #call;
+                <Self as ::dedrv::Retag<#tag>>::retag(self)
+            }
+        }
+        None => call,
+    };
+
     Ok(quote! {
-        fn #ident #generics (#args) #out #r#where {
+        #asyncness fn #ident #generics (#args) #out #r#where {
             // Call the driver implementation of the device class trait.
-            D:: #ident (#argv)
+            #body
         }
     })
 }
 
-fn validate_trait(t: &ItemTrait) -> Result<()> {
-    if !t.generics.params.is_empty() {
-        return Err(Error::InvalidClassGenerics);
-    }
+/// The name of the lifetime that binds the driver state to the elided lifetimes of the output.
+const STATE_LIFETIME: &str = "'__dedrv_state";
+
+/// Get the typed inputs of a class method, except the receiver.
+///
+/// Each input is given as an identifier, which is the one of the original argument for simple
+/// patterns (without `mut` or `ref` binding modes) or a generated one for other patterns.
+fn method_inputs(m: &TraitItemFn) -> Vec<(Ident, Box<Type>)> {
+    m.sig
+        .inputs
+        .iter()
+        .filter_map(|x| match x {
+            FnArg::Typed(t) => Some(t),
+            FnArg::Receiver(_) => None,
+        })
+        .enumerate()
+        .map(|(n, t)| match &*t.pat {
+            Pat::Ident(x) if x.subpat.is_none() => (x.ident.clone(), t.ty.clone()),
+            _ => (format_ident!("__arg{}", n), t.ty.clone()),
+        })
+        .collect()
+}
 
-    if t.generics.where_clause.is_some() {
-        return Err(Error::InvalidClassWhereClause);
+/// Get the target tag of a typestate transition, i.e. a consuming method that returns an accessor
+/// for another tag (e.g. `fn into_output(self) -> dedrv::Accessor<'d, D, tag::GpioOutput>`).
+///
+/// The accessor is only recognized by its full path, so that another type named `Accessor` is
+/// returned as is.
+fn typestate_tag(m: &TraitItemFn) -> Option<Type> {
+    if !matches!(m.sig.inputs.first(), Some(FnArg::Receiver(r)) if r.reference.is_none()) {
+        return None;
     }
 
-    Ok(())
-}
+    let ReturnType::Type(_, ty) = &m.sig.output else {
+        return None;
+    };
 
-fn validate_method(m: &TraitItemFn) -> Result<()> {
-    let arg = match m.sig.inputs.first() {
-        Some(x) => x,
-        None => return Err(Error::MissingReceiver),
+    let segment = dedrv_type(ty, &["dedrv", "Accessor"])?;
+
+    let PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
     };
 
-    if !matches!(arg, FnArg::Receiver(_)) {
-        return Err(Error::MissingReceiver);
+    match args.args.last()? {
+        GenericArgument::Type(tag) => Some(tag.clone()),
+        _ => None,
     }
+}
 
-    if m.sig.asyncness.is_some() {
-        return Err(Error::AsyncNotSupported);
-    }
+/// Get the output and the where predicate of a typestate transition, which are expressed with the
+/// `Retag` trait because the class trait cannot name the device nor the driver of the accessor.
+fn typestate_transition(m: &TraitItemFn) -> Option<(Type, ReturnType, WherePredicate)> {
+    let tag = typestate_tag(m)?;
 
-    Ok(())
+    Some((
+        tag.clone(),
+        parse_quote!(-> <Self as ::dedrv::Retag<#tag>>::Output),
+        parse_quote!(Self: ::dedrv::Retag<#tag> + Sized),
+    ))
 }
 
-#[cfg(test)]
-mod tests {
-    use googletest::prelude::*;
+/// Get the buffer type of a transfer method, i.e. a method that returns a transfer of its last
+/// argument (e.g. `fn start_read(&mut self, buf: B) -> dedrv::dma::Transfer<'_, B>`).
+///
+/// The transfer is only recognized by its full path, so that another type named `Transfer` (e.g.
+/// the DMA transfer of a HAL) is returned as is.
+fn transfer_buffer(m: &TraitItemFn) -> Option<Type> {
+    let ReturnType::Type(_, ty) = &m.sig.output else {
+        return None;
+    };
 
-    use super::*;
+    let segment = dedrv_type(ty, &["dedrv", "dma", "Transfer"])?;
 
-    #[test]
-    fn it_should_compile_class_trait() -> googletest::Result<()> {
-        let code = run(
-            quote!(),
-            quote! {
-                trait SomeClass {}
-            },
-        );
+    let PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
 
-        assert_that!(code.is_empty(), eq(false));
+    match args.args.last()? {
+        GenericArgument::Type(buf) => Some(buf.clone()),
+        _ => None,
+    }
+}
 
-        let result = code.to_string();
+/// Get the last segment of a type that is the given item of the framework (i.e. its path is the
+/// full path of the item, with or without leading `::`).
+fn dedrv_type<'a>(ty: &'a Type, path: &[&str]) -> Option<&'a PathSegment> {
+    let Type::Path(p) = ty else {
+        return None;
+    };
 
-        verify_that!(result, not(contains_substring("error")))?;
-        verify_that!(result, contains_substring(quote!(mod driver).to_string()))?;
-        verify_that!(
-            result,
-            contains_substring(
-                quote!(
-                    trait SomeClass {}
-                )
-                .to_string()
-            )
-        )?;
+    let matches = p.qself.is_none()
+        && p.path.segments.len() == path.len()
+        && p.path.segments.iter().zip(path).all(|(x, y)| x.ident == y);
 
-        Ok(())
-    }
+    matches.then(|| p.path.segments.last()).flatten()
+}
 
-    #[test]
-    fn it_should_compile_method_with_no_arg() -> googletest::Result<()> {
-        let code = run(
+/// Check whether an attribute is the `no_lock` attribute (i.e. `#[no_lock]` or
+/// `#[dedrv::no_lock]`).
+fn is_no_lock(attr: &Attribute) -> bool {
+    attr.path()
+        .segments
+        .last()
+        .is_some_and(|x| x.ident == "no_lock")
+}
+
+/// Check whether an attribute is the `capability` attribute (i.e. `#[capability(..)]` or
+/// `#[dedrv::capability(..)]`).
+fn is_capability(attr: &Attribute) -> bool {
+    attr.path()
+        .segments
+        .last()
+        .is_some_and(|x| x.ident == "capability")
+}
+
+/// Check whether the method opts out of the driver state (see the `no_lock` attribute).
+fn has_no_lock(m: &TraitItemFn) -> bool {
+    m.attrs.iter().any(is_no_lock)
+}
+
+/// Check whether a class trait has any async method.
+fn has_async(t: &ItemTrait) -> bool {
+    t.items
+        .iter()
+        .any(|x| matches!(x, TraitItem::Fn(f) if f.sig.asyncness.is_some()))
+}
+
+/// The paths of the `Result` types that are recognized in the output of a method, besides the
+/// bare `Result` of the prelude (or of a `use dedrv::Result`).
+const RESULT_PATHS: &[&str] = &[
+    "core::result::Result",
+    "std::result::Result",
+    "dedrv::Result",
+    "dedrv::error::Result",
+];
+
+/// Check whether the output of a method is a `Result` (e.g. `dedrv::Result<u32>`).
+///
+/// Another type named `Result` (e.g. `core::fmt::Result`) is not, since its error type may not
+/// be converted from a `dedrv::Error`.
+fn returns_result(out: &ReturnType) -> bool {
+    match out {
+        ReturnType::Type(_, ty) => match ty.as_ref() {
+            Type::Path(p) if p.qself.is_none() => {
+                let path = p
+                    .path
+                    .segments
+                    .iter()
+                    .map(|x| x.ident.to_string())
+                    .collect::<Vec<_>>()
+                    .join("::");
+
+                path == "Result" || RESULT_PATHS.contains(&path.as_str())
+            }
+            _ => false,
+        },
+        ReturnType::Default => false,
+    }
+}
+
+/// Check whether the receiver of the method is a reference (i.e. `&self` or `&mut self`).
+fn has_ref_receiver(m: &TraitItemFn) -> bool {
+    matches!(m.sig.inputs.first(), Some(FnArg::Receiver(r)) if r.reference.is_some())
+}
+
+/// Get the named lifetime of the receiver of the method, if any (e.g. `'cs` of `&'cs self`).
+fn receiver_lifetime(m: &TraitItemFn) -> Option<&Lifetime> {
+    match m.sig.inputs.first() {
+        Some(FnArg::Receiver(r)) => r.reference.as_ref().and_then(|(_, x)| x.as_ref()),
+        _ => None,
+    }
+}
+
+/// Check whether the receiver of the method is a shared reference (i.e. `&self`).
+fn has_shared_receiver(m: &TraitItemFn) -> bool {
+    matches!(m.sig.inputs.first(), Some(FnArg::Receiver(r)) if r.reference.is_some() && r.mutability.is_none())
+}
+
+/// Check whether the output of a method has elided lifetimes (i.e. `&T` or `'_`).
+fn elides_lifetime(out: &ReturnType) -> bool {
+    struct Visitor(bool);
+
+    impl<'ast> Visit<'ast> for Visitor {
+        fn visit_type_reference(&mut self, r: &'ast TypeReference) {
+            self.0 |= r.lifetime.is_none();
+            visit::visit_type_reference(self, r);
+        }
+
+        fn visit_lifetime(&mut self, l: &'ast Lifetime) {
+            self.0 |= l.ident == "_";
+        }
+    }
+
+    let mut visitor = Visitor(false);
+    visitor.visit_return_type(out);
+    visitor.0
+}
+
+/// Replace the elided lifetimes (i.e. `&T` or `'_`) by the given lifetime.
+struct ElidedLifetimes<'a>(&'a Lifetime);
+
+impl VisitMut for ElidedLifetimes<'_> {
+    fn visit_type_reference_mut(&mut self, r: &mut TypeReference) {
+        if r.lifetime.is_none() {
+            r.lifetime = Some(self.0.clone());
+        }
+        visit_mut::visit_type_reference_mut(self, r);
+    }
+
+    fn visit_lifetime_mut(&mut self, l: &mut Lifetime) {
+        if l.ident == "_" {
+            *l = self.0.clone();
+        }
+    }
+}
+
+/// Check the class trait, where an error points at the offending generic parameter.
+fn validate_trait(t: &ItemTrait) -> syn::Result<()> {
+    if let Some(param) = t.generics.type_params().find(|x| x.ident == "D") {
+        return Err(syn::Error::new_spanned(
+            &param.ident,
+            Error::ReservedClassGeneric,
+        ));
+    }
+
+    Ok(())
+}
+
+/// Get the declaration of a tag type, which is a unit struct for a plain class, or a marker
+/// struct of the class generics for a generic class (e.g. `struct Dac<Word>(PhantomData<..>)`).
+///
+/// The bounds of the class generics are left out, so they are only checked where the tag is
+/// bound to a driver.
+fn tag_struct_quote(ident: &Ident, generics: &Generics) -> TokenStream {
+    if generics.params.is_empty() {
+        return quote!(struct #ident;);
+    }
+
+    let mut generics = generics.clone();
+    generics.where_clause = None;
+
+    let mut markers = Vec::new();
+    for param in generics.params.iter_mut() {
+        match param {
+            GenericParam::Lifetime(l) => {
+                l.colon_token = None;
+                l.bounds.clear();
+                let lifetime = &l.lifetime;
+                markers.push(quote!(&#lifetime ()));
+            }
+            GenericParam::Type(t) => {
+                t.colon_token = None;
+                t.bounds.clear();
+                let ident = &t.ident;
+                markers.push(quote!(#ident));
+            }
+            GenericParam::Const(_) => {}
+        }
+    }
+
+    quote!(struct #ident #generics (::core::marker::PhantomData<fn() -> (#(#markers),*)>);)
+}
+
+/// Check a method of the class trait, where an error points at the offending part of its
+/// signature rather than at the whole method.
+fn validate_method(m: &TraitItemFn) -> syn::Result<()> {
+    let arg = match m.sig.inputs.first() {
+        Some(FnArg::Receiver(x)) => x,
+        Some(x) => return Err(syn::Error::new_spanned(x, Error::MissingReceiver)),
+        None => {
+            return Err(syn::Error::new(
+                m.sig.paren_token.span.join(),
+                Error::MissingReceiver,
+            ))
+        }
+    };
+
+    // The buffer of a transfer is borrowed by the transfer, along with the accessor.
+    if let Some(buf) = transfer_buffer(m) {
+        if !cfg!(feature = "dma") {
+            return Err(syn::Error::new_spanned(&m.sig.output, Error::DmaDisabled));
+        }
+
+        let invalid =
+            |tokens: &dyn ToTokens| syn::Error::new_spanned(tokens, Error::InvalidTransfer);
+
+        if arg.reference.is_none() || arg.mutability.is_none() {
+            return Err(invalid(arg));
+        }
+
+        if let Some(asyncness) = &m.sig.asyncness {
+            return Err(invalid(asyncness));
+        }
+
+        if let Some(attr) = m.attrs.iter().find(|x| is_no_lock(x)) {
+            return Err(invalid(attr));
+        }
+
+        match method_inputs(m).pop() {
+            Some((_, ty)) if *ty == buf => {}
+            Some((_, ty)) => return Err(invalid(&ty)),
+            None => return Err(invalid(&m.sig.output)),
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use googletest::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn it_should_compile_class_trait() -> googletest::Result<()> {
+        let code = run(
+            quote!(),
+            quote! {
+                trait SomeClass {}
+            },
+        );
+
+        assert_that!(code.is_empty(), eq(false));
+
+        let result = code.to_string();
+
+        verify_that!(result, not(contains_substring("error")))?;
+        verify_that!(result, contains_substring(quote!(mod driver).to_string()))?;
+        verify_that!(
+            result,
+            contains_substring(
+                quote!(
+                    trait SomeClass {}
+                )
+                .to_string()
+            )
+        )?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_compile_generic_class_trait() -> googletest::Result<()> {
+        let code = run(
+            quote!(),
+            quote! {
+                trait Dac<'a, Word: Copy> {
+                    fn write(&mut self, words: &'a [Word]);
+                }
+            },
+        );
+
+        let result = code.to_string();
+        verify_that!(result, not(contains_substring("error")))?;
+        verify_that!(
+            result,
+            contains_substring(quote!(pub trait Dac<'a, Word: Copy> : Driver).to_string())
+        )?;
+        verify_that!(
+            result,
+            contains_substring(
+                quote!(
+                    struct Dac<'a, Word>(::core::marker::PhantomData<fn() -> (&'a (), Word)>);
+                )
+                .to_string()
+            )
+        )?;
+        verify_that!(
+            result,
+            contains_substring(
+                quote!(impl<'a, D: driver::Dac<'a, Word>, Word: Copy> Dac<'a, Word> for Accessor<'_, D, tag::Dac<'a, Word> >)
+                    .to_string()
+            )
+        )?;
+
+        let code = run(
+            quote!(),
+            quote! {
+                trait Dac<D> {}
+            },
+        );
+        verify_that!(
+            code.to_string(),
+            contains_substring(Error::ReservedClassGeneric.to_string())
+        )
+    }
+
+    #[test]
+    fn it_should_compile_method_with_no_arg() -> googletest::Result<()> {
+        let code = run(
             quote!(),
             quote! {
                 trait SomeClass {
@@ -443,27 +1380,796 @@ mod tests {
     }
 
     #[test]
-    fn it_should_compile_method_with_one_param_and_clause_and_no_arg() -> googletest::Result<()> {
+    fn it_should_compile_method_with_const_param() -> googletest::Result<()> {
         let code = run(
             quote!(),
             quote! {
                 trait SomeClass {
-                    fn a_method<T>(&self) where T: Default;
+                    fn page<'a, T, const N: usize>(&self, buf: &'a mut [T; N]);
                 }
             },
         );
 
-        assert_that!(code.is_empty(), eq(false));
+        let result = code.to_string();
+
+        verify_that!(result, not(contains_substring("error")))?;
+        verify_that!(
+            result,
+            contains_substring(
+                quote!(fn page<'a, T, const N: usize>(state: &StateLock<Self>, buf: &'a mut [T; N]))
+                    .to_string()
+            )
+        )?;
+        verify_that!(
+            result,
+            contains_substring(quote!(D::page::<T, N>(&self.inner().state, buf)).to_string())
+        )
+    }
+
+    #[test]
+    fn it_should_compile_method_without_lock() -> googletest::Result<()> {
+        let code = run(
+            quote!(),
+            quote! {
+                trait SomeClass {
+                    #[dedrv::no_lock]
+                    fn kick(&self, value: u32);
+                }
+            },
+        );
 
         let result = code.to_string();
 
         verify_that!(result, not(contains_substring("error")))?;
+        verify_that!(result, not(contains_substring("no_lock")))?;
         verify_that!(
             result,
             contains_substring(
-                quote!(fn a_method<T>(state: &StateLock<Self>) where T: Default).to_string()
+                quote!(
+                    fn kick(value: u32);
+                )
+                .to_string()
+            )
+        )?;
+        verify_that!(
+            result,
+            contains_substring(
+                quote!(
+                    fn kick(&self, value: u32) {
+                        D::kick(value)
+                    }
+                )
+                .to_string()
+            )
+        )
+    }
+
+    #[test]
+    fn it_should_compile_async_method() -> googletest::Result<()> {
+        let code = run(
+            quote!(),
+            quote! {
+                trait SomeClass {
+                    async fn wait(&mut self, value: u32) -> u32;
+                }
+            },
+        );
+
+        let result = code.to_string();
+
+        verify_that!(result, not(contains_substring("error")))?;
+        verify_that!(
+            result,
+            contains_substring(quote!(#[allow(async_fn_in_trait)]).to_string())
+        )?;
+        verify_that!(
+            result,
+            contains_substring(
+                quote!(
+                    async fn wait(state: &StateLock<Self>, value: u32) -> u32;
+                )
+                .to_string()
+            )
+        )?;
+        verify_that!(
+            result,
+            contains_substring(
+                quote!(
+                    async fn wait(&mut self, value: u32) -> u32 {
+                        D::wait(&self.inner().state, value).await
+                    }
+                )
+                .to_string()
+            )
+        )
+    }
+
+    #[test]
+    fn it_should_leave_out_invalid_methods() -> googletest::Result<()> {
+        let code = run(
+            quote!(),
+            quote! {
+                trait Gpio {
+                    fn get_value(&self) -> u32;
+                    fn set_value(value: u32);
+                }
+            },
+        );
+
+        let result = code.to_string();
+        verify_that!(
+            result,
+            contains_substring(Error::MissingReceiver.to_string())
+        )?;
+        verify_that!(
+            result,
+            contains_substring(
+                quote!(
+                    fn get_value(&self) -> u32;
+                )
+                .to_string()
             )
         )?;
+        verify_that!(
+            result,
+            not(contains_substring(quote!(fn set_value).to_string()))
+        )
+    }
+
+    #[test]
+    #[cfg(feature = "error-history")]
+    fn it_should_record_method_errors() -> googletest::Result<()> {
+        let code = run(
+            quote!(),
+            quote! {
+                trait SomeClass {
+                    fn sample(&self) -> dedrv::Result<u32>;
+                }
+            },
+        );
+
+        let result = code.to_string();
+
+        verify_that!(result, not(contains_substring("compile_error")))?;
+        verify_that!(
+            result,
+            contains_substring(
+                quote!(::dedrv::history::record(self.inner(), stringify!(sample), error);)
+                    .to_string()
+            )
+        )
+    }
+
+    #[test]
+    #[cfg(not(feature = "error-history"))]
+    fn it_should_not_record_method_errors_without_history() -> googletest::Result<()> {
+        let code = run(
+            quote!(),
+            quote! {
+                trait SomeClass {
+                    fn sample(&self) -> dedrv::Result<u32>;
+                }
+            },
+        );
+
+        verify_that!(code.to_string(), not(contains_substring("history")))
+    }
+
+    #[test]
+    fn it_should_check_framework_failures() -> googletest::Result<()> {
+        let code = run(
+            quote!(checked),
+            quote! {
+                trait SomeClass {
+                    fn sample(&self) -> Result<u32, SomeError>;
+                    fn reset(&mut self) -> dedrv::Result<()>;
+                    fn toggle(&mut self) -> bool;
+                    fn describe(&self) -> core::fmt::Result;
+                }
+            },
+        );
+
+        let result = code.to_string();
+
+        verify_that!(result, not(contains_substring("compile_error")))?;
+        verify_that!(
+            result,
+            contains_substring(quote!(::dedrv::precheck(self.inner(), false)).to_string())
+        )?;
+        verify_that!(
+            result,
+            contains_substring(quote!(::dedrv::precheck(self.inner(), true)).to_string())
+        )?;
+        verify_that!(
+            result
+                .matches(&quote!(::dedrv::precheck).to_string())
+                .count(),
+            eq(2 * 2)
+        )
+    }
+
+    #[test]
+    fn it_should_declare_capabilities() -> googletest::Result<()> {
+        let code = run(
+            quote!(),
+            quote! {
+                #[capability(pull_up, open_drain)]
+                trait Gpio {
+                    fn set(&mut self, high: bool);
+                }
+            },
+        );
+
+        let result = code.to_string();
+
+        verify_that!(result, not(contains_substring("error")))?;
+        verify_that!(result, not(contains_substring("# [capability")))?;
+        verify_that!(
+            result,
+            contains_substring(
+                quote!(
+                    #[doc = "The `open_drain` capability."]
+                    OpenDrain,
+                )
+                .to_string()
+            )
+        )?;
+        verify_that!(
+            result,
+            contains_substring(
+                quote!(
+                    const CAPABILITIES: u32 = 0;
+                )
+                .to_string()
+            )
+        )
+    }
+
+    #[test]
+    fn it_should_compile_method_with_one_param_and_clause_and_no_arg() -> googletest::Result<()> {
+        let code = run(
+            quote!(),
+            quote! {
+                trait SomeClass {
+                    fn a_method<T>(&self) where T: Default;
+                }
+            },
+        );
+
+        assert_that!(code.is_empty(), eq(false));
+
+        let result = code.to_string();
+
+        verify_that!(result, not(contains_substring("error")))?;
+        verify_that!(
+            result,
+            contains_substring(
+                quote!(fn a_method<T>(state: &StateLock<Self>) where T: Default).to_string()
+            )
+        )?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_compile_method_with_buffer_arg() -> googletest::Result<()> {
+        let code = run(
+            quote!(),
+            quote! {
+                trait SomeClass {
+                    fn read(&self, buf: &mut [u8]) -> usize;
+                }
+            },
+        );
+
+        assert_that!(code.is_empty(), eq(false));
+
+        let result = code.to_string();
+
+        verify_that!(result, not(contains_substring("error")))?;
+        verify_that!(
+            result,
+            contains_substring(
+                quote!(fn read(state: &StateLock<Self>, buf: &mut [u8]) -> usize).to_string()
+            )
+        )?;
+        verify_that!(
+            result,
+            contains_substring(quote!(fn read(&self, buf: &mut [u8]) -> usize).to_string())
+        )?;
+        verify_that!(
+            result,
+            contains_substring(quote!(D::read(&self.inner().state, buf)).to_string())
+        )?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_compile_method_with_buffer_arg_and_borrowed_output() -> googletest::Result<()> {
+        let code = run(
+            quote!(),
+            quote! {
+                trait SomeClass {
+                    fn split<'a>(&self, buf: &'a [u8]) -> (&[u8], &'a [u8]);
+                }
+            },
+        );
+
+        assert_that!(code.is_empty(), eq(false));
+
+        let result = code.to_string();
+
+        verify_that!(result, not(contains_substring("error")))?;
+        verify_that!(
+            result,
+            contains_substring(
+                quote!(
+                    fn split<'__dedrv_state, 'a>(
+                        state: &'__dedrv_state StateLock<Self>,
+                        buf: &'a [u8]
+                    ) -> (&'__dedrv_state [u8], &'a [u8])
+                )
+                .to_string()
+            )
+        )?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_bind_receiver_lifetime_to_state() -> googletest::Result<()> {
+        let code = run(
+            quote!(),
+            quote! {
+                trait SomeClass {
+                    fn buffer<'cs>(&'cs self, cs: CriticalSection<'cs>) -> StateGuard<'cs, [u8]>;
+                }
+            },
+        );
+
+        let result = code.to_string();
+
+        verify_that!(result, not(contains_substring("error")))?;
+        verify_that!(
+            result,
+            contains_substring(
+                quote! {
+                    fn buffer<'cs>(state: &'cs StateLock<Self>, cs: CriticalSection<'cs>)
+                        -> StateGuard<'cs, [u8]>;
+                }
+                .to_string()
+            )
+        )
+    }
+
+    #[test]
+    fn it_should_compile_method_with_pattern_args() -> googletest::Result<()> {
+        let code = run(
+            quote!(),
+            quote! {
+                trait SomeClass {
+                    fn write(&mut self, mut buf: &[u8], _: u32);
+                }
+            },
+        );
+
+        assert_that!(code.is_empty(), eq(false));
+
+        let result = code.to_string();
+
+        verify_that!(result, not(contains_substring("error")))?;
+        verify_that!(
+            result,
+            contains_substring(
+                quote!(fn write(state: &StateLock<Self>, buf: &[u8], __arg1: u32)).to_string()
+            )
+        )?;
+        verify_that!(
+            result,
+            contains_substring(quote!(D::write(&self.inner().state, buf, __arg1)).to_string())
+        )?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_rename_driver_module() -> googletest::Result<()> {
+        let code = run(
+            quote!(driver_mod = "gpio_driver"),
+            quote! {
+                trait SomeClass {}
+            },
+        );
+
+        let result = code.to_string();
+
+        verify_that!(result, not(contains_substring("error")))?;
+        verify_that!(
+            result,
+            contains_substring(quote!(mod gpio_driver).to_string())
+        )?;
+        verify_that!(
+            result,
+            contains_substring(
+                quote!(impl<D: gpio_driver::SomeClass> SomeClass for Accessor<'_, D, tag::SomeClass>)
+                    .to_string()
+            )
+        )?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_rename_tag() -> googletest::Result<()> {
+        let code = run(
+            quote!(tag = "SomeTag"),
+            quote! {
+                pub trait SomeClass {}
+            },
+        );
+
+        let result = code.to_string();
+
+        verify_that!(result, not(contains_substring("error")))?;
+        verify_that!(result, not(contains_substring(quote!(mod tag).to_string())))?;
+        verify_that!(
+            result,
+            contains_substring(
+                quote!(
+                    pub struct SomeTag;
+                )
+                .to_string()
+            )
+        )?;
+        verify_that!(
+            result,
+            contains_substring(
+                quote!(impl<D: driver::SomeClass> SomeClass for Accessor<'_, D, SomeTag>)
+                    .to_string()
+            )
+        )?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_reject_invalid_names() -> googletest::Result<()> {
+        let code = run(
+            quote!(driver_mod = "not an ident"),
+            quote! {
+                trait SomeClass {}
+            },
+        );
+
+        verify_that!(
+            code.to_string(),
+            contains_substring("'not an ident' is not a valid identifier")
+        )
+    }
+
+    #[test]
+    fn it_should_generate_device_extension() -> googletest::Result<()> {
+        let code = run(
+            quote!(),
+            quote! {
+                pub trait SpiBus {}
+            },
+        );
+
+        let result = code.to_string();
+
+        verify_that!(result, not(contains_substring("error")))?;
+        verify_that!(
+            result,
+            contains_substring(quote!(pub trait SpiBusExt<D: driver::SpiBus>).to_string())
+        )?;
+        verify_that!(
+            result,
+            contains_substring(
+                quote!(fn spi_bus(&self) -> ::dedrv::Accessor<'_, D, tag::SpiBus>).to_string()
+            )
+        )?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_bind_tag_to_class_drivers() -> googletest::Result<()> {
+        let code = run(
+            quote!(),
+            quote! {
+                trait SomeClass {}
+            },
+        );
+
+        verify_that!(
+            code.to_string(),
+            contains_substring(
+                quote!(
+                    impl<D: driver::SomeClass> ::dedrv::ClassTag<D> for tag::SomeClass {}
+                )
+                .to_string()
+            )
+        )?;
+
+        verify_that!(
+            code.to_string(),
+            contains_substring(quote!(impl ::dedrv::Class for tag::SomeClass).to_string())
+        )?;
+
+        verify_that!(
+            code.to_string(),
+            contains_substring(quote!(impl ::dedrv::sealed::Tag for tag::SomeClass {}).to_string())
+        )
+    }
+
+    #[test]
+    fn it_should_report_missing_tag() -> googletest::Result<()> {
+        let code = run(
+            quote!(),
+            quote! {
+                trait SomeClass {
+                    fn a_method(&self, (a, b): (u8, u8)) -> u8;
+                }
+            },
+        );
+
+        let result = code.to_string();
+        verify_that!(result, not(contains_substring("error")))?;
+        verify_that!(
+            result,
+            all![
+                contains_substring(
+                    quote!(for Accessor<'_, D, ::dedrv::tag::NoTag>
+                        where D: ::dedrv::sealed::NeedsTag<tag::SomeClass>)
+                    .to_string()
+                ),
+                contains_substring(
+                    quote!(
+                        fn a_method(&self, __arg0: (u8, u8)) -> u8 {
+                            match <D as ::dedrv::sealed::NeedsTag<tag::SomeClass>>::never() {}
+                        }
+                    )
+                    .to_string()
+                )
+            ]
+        )
+    }
+
+    #[test]
+    fn it_should_compile_typestate_transition() -> googletest::Result<()> {
+        let code = run(
+            quote!(),
+            quote! {
+                trait SomeClass {
+                    fn into_other(self) -> dedrv::Accessor<'d, D, tag::OtherClass>;
+                }
+            },
+        );
+
+        let result = code.to_string();
+
+        verify_that!(result, not(contains_substring("error")))?;
+
+        // The spacing of the tokens depends on whether they are generated or parsed.
+        let compact = result.replace(' ', "");
+        verify_that!(
+            compact,
+            contains_substring(
+                quote!(
+                    fn into_other(self) -> <Self as ::dedrv::Retag<tag::OtherClass>>::Output
+                    where
+                        Self: ::dedrv::Retag<tag::OtherClass> + Sized;
+                )
+                .to_string()
+                .replace(' ', "")
+            )
+        )?;
+        verify_that!(
+            result,
+            contains_substring(
+                quote!(
+                    fn into_other(state: &StateLock<Self>);
+                )
+                .to_string()
+            )
+        )?;
+        verify_that!(
+            result,
+            contains_substring(
+                quote!(<Self as ::dedrv::Retag<tag::OtherClass>>::retag(self)).to_string()
+            )
+        )?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_not_compile_foreign_accessor_as_typestate_transition() -> googletest::Result<()> {
+        let code = run(
+            quote!(),
+            quote! {
+                trait SomeClass {
+                    fn into_raw(self) -> hal::Accessor<'static, u8>;
+                }
+            },
+        );
+
+        let result = code.to_string();
+        verify_that!(result, not(contains_substring("error")))?;
+        verify_that!(
+            result,
+            all![
+                not(contains_substring("Retag")),
+                contains_substring(
+                    quote!(
+                        fn into_raw(state: &StateLock<Self>) -> hal::Accessor<'static, u8>;
+                    )
+                    .to_string()
+                )
+            ]
+        )
+    }
+
+    #[test]
+    fn it_should_implement_class_for_accessor_references() -> googletest::Result<()> {
+        let code = run(
+            quote!(),
+            quote! {
+                trait SomeClass {
+                    fn a_method(&self);
+                }
+            },
+        );
+
+        let result = code.to_string();
+
+        verify_that!(result, not(contains_substring("error")))?;
+        verify_that!(
+            result,
+            contains_substring(
+                quote!(impl<D: driver::SomeClass> SomeClass for &Accessor<'_, D, tag::SomeClass>)
+                    .to_string()
+            )
+        )?;
+        verify_that!(
+            result,
+            contains_substring(
+                quote!(impl<D: driver::SomeClass> SomeClass for &mut Accessor<'_, D, tag::SomeClass>)
+                    .to_string()
+            )
+        )?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_only_implement_shared_class_for_shared_references() -> googletest::Result<()> {
+        let code = run(
+            quote!(),
+            quote! {
+                trait SomeClass {
+                    fn is_set(&self) -> bool;
+                    fn set(&mut self);
+                }
+            },
+        );
+
+        let result = code.to_string();
+        verify_that!(
+            result,
+            not(contains_substring(
+                quote!(SomeClass for &Accessor).to_string()
+            ))
+        )?;
+        verify_that!(
+            result,
+            contains_substring(quote!(SomeClass for &mut Accessor).to_string())
+        )
+    }
+
+    #[test]
+    fn it_should_not_implement_consuming_class_for_accessor_references() -> googletest::Result<()> {
+        let code = run(
+            quote!(),
+            quote! {
+                trait SomeClass {
+                    fn is_set(&self) -> bool;
+                    fn release(self);
+                }
+            },
+        );
+
+        let result = code.to_string();
+        verify_that!(
+            result,
+            not(contains_substring(
+                quote!(SomeClass for &Accessor).to_string()
+            ))
+        )?;
+        verify_that!(
+            result,
+            not(contains_substring(
+                quote!(SomeClass for &mut Accessor).to_string()
+            ))
+        )
+    }
+
+    #[test]
+    #[cfg(feature = "dma")]
+    fn it_should_compile_transfer_method() -> googletest::Result<()> {
+        let code = run(
+            quote!(),
+            quote! {
+                trait SomeClass {
+                    fn start_read(&mut self, addr: u8, buf: Buf) -> dedrv::dma::Transfer<'_, Buf>;
+                }
+            },
+        );
+
+        let result = code.to_string();
+        verify_that!(result, not(contains_substring("error")))?;
+        verify_that!(
+            result,
+            all![
+                contains_substring(quote!(: Driver + ::dedrv::dma::Transferred).to_string()),
+                contains_substring(quote!(addr: u8, buf: &mut Buf).to_string()),
+                contains_substring(quote!(-> ::dedrv::Result<()>;).to_string()),
+                contains_substring(
+                    quote!(::dedrv::dma::Transfer::start(
+                        self.inner(),
+                        buf,
+                        move |state, buf| { D::start_read(state, addr, buf) }
+                    ))
+                    .to_string()
+                )
+            ]
+        )
+    }
+
+    #[test]
+    fn it_should_not_compile_foreign_transfer_as_transfer_method() -> googletest::Result<()> {
+        let code = run(
+            quote!(),
+            quote! {
+                trait SomeClass {
+                    fn start_read(&mut self, buf: Buf) -> hal::dma::Transfer<'static, Buf>;
+                }
+            },
+        );
+
+        let result = code.to_string();
+        verify_that!(result, not(contains_substring("error")))?;
+        verify_that!(
+            result,
+            all![
+                not(contains_substring("Transferred")),
+                contains_substring(
+                    quote! {
+                        fn start_read(state: &StateLock<Self>, buf: Buf)
+                            -> hal::dma::Transfer<'static, Buf>;
+                    }
+                    .to_string()
+                )
+            ]
+        )
+    }
+
+    #[test]
+    #[cfg(feature = "dma")]
+    fn it_should_reject_invalid_transfer_method() -> googletest::Result<()> {
+        for method in [
+            quote!(
+                fn start_read(&self, buf: Buf) -> ::dedrv::dma::Transfer<'_, Buf>;
+            ),
+            quote!(
+                fn start_read(&mut self, buf: Buf, len: usize) -> dedrv::dma::Transfer<'_, Buf>;
+            ),
+        ] {
+            let code = run(quote!(), quote!(trait SomeClass { #method }));
+            verify_that!(code.to_string(), contains_substring("transfer method"))?;
+        }
 
         Ok(())
     }
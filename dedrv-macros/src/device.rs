@@ -1,15 +1,47 @@
 use std::fmt::Debug;
 
 use darling::export::NestedMeta;
+use darling::util::{PathList, SpannedValue};
 use darling::FromMeta;
-use proc_macro2::TokenStream;
+use dedrv_path::PathError;
+use proc_macro2::{Span, TokenStream};
 use quote::{format_ident, quote};
-use syn::ItemStatic;
+use syn::{parse_quote, Expr, ItemStatic, LitByteStr, LitStr};
 
 #[derive(Debug, Default, FromMeta)]
 struct Args {
     #[darling(default)]
-    path: Option<String>,
+    path: Option<LitStr>,
+
+    #[darling(default)]
+    early: bool,
+
+    #[darling(default)]
+    registry: Option<LitStr>,
+
+    #[darling(default)]
+    classes: PathList,
+
+    #[darling(default)]
+    depends_on: PathList,
+
+    #[darling(default)]
+    stage: u8,
+
+    #[darling(default)]
+    flags: PathList,
+
+    #[darling(default)]
+    singleton: bool,
+
+    #[darling(default)]
+    weak: bool,
+
+    #[darling(default)]
+    irq: Option<Expr>,
+
+    #[darling(default)]
+    irq_priority: Option<SpannedValue<u8>>,
 }
 
 use crate::helpers::{error, token_stream_with_error};
@@ -28,7 +60,11 @@ pub fn run(args: TokenStream, item: TokenStream) -> TokenStream {
 
     // Ensure that the variable name is uppercase, as required by Rust for static variables.
     if ident != ident.to_string().to_uppercase() {
-        error(&mut errors, &item, "device variable name must be uppercase");
+        error(
+            &mut errors,
+            &ident,
+            "device variable name must be uppercase",
+        );
     }
 
     // Parse the macro arguments.
@@ -45,44 +81,302 @@ pub fn run(args: TokenStream, item: TokenStream) -> TokenStream {
         }
     };
 
-    if args.path.is_none() {
-        error(&mut errors, &item, "missing device path on instance");
-    }
-
-    // Extract the path from arguments. In case of error, the path is "undefined".
-    let path = args.path.unwrap_or_default();
+    // Without a valid path, the device is not registered at all, so that the only reported errors
+    // are the ones of the macro.
+    let path = match &args.path {
+        Some(lit) => match check_path(&lit.value(), &Scheme::from_env()) {
+            Ok(()) => lit.value(),
+            Err(e) => {
+                error(&mut errors, lit, e);
+                return quote!(#item #errors);
+            }
+        },
+        None => {
+            // The call site of the attribute is the attribute itself.
+            let e = syn::Error::new(Span::call_site(), "missing device path on instance");
+            errors.extend(e.into_compile_error());
+            return quote!(#item #errors);
+        }
+    };
 
     let desc_mod_ident = format_ident!("__dedrv_desc_{}", ident.to_string().to_lowercase());
-    let desc_sname = format!(".dedrv.device.{}", ident.to_string().to_lowercase());
+
+    // Early devices (e.g. a console) are stored in their own table, which is initialized first.
+    //
+    // Other registries are stored in their own sections, which are gathered by the linker script
+    // of the application.
+    let table = match &args.registry {
+        Some(lit) if args.early => {
+            error(
+                &mut errors,
+                lit,
+                "an early device cannot be stored in another registry",
+            );
+            return quote!(#item #errors);
+        }
+        Some(lit) => match check_registry(&lit.value()) {
+            Ok(()) => lit.value(),
+            Err(e) => {
+                error(&mut errors, lit, e);
+                return quote!(#item #errors);
+            }
+        },
+        None if args.early => "early".into(),
+        None => "device".into(),
+    };
+
+    // The descriptor section is keyed by the hex-encoded path, which preserves the byte order of
+    // the paths. Then, the linker script sorts the sections by name, so the descriptor table is
+    // sorted by path and may be binary searched.
+    //
+    // A weak default device (e.g. of a board support package) is stored after any other device
+    // at the same path, which overrides it at runtime.
+    let weak = if args.weak { ".weak" } else { "" };
+    let desc_sname = format!(".dedrv.{}.{}{}", table, hex(&path), weak);
     let desc_ident = format_ident!("__DEDRV_DESC_{}", ident);
 
+    // The metadata record is read back by the host-side build support (i.e. `dedrv-build`) for
+    // validating the registry and generating the device map. Its layout is a sequence of
+    // NUL-terminated strings: version, path, device, driver and section.
+    let meta = format!(
+        "1\0{}\0{}\0{}\0{}\0",
+        path,
+        ident,
+        quote!(#ty).to_string().replace(' ', ""),
+        desc_sname
+    );
+    let meta_len = meta.len();
+    let meta = LitByteStr::new(meta.as_bytes(), Span::call_site());
+    let meta_sname = format!(".dedrv.meta.{}", ident.to_string().to_lowercase());
+    let meta_ident = format_ident!("__DEDRV_META_{}", ident);
+
+    // The classes of the device are recorded by their identifier, which also checks at compile
+    // time that the driver implements them.
+    let classes = args.classes.iter();
+    let classes_len = args.classes.len();
+    let classes_ident = format_ident!("__DEDRV_CLASSES_{}", ident);
+
+    // The dependencies of the device are recorded by reference, so that the init checks that they
+    // belong to an earlier stage.
+    let deps = args.depends_on.iter();
+    let deps_len = args.depends_on.len();
+    let deps_ident = format_ident!("__DEDRV_DEPS_{}", ident);
+
+    let stage = args.stage;
+
+    // The flags of the device are named after the constants of `DeviceFlags`.
+    let mut flags = Vec::new();
+    if args.weak {
+        flags.push(quote!(::dedrv::DeviceFlags::WEAK));
+    }
+    for flag in args.flags.iter() {
+        match flag.get_ident().map(|x| x.to_string()) {
+            Some(name) if ["optional", "disabled", "defer"].contains(&name.as_str()) => {
+                let name = format_ident!("{}", name.to_uppercase());
+                flags.push(quote!(::dedrv::DeviceFlags::#name));
+            }
+            _ => error(
+                &mut errors,
+                flag,
+                "unknown device flag (expected `optional`, `disabled` or `defer`)",
+            ),
+        }
+    }
+
+    // The interrupt line is declared on the device instance, so that it is managed by the device
+    // itself whenever it is initialized or cleaned up.
+    let item = match (&args.irq, args.irq_priority) {
+        (Some(irq), priority) => {
+            let mut var = var.clone();
+            let expr = &var.expr;
+            let irq = match priority {
+                Some(p) => {
+                    let p = *p;
+                    quote!(::dedrv::irq::Irq::new(#irq as u16).with_priority(#p))
+                }
+                None => quote!(::dedrv::irq::Irq::new(#irq as u16)),
+            };
+
+            var.expr = parse_quote!((#expr).with_irq(#irq));
+            quote!(#var)
+        }
+        (None, Some(p)) => {
+            let e = syn::Error::new(
+                p.span(),
+                "an interrupt priority requires an interrupt line (i.e. `irq = ...`)",
+            );
+            errors.extend(e.into_compile_error());
+            item
+        }
+        (None, None) => item,
+    };
+
+    // A singleton device is owned through a module of the same name as the instance, which lives
+    // in the type namespace (e.g. `GPIO0::take()`).
+    let singleton = if args.singleton {
+        let vis = var.vis.clone();
+        let doc = format!("The single owner of the [`{ident}`] device.");
+        let take_doc = format!(
+            "Take the only accessor of the [`{ident}`] device, or `None` if it has been taken \
+             already."
+        );
+
+        quote! {
+            #[doc = #doc]
+            #[allow(non_snake_case)]
+            #vis mod #ident {
+                use super::*;
+
+                static TAKEN: ::dedrv::Singleton = ::dedrv::Singleton::new();
+
+                #[doc = #take_doc]
+                pub fn take<Tag: ::dedrv::ClassTag<<#ty as ::dedrv::DeviceType>::Driver>>(
+                ) -> Option<::dedrv::Accessor<'static, <#ty as ::dedrv::DeviceType>::Driver, Tag>> {
+                    TAKEN.take(&super:: #ident)
+                }
+            }
+        }
+    } else {
+        quote!()
+    };
+
     quote! {
         // The original device instance variable.
         #item
 
         // The descriptor module with self-contained imports.
         mod #desc_mod_ident {
-            use ::dedrv::{Device, Descriptor};
+            use ::dedrv::Descriptor;
 
             use super::*;
 
-            // Do not mangle the function name, so one can debug it easily.
-            #[no_mangle]
-            fn __dedrv_desc_init(ptr: *const ()) {
-                let device: &'static _ = unsafe { &*(ptr as *const #ty) };
-                device.init();
-            }
+            // The compile-time invariants of the driver are checked for this instance.
+            const _: () = <<#ty as ::dedrv::DeviceType>::Driver as ::dedrv::Driver>::CHECK;
+
+            // The path scheme is read from the environment, which is tracked by the compiler
+            // through these reads, so the path is checked again whenever the scheme changes.
+            const _: Option<&str> = ::core::option_env!("DEDRV_PATH_PREFIX");
+            const _: Option<&str> = ::core::option_env!("DEDRV_PATH_MAX_LEN");
+
+            static #classes_ident: [::dedrv::ClassId; #classes_len] =
+                [#(::dedrv::ClassId::of::<_, #classes>(& #ident)),*];
+
+            static #deps_ident: [::dedrv::DeviceRef; #deps_len] =
+                [#(::dedrv::DeviceRef::new(& #deps)),*];
 
             #[allow(unused)]
             #[link_section = #desc_sname]
-            static #desc_ident: Descriptor = Descriptor::new(#path, & #ident, __dedrv_desc_init);
+            static #desc_ident: Descriptor = Descriptor::new(#path, & #ident)
+                .with_classes(& #classes_ident)
+                .with_dependencies(& #deps_ident)
+                .with_stage(#stage)
+                .with_flags(::dedrv::DeviceFlags::empty() #(.union(#flags))*);
+
+            // The metadata record, which is not loaded on the target.
+            #[used]
+            #[link_section = #meta_sname]
+            static #meta_ident: [u8; #meta_len] = *#meta;
         }
 
+        // The single owner of the device, if any.
+        #singleton
+
         // Compilation errors.
         #errors
     }
 }
 
+/// The scheme of the accepted device paths.
+///
+/// The scheme may be configured at the crate level with the following environment variables (e.g.
+/// in the `[env]` section of `.cargo/config.toml`):
+///
+/// - `DEDRV_PATH_PREFIX`: the prefix of every path (i.e. `/` by default).
+/// - `DEDRV_PATH_MAX_LEN`: the maximum length of a path (i.e. 64 by default).
+///
+/// The macro cannot declare its reads of the environment, so the expanded code reads both
+/// variables with `option_env!`, which makes the compiler rebuild the crate when they change.
+struct Scheme {
+    prefix: String,
+    max_len: usize,
+}
+
+impl Default for Scheme {
+    fn default() -> Self {
+        Scheme {
+            prefix: "/".into(),
+            max_len: 64,
+        }
+    }
+}
+
+impl Scheme {
+    fn from_env() -> Self {
+        let default = Scheme::default();
+
+        Scheme {
+            prefix: std::env::var("DEDRV_PATH_PREFIX").unwrap_or(default.prefix),
+            max_len: std::env::var("DEDRV_PATH_MAX_LEN")
+                .ok()
+                .and_then(|x| x.parse().ok())
+                .unwrap_or(default.max_len),
+        }
+    }
+}
+
+/// Check that a device path is well-formed (i.e. like a runtime `dedrv::Path`) with respect to the
+/// given scheme.
+fn check_path(path: &str, scheme: &Scheme) -> Result<(), String> {
+    match dedrv_path::check(path) {
+        Ok(()) => {}
+        Err(PathError::InvalidCharacter { index }) => {
+            let c = path[index..].chars().next().unwrap_or_default();
+            return Err(format!(
+                "invalid character {c:?} in device path, only alphanumeric characters, '/', '_', \
+                 '-' and '.' are allowed"
+            ));
+        }
+        Err(e) => return Err(e.message().into()),
+    }
+
+    if !path.starts_with(&scheme.prefix) {
+        return Err(format!("device path must start with '{}'", scheme.prefix));
+    }
+
+    if path.len() > scheme.max_len {
+        return Err(format!(
+            "device path must not be longer than {} characters",
+            scheme.max_len
+        ));
+    }
+
+    Ok(())
+}
+
+/// Check that a registry name may be used as a section name component.
+fn check_registry(name: &str) -> Result<(), String> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+    {
+        return Err(
+            "registry name must only contain lowercase alphanumeric characters or '_'".into(),
+        );
+    }
+
+    if ["early", "meta", "config", "markers"].contains(&name) {
+        return Err(format!("registry name '{name}' is reserved"));
+    }
+
+    Ok(())
+}
+
+/// Encode the bytes of a string in lowercase hexadecimal.
+fn hex(s: &str) -> String {
+    s.bytes().map(|b| format!("{b:02x}")).collect()
+}
+
 #[cfg(test)]
 mod tests {
     use googletest::prelude::*;
@@ -115,13 +409,337 @@ mod tests {
             contains_substring(quote!(mod __dedrv_desc_device).to_string())
         )?;
 
+        verify_that!(
+            result,
+            contains_substring(quote!(Descriptor::new("/gpio0", &DEVICE)).to_string())
+        )?;
+
+        verify_that!(
+            result,
+            contains_substring(quote!(#[link_section = ".dedrv.device.2f6770696f30"]).to_string())
+        )?;
+
         verify_that!(
             result,
             contains_substring(
-                quote!(Descriptor::new("/gpio0", &DEVICE, __dedrv_desc_init)).to_string()
+                quote!(
+                    #[link_section = ".dedrv.meta.device"]
+                    static __DEDRV_META_DEVICE: [u8; 62usize] =
+                        *b"1\0/gpio0\0DEVICE\0Device<DriverImpl>\0.dedrv.device.2f6770696f30\0";
+                )
+                .to_string()
             )
         )?;
 
         Ok(())
     }
+
+    #[test]
+    fn it_should_check_driver_invariants() -> googletest::Result<()> {
+        let code = run(
+            quote!(path = "/fifo0"),
+            quote! {
+                static FIFO0: Device<FifoDriver<Board>> = Device::new();
+            },
+        );
+
+        verify_that!(
+            code.to_string(),
+            contains_substring(quote!(as ::dedrv::Driver>::CHECK;).to_string())
+        )
+    }
+
+    #[test]
+    fn it_should_install_early_device() -> googletest::Result<()> {
+        let code = run(
+            quote!(path = "/uart0", early),
+            quote! {
+                static UART0: Device<DriverImpl> = Device::new();
+            },
+        );
+
+        let result = code.to_string();
+        verify_that!(result, not(contains_substring("error")))?;
+
+        verify_that!(
+            result,
+            contains_substring(quote!(#[link_section = ".dedrv.early.2f7561727430"]).to_string())
+        )
+    }
+
+    #[test]
+    fn it_should_record_device_classes() -> googletest::Result<()> {
+        let code = run(
+            quote!(path = "/gpio0", classes(tag::Gpio, LedTag)),
+            quote! {
+                static GPIO0: Device<DriverImpl> = Device::new();
+            },
+        );
+
+        let result = code.to_string();
+        verify_that!(result, not(contains_substring("error")))?;
+
+        verify_that!(
+            result,
+            contains_substring(
+                quote!(static __DEDRV_CLASSES_GPIO0: [::dedrv::ClassId; 2usize]).to_string()
+            )
+        )?;
+
+        verify_that!(
+            result,
+            contains_substring(quote!(::dedrv::ClassId::of::<_, LedTag>(&GPIO0)).to_string())
+        )?;
+
+        verify_that!(
+            result,
+            contains_substring(quote!(.with_classes(&__DEDRV_CLASSES_GPIO0)).to_string())
+        )
+    }
+
+    #[test]
+    fn it_should_record_device_dependencies() -> googletest::Result<()> {
+        let code = run(
+            quote!(path = "/eth0/phy", stage = 1, depends_on(GPIO0)),
+            quote! {
+                static PHY0: Device<PhyDriver> = Device::new();
+            },
+        );
+
+        let result = code.to_string();
+        verify_that!(result, not(contains_substring("error")))?;
+
+        verify_that!(
+            result,
+            contains_substring(
+                quote!(
+                    static __DEDRV_DEPS_PHY0: [::dedrv::DeviceRef; 1usize] =
+                        [::dedrv::DeviceRef::new(&GPIO0)];
+                )
+                .to_string()
+            )
+        )?;
+
+        verify_that!(
+            result,
+            contains_substring(quote!(.with_dependencies(&__DEDRV_DEPS_PHY0)).to_string())
+        )
+    }
+
+    #[test]
+    fn it_should_install_device_in_registry() -> googletest::Result<()> {
+        let code = run(
+            quote!(path = "/flash0", registry = "boot"),
+            quote! {
+                static FLASH0: Device<DriverImpl> = Device::new();
+            },
+        );
+
+        let result = code.to_string();
+        verify_that!(result, not(contains_substring("error")))?;
+
+        verify_that!(
+            result,
+            contains_substring(quote!(#[link_section = ".dedrv.boot.2f666c61736830"]).to_string())
+        )
+    }
+
+    #[test]
+    fn it_should_reject_invalid_registry() -> googletest::Result<()> {
+        verify_that!(check_registry("app_1"), ok(eq(&())))?;
+        verify_that!(check_registry("Boot"), err(anything()))?;
+        verify_that!(check_registry("meta"), err(anything()))?;
+
+        let code = run(
+            quote!(path = "/uart0", early, registry = "boot"),
+            quote! {
+                static UART0: Device<DriverImpl> = Device::new();
+            },
+        );
+
+        verify_that!(code.to_string(), contains_substring("compile_error"))
+    }
+
+    #[test]
+    fn it_should_install_weak_device() -> googletest::Result<()> {
+        let code = run(
+            quote!(path = "/uart0", weak),
+            quote! {
+                static UART0: Device<DriverImpl> = Device::new();
+            },
+        );
+
+        let result = code.to_string();
+        verify_that!(result, not(contains_substring("error")))?;
+
+        verify_that!(
+            result,
+            contains_substring(
+                quote!(#[link_section = ".dedrv.device.2f7561727430.weak"]).to_string()
+            )
+        )?;
+
+        verify_that!(
+            result,
+            contains_substring(quote!(.union(::dedrv::DeviceFlags::WEAK)).to_string())
+        )
+    }
+
+    #[test]
+    fn it_should_set_device_stage() -> googletest::Result<()> {
+        let code = run(
+            quote!(path = "/sensor0", stage = 2),
+            quote! {
+                static SENSOR0: Device<DriverImpl> = Device::new();
+            },
+        );
+
+        let result = code.to_string();
+        verify_that!(result, not(contains_substring("error")))?;
+        verify_that!(
+            result,
+            contains_substring(quote!(.with_stage(2u8)).to_string())
+        )
+    }
+
+    #[test]
+    fn it_should_set_device_flags() -> googletest::Result<()> {
+        let code = run(
+            quote!(path = "/modem0", flags(optional, defer)),
+            quote! {
+                static MODEM0: Device<DriverImpl> = Device::new();
+            },
+        );
+
+        let result = code.to_string();
+        verify_that!(result, not(contains_substring("error")))?;
+        verify_that!(
+            result,
+            contains_substring(
+                quote!(.union(::dedrv::DeviceFlags::OPTIONAL)
+                    .union(::dedrv::DeviceFlags::DEFER))
+                .to_string()
+            )
+        )
+    }
+
+    #[test]
+    fn it_should_reject_unknown_device_flag() -> googletest::Result<()> {
+        let code = run(
+            quote!(path = "/modem0", flags(lazy)),
+            quote! {
+                static MODEM0: Device<DriverImpl> = Device::new();
+            },
+        );
+
+        verify_that!(code.to_string(), contains_substring("unknown device flag"))
+    }
+
+    #[test]
+    fn it_should_generate_singleton_owner() -> googletest::Result<()> {
+        let code = run(
+            quote!(path = "/gpio0", singleton),
+            quote! {
+                pub static GPIO0: Device<DriverImpl> = Device::new();
+            },
+        );
+
+        let result = code.to_string();
+        verify_that!(result, not(contains_substring("error")))?;
+        verify_that!(
+            result,
+            contains_substring(quote!(pub mod GPIO0).to_string())
+        )?;
+        verify_that!(
+            result,
+            contains_substring(quote!(TAKEN.take(&super::GPIO0)).to_string())
+        )
+    }
+
+    #[test]
+    fn it_should_declare_irq_line() -> googletest::Result<()> {
+        let code = run(
+            quote!(path = "/uart0", irq = Interrupt::USART1, irq_priority = 32),
+            quote! {
+                static UART0: Device<DriverImpl> = Device::new();
+            },
+        );
+
+        let result = code.to_string();
+        verify_that!(result, not(contains_substring("error")))?;
+        verify_that!(
+            result,
+            contains_substring(
+                quote!((Device::new()).with_irq(
+                    ::dedrv::irq::Irq::new(Interrupt::USART1 as u16).with_priority(32u8)
+                ))
+                .to_string()
+            )
+        )?;
+
+        let code = run(
+            quote!(path = "/uart0", irq_priority = 32),
+            quote! {
+                static UART0: Device<DriverImpl> = Device::new();
+            },
+        );
+        verify_that!(code.to_string(), contains_substring("compile_error"))
+    }
+
+    #[test]
+    fn it_should_check_path() -> googletest::Result<()> {
+        let scheme = Scheme::default();
+
+        verify_that!(check_path("/gpio0", &scheme), ok(eq(&())))?;
+        verify_that!(check_path("/soc/uart-0.1_a", &scheme), ok(eq(&())))?;
+
+        for path in [
+            "",
+            "gpio0",
+            "/gpio0/",
+            "/gpio//0",
+            "/gpio 0",
+            &"/a".repeat(33),
+        ] {
+            verify_that!(check_path(path, &scheme), err(anything()))?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_check_path_with_configured_scheme() -> googletest::Result<()> {
+        let scheme = Scheme {
+            prefix: "/soc/".into(),
+            max_len: 10,
+        };
+
+        verify_that!(check_path("/soc/gpio0", &scheme), ok(eq(&())))?;
+        verify_that!(
+            check_path("/gpio0", &scheme),
+            err(eq("device path must start with '/soc/'"))
+        )?;
+        verify_that!(
+            check_path("/soc/gpio10", &scheme),
+            err(eq("device path must not be longer than 10 characters"))
+        )
+    }
+
+    #[test]
+    fn it_should_not_install_device_with_invalid_path() -> googletest::Result<()> {
+        let code = run(
+            quote!(path = "gpio0"),
+            quote! {
+                static DEVICE: Device<DriverImpl> = Device::new();
+            },
+        );
+
+        let result = code.to_string();
+
+        verify_that!(
+            result,
+            contains_substring("device path must start with '/'")
+        )?;
+        verify_that!(result, not(contains_substring("Descriptor")))
+    }
 }
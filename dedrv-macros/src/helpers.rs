@@ -11,3 +11,62 @@ pub fn token_stream_with_error(mut tokens: TokenStream, err: syn::Error) -> Toke
 pub fn error<A: ToTokens, T: Display>(tokens: &mut TokenStream, obj: A, msg: T) {
     tokens.extend(syn::Error::new_spanned(obj.into_token_stream(), msg).into_compile_error())
 }
+
+/// Convert an upper camel case identifier (e.g. `SpiBus` or `ADCSequencer`) into snake case.
+pub fn snake_case(ident: &str) -> String {
+    let chars: Vec<char> = ident.chars().collect();
+    let mut out = String::with_capacity(ident.len() + 4);
+
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            let prev = chars[i - 1];
+            let next_lower = chars.get(i + 1).is_some_and(|x| x.is_lowercase());
+
+            if prev.is_lowercase() || prev.is_ascii_digit() || (prev.is_uppercase() && next_lower) {
+                out.push('_');
+            }
+        }
+
+        out.extend(c.to_lowercase());
+    }
+
+    out
+}
+
+/// Convert a snake case identifier (e.g. `pull_up`) into upper camel case.
+pub fn pascal_case(ident: &str) -> String {
+    ident
+        .split('_')
+        .filter(|x| !x.is_empty())
+        .flat_map(|x| {
+            let mut chars = x.chars();
+            chars
+                .next()
+                .into_iter()
+                .flat_map(char::to_uppercase)
+                .chain(chars)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use googletest::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn it_should_convert_to_snake_case() -> googletest::Result<()> {
+        verify_that!(snake_case("Gpio"), eq("gpio"))?;
+        verify_that!(snake_case("SpiBus"), eq("spi_bus"))?;
+        verify_that!(snake_case("ADCSequencer"), eq("adc_sequencer"))?;
+        verify_that!(snake_case("I2c"), eq("i2c"))
+    }
+
+    #[test]
+    fn it_should_convert_to_pascal_case() -> googletest::Result<()> {
+        verify_that!(pascal_case("pull_up"), eq("PullUp"))?;
+        verify_that!(pascal_case("dma"), eq("Dma"))?;
+        verify_that!(pascal_case("rx_fifo_16"), eq("RxFifo16"))
+    }
+}
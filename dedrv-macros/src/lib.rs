@@ -5,6 +5,7 @@
 
 use proc_macro::TokenStream;
 
+mod board;
 mod class;
 mod device;
 mod helpers;
@@ -20,3 +21,53 @@ pub fn class(args: TokenStream, item: TokenStream) -> TokenStream {
 pub fn device(args: TokenStream, item: TokenStream) -> TokenStream {
     device::run(args.into(), item.into()).into()
 }
+
+/// The `board` macro that groups the devices, the pin assignments and the aliases of a board into
+/// a module, so the applications for the same board share one device inventory.
+#[proc_macro]
+pub fn board(item: TokenStream) -> TokenStream {
+    if !cfg!(feature = "board") {
+        let e = syn::Error::new(
+            proc_macro2::Span::call_site(),
+            "`board!` requires the `board` feature of dedrv",
+        );
+        return e.into_compile_error().into();
+    }
+
+    board::run(item.into()).into()
+}
+
+/// The `no_lock` attribute, which opts a method of a device class out of the driver state.
+///
+/// The driver implementation of such a method is not given the driver state, and the accessor
+/// calls it without entering a critical section. This is meant for methods that only poke hardware
+/// registers. This attribute is handled by the `class` attribute, so it cannot be used elsewhere.
+#[proc_macro_attribute]
+pub fn no_lock(_args: TokenStream, item: TokenStream) -> TokenStream {
+    let item: proc_macro2::TokenStream = item.into();
+    let mut tokens = item.clone();
+    helpers::error(
+        &mut tokens,
+        item,
+        "`no_lock` may only be used on a method of a device class",
+    );
+    tokens.into()
+}
+
+/// The `capability` attribute, which declares the optional capabilities of a device class (e.g.
+/// `#[capability(pull_up, open_drain)]`).
+///
+/// The drivers advertise the capabilities they support with the `CAPABILITIES` constant of the
+/// class driver trait, which is queried through `Accessor::has_capability`. This attribute is
+/// handled by the `class` attribute, so it cannot be used elsewhere.
+#[proc_macro_attribute]
+pub fn capability(_args: TokenStream, item: TokenStream) -> TokenStream {
+    let item: proc_macro2::TokenStream = item.into();
+    let mut tokens = item.clone();
+    helpers::error(
+        &mut tokens,
+        item,
+        "`capability` may only be used on a device class trait",
+    );
+    tokens.into()
+}
@@ -0,0 +1,108 @@
+//! The validation of the device paths.
+//!
+//! The rules are shared by the runtime (i.e. `dedrv::Path`), the `device` attribute and the
+//! host-side build support (i.e. `dedrv-build`), so they all accept the same paths.
+
+#![no_std]
+
+/// The reason why a device path is not well-formed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathError {
+    /// The path is empty.
+    Empty,
+
+    /// The path does not start with `/`.
+    Relative,
+
+    /// The path ends with `/` (except the root path).
+    TrailingSlash,
+
+    /// The path has an empty component (i.e. `//`).
+    EmptyComponent,
+
+    /// The path has a character that is not allowed, at the given byte index.
+    InvalidCharacter { index: usize },
+}
+
+impl PathError {
+    /// Get the description of the error.
+    pub const fn message(&self) -> &'static str {
+        match self {
+            PathError::Empty => "device path must not be empty",
+            PathError::Relative => "device path must start with '/'",
+            PathError::TrailingSlash => "device path must not end with '/'",
+            PathError::EmptyComponent => "device path must not contain empty components",
+            PathError::InvalidCharacter { .. } => {
+                "device path must only contain alphanumeric characters, '/', '_', '-' or '.'"
+            }
+        }
+    }
+}
+
+/// Check that a device path is well-formed.
+///
+/// A path starts with `/`, it does not end with `/` (except the root path), it has no empty
+/// component, and it only contains ASCII alphanumeric characters, `/`, `_`, `-` and `.`.
+pub const fn check(path: &str) -> Result<(), PathError> {
+    let bytes = path.as_bytes();
+
+    if bytes.is_empty() {
+        return Err(PathError::Empty);
+    }
+
+    if bytes[0] != b'/' {
+        return Err(PathError::Relative);
+    }
+
+    let mut i = 0;
+    while i < bytes.len() {
+        if !is_allowed(bytes[i]) {
+            return Err(PathError::InvalidCharacter { index: i });
+        }
+
+        if i > 0 && bytes[i] == b'/' && bytes[i - 1] == b'/' {
+            return Err(PathError::EmptyComponent);
+        }
+
+        i += 1;
+    }
+
+    if bytes.len() > 1 && bytes[bytes.len() - 1] == b'/' {
+        return Err(PathError::TrailingSlash);
+    }
+
+    Ok(())
+}
+
+/// Check whether a byte may appear in a device path.
+const fn is_allowed(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || matches!(b, b'/' | b'_' | b'-' | b'.')
+}
+
+#[cfg(test)]
+mod tests {
+    use googletest::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn it_should_accept_well_formed_paths() -> googletest::Result<()> {
+        for path in ["/", "/gpio0", "/soc/uart-0.1_a"] {
+            verify_that!(check(path), ok(eq(())))?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_reject_malformed_paths() -> googletest::Result<()> {
+        verify_that!(check(""), err(eq(PathError::Empty)))?;
+        verify_that!(check("gpio0"), err(eq(PathError::Relative)))?;
+        verify_that!(check("/gpio0/"), err(eq(PathError::TrailingSlash)))?;
+        verify_that!(check("/gpio//0"), err(eq(PathError::EmptyComponent)))?;
+        verify_that!(
+            check("/gpio 0"),
+            err(eq(PathError::InvalidCharacter { index: 5 }))
+        )
+    }
+}
@@ -3,10 +3,37 @@ use std::fs::File;
 use std::io::Write;
 use std::path::PathBuf;
 
+/// The linker fragments for each supported runtime crate.
+const FRAGMENTS: &[(&str, &[u8])] = &[
+    ("dedrv-cortex-m.x", include_bytes!("dedrv-cortex-m.x")),
+    ("dedrv-esp.x", include_bytes!("dedrv-esp.x")),
+    ("dedrv-riscv.x", include_bytes!("dedrv-riscv.x")),
+];
+
 fn main() -> anyhow::Result<()> {
-    // Copy the memory layout to the final build destination.
+    // Hosted targets (e.g. unit tests) do not use a custom linker script, so there is nothing to
+    // provide. The descriptor table is then always empty.
+    if env::var("CARGO_CFG_TARGET_OS")? != "none" {
+        return Ok(());
+    }
+
     let out = &PathBuf::from(env::var("OUT_DIR")?);
-    File::create(out.join("dedrv.x"))?.write_all(include_bytes!("dedrv.x"))?;
+
+    // Copy every linker fragment to the final build destination, so one can pick a specific one
+    // (e.g. `dedrv-esp.x` on ESP RISC-V chips).
+    for (name, content) in FRAGMENTS {
+        File::create(out.join(name))?.write_all(content)?;
+        println!("cargo:rerun-if-changed={}", name);
+    }
+
+    // Then, the default `dedrv.x` is the fragment for the usual runtime of the target family.
+    let default = match env::var("CARGO_CFG_TARGET_ARCH")?.as_str() {
+        "riscv32" | "riscv64" => "dedrv-riscv.x",
+        "xtensa" => "dedrv-esp.x",
+        _ => "dedrv-cortex-m.x",
+    };
+
+    std::fs::copy(out.join(default), out.join("dedrv.x"))?;
 
     // Add the build destination as a linker search path.
     println!("cargo:rustc-link-search={}", out.display());
@@ -0,0 +1,29 @@
+/* Linker fragment for `cortex-m-rt`, which places read-only data in the `FLASH` region. */
+SECTIONS {
+	.dedrv ALIGN(4) :
+	{
+		/* Early device descriptors, which are initialized before every other device. */
+		__DEDRV_MARKER_EARLY_START = .;
+		KEEP(*(SORT_BY_NAME(.dedrv.early.*)));
+		__DEDRV_MARKER_EARLY_END = .;
+
+		/* Device desriptors for init ans cleanup, sorted by path for binary-search lookup. */
+		__DEDRV_MARKER_DEVICE_START = .;
+		KEEP(*(SORT_BY_NAME(.dedrv.device.*)));
+		__DEDRV_MARKER_DEVICE_END = .;
+
+		/* Runtime configuration blob, see `dedrv::config`. */
+		__DEDRV_MARKER_CONFIG_START = .;
+		KEEP(*(.dedrv.config .dedrv.config.*));
+		__DEDRV_MARKER_CONFIG_END = .;
+
+		/* End of `dedrv` section. */
+		__DEDRV_MARKER_END = .;
+	} >FLASH
+
+	/* Device metadata for the host-side build support, which is not loaded on the target. */
+	.dedrv.meta (INFO) :
+	{
+		KEEP(*(.dedrv.meta.*));
+	}
+}
@@ -0,0 +1,146 @@
+use core::cell::{Ref, RefMut};
+use core::hash::{Hash, Hasher};
+use core::marker::PhantomData;
+use core::ptr::NonNull;
+
+use critical_section::CriticalSection;
+
+use crate::{tag, ClassTag, Device, DeviceId, Driver, Result};
+
+/// An device class accessor.
+pub struct Accessor<'d, D: Driver + 'static, Tag = tag::NoTag> {
+    /// The owning device of this accessor.
+    pub device: NonNull<Device<D>>,
+
+    #[doc(hidden)]
+    pub(crate) _marker: PhantomData<&'d Device<D>>,
+
+    #[doc(hidden)]
+    pub(crate) _tag: PhantomData<Tag>,
+}
+
+impl<D: Driver> Device<D> {
+    /// Limit the number of accessors that may be open at the same time on this device instance.
+    ///
+    /// This is meant for device classes where concurrent users cannot coexist (e.g. a one-shot
+    /// ADC sequencer). Once the limit is reached, [`Device::try_accessor`] returns
+    /// [`Error::Busy`](crate::Error::Busy) until an accessor is dropped. By default, the number of
+    /// accessors is not limited.
+    ///
+    /// # Panics
+    ///
+    /// The driver must opt in with [`Driver::LIMITED_ACCESSORS`], otherwise this panics, which
+    /// fails the build of a `static` device instance.
+    pub const fn with_max_accessors(mut self, max: usize) -> Self {
+        assert!(
+            D::LIMITED_ACCESSORS,
+            "the driver does not opt in to limited accessors"
+        );
+        self.max_accessors = max;
+        self
+    }
+}
+
+impl<'d, D: Driver, Tag> Accessor<'d, D, Tag> {
+    /// Create a new accessor from an owning [`Device`].
+    ///
+    /// A driver with limited accessors (see [`Driver::LIMITED_ACCESSORS`]) fails the build, since
+    /// its accessors are only obtained with [`Accessor::try_new`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if an exclusive accessor of the device is open (see [`crate::ExclusiveAccessor`]).
+    pub fn new(device: &'d Device<D>) -> Self
+    where
+        Tag: ClassTag<D>,
+    {
+        #[allow(clippy::let_unit_value)]
+        let () = Unlimited::<D>::CHECK;
+
+        Self::try_new(device).expect("an exclusive accessor of the device is open")
+    }
+
+    /// Try to create a new accessor from an owning [`Device`].
+    ///
+    /// This returns [`Error::Busy`](crate::Error::Busy) if the limit of open accessors of the
+    /// device is reached, or if an exclusive accessor of the device is open.
+    pub fn try_new(device: &'d Device<D>) -> Result<Self>
+    where
+        Tag: ClassTag<D>,
+    {
+        device.open_accessor()?;
+
+        let device = unsafe { NonNull::new_unchecked(device as *const _ as *mut _) };
+        Ok(Accessor {
+            device,
+            _marker: PhantomData,
+            _tag: PhantomData,
+        })
+    }
+
+    /// Helper function to get access to the inner device.
+    #[inline(always)]
+    pub fn inner(&self) -> &'d Device<D> {
+        // SAFETY: The pointer is valid because of the lifetime of the accessor, which is at least
+        // as long as the inner device.
+        unsafe { self.device.as_ref() }
+    }
+
+    /// Get the identifier of the owning device.
+    #[inline(always)]
+    pub fn id(&self) -> DeviceId {
+        self.inner().id()
+    }
+
+    /// Helper function to get access to the internal driver state from a critical section.
+    #[inline(always)]
+    pub fn inner_state_ref<'a, 'cs>(&'a self, cs: CriticalSection<'cs>) -> Ref<'a, D::StateType>
+    where
+        'cs: 'a,
+    {
+        self.inner().state_ref(cs)
+    }
+
+    /// Helper function to get access to the mutable internal driver state from a critical section.
+    #[inline(always)]
+    pub fn inner_state_ref_mut<'a, 'cs>(
+        &'a self,
+        cs: CriticalSection<'cs>,
+    ) -> RefMut<'a, D::StateType>
+    where
+        'cs: 'a,
+    {
+        self.inner().state_ref_mut(cs)
+    }
+}
+
+/// Two accessors are equal if they own the same device, whatever their class.
+impl<D: Driver, Tag, Other> PartialEq<Accessor<'_, D, Other>> for Accessor<'_, D, Tag> {
+    fn eq(&self, other: &Accessor<'_, D, Other>) -> bool {
+        self.id() == other.id()
+    }
+}
+
+impl<D: Driver, Tag> Eq for Accessor<'_, D, Tag> {}
+
+impl<D: Driver, Tag> Hash for Accessor<'_, D, Tag> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id().hash(state)
+    }
+}
+
+impl<D: Driver, Tag> Drop for Accessor<'_, D, Tag> {
+    fn drop(&mut self) {
+        self.inner().close_accessor();
+    }
+}
+
+/// The check that the number of open accessors of a driver is not limited.
+struct Unlimited<D>(PhantomData<D>);
+
+impl<D: Driver> Unlimited<D> {
+    const CHECK: () = assert!(
+        !D::LIMITED_ACCESSORS,
+        "the accessors of the driver are limited, use `try_accessor` instead"
+    );
+}
@@ -0,0 +1,94 @@
+use crate::{Accessor, Driver, Result};
+
+/// A tuple of accessors whose driver states are borrowed together (see [`atomic_with`]).
+pub trait AtomicStates {
+    /// The tuple of the mutable driver states, in the order of the accessors.
+    type States<'s>;
+
+    /// Borrow every driver state within a single critical section, then call `f` with them.
+    fn with<R>(self, f: impl FnOnce(Self::States<'_>) -> R) -> Result<R>;
+}
+
+macro_rules! impl_atomic_states {
+    ($(($i:tt, $d:ident, $t:ident, $state:ident)),+) => {
+        impl<'a, $($d: Driver, $t),+> AtomicStates for ($(&'a Accessor<'a, $d, $t>,)+) {
+            type States<'s> = ($(&'s mut $d::StateType,)+);
+
+            fn with<R>(self, f: impl FnOnce(Self::States<'_>) -> R) -> Result<R> {
+                critical_section::with(|cs| {
+                    $(let mut $state = self.$i.inner().state.borrow(cs).try_borrow_mut()?;)+
+                    Ok(f(($(&mut *$state,)+)))
+                })
+            }
+        }
+    };
+}
+
+impl_atomic_states!((0, D0, T0, s0));
+impl_atomic_states!((0, D0, T0, s0), (1, D1, T1, s1));
+impl_atomic_states!((0, D0, T0, s0), (1, D1, T1, s1), (2, D2, T2, s2));
+impl_atomic_states!(
+    (0, D0, T0, s0),
+    (1, D1, T1, s1),
+    (2, D2, T2, s2),
+    (3, D3, T3, s3)
+);
+
+/// Borrow the driver states of several devices at once, then call `f` with them (e.g. for a
+/// consistent snapshot of a timestamp and a sensor value).
+///
+/// The accessors are given as a tuple of up to four references (e.g. `(&rtc, &imu)`), and `f` is
+/// given the tuple of their mutable states in the same order. Every state is borrowed within a
+/// single critical section, so no interrupt nor other core may update one of the devices in the
+/// middle of the snapshot, and there is no lock order to get wrong between nested borrows.
+///
+/// This returns [`Error::Busy`](crate::Error::Busy) if a state is already borrowed, including if
+/// a device is given twice.
+pub fn atomic_with<A: AtomicStates, R>(
+    accessors: A,
+    f: impl FnOnce(A::States<'_>) -> R,
+) -> Result<R> {
+    accessors.with(f)
+}
+
+#[cfg(test)]
+mod tests {
+    use googletest::prelude::*;
+
+    use super::*;
+    use crate::testing::CounterDriver;
+    use crate::{tag, Device, Error, StateLock};
+
+    struct FlagDriver;
+
+    impl Driver for FlagDriver {
+        type StateType = bool;
+
+        fn init(_: &StateLock<Self>) {}
+        fn cleanup(_: &StateLock<Self>) {}
+    }
+
+    #[test]
+    fn it_should_borrow_states_together() -> googletest::Result<()> {
+        static COUNTER: Device<CounterDriver> = Device::new();
+        static FLAG: Device<FlagDriver> = Device::new();
+
+        let counter = COUNTER.accessor::<tag::NoTag>();
+        let flag = FLAG.accessor::<tag::NoTag>();
+
+        atomic_with((&counter, &flag), |(count, flag)| {
+            *count += 1;
+            *flag = true;
+        })?;
+        verify_that!(
+            atomic_with((&flag, &counter), |(flag, count)| (*flag, *count)),
+            ok(eq(&(true, 1)))
+        )?;
+
+        let again = COUNTER.accessor::<tag::NoTag>();
+        verify_that!(
+            atomic_with((&counter, &again), |_| ()),
+            err(eq(&Error::Busy))
+        )
+    }
+}
@@ -0,0 +1,172 @@
+//! The board support packages, which share the device inventory of a board between applications.
+//!
+//! A board support package (i.e. a BSP crate) declares the devices of a board once, along with the
+//! pin assignments and the aliases of the board, with the [`board!`](crate::board!) macro:
+//!
+//! ```rust,ignore
+//! dedrv::board! {
+//!     /// The Nucleo-F429ZI board.
+//!     pub mod nucleo {
+//!         use super::*;
+//!
+//!         #[dedrv::device(path = "/soc/usart3", early)]
+//!         pub static USART3: Device<UartDriver> = Device::new();
+//!
+//!         #[dedrv::device(path = "/soc/gpiob", classes(tag::Gpio))]
+//!         pub static GPIOB: Device<GpioDriver> = Device::new();
+//!
+//!         pins! {
+//!             LED_GREEN = GPIOB[0],
+//!             LED_BLUE = GPIOB[7],
+//!         }
+//!
+//!         aliases! {
+//!             CONSOLE = USART3,
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! Each pin becomes a [`Pin`] constant of the module, and each alias re-exports its device under
+//! another name (e.g. `nucleo::CONSOLE`). The module also gets a `BOARD` constant, i.e. the
+//! [`Board`] inventory, which the applications initialize with [`Board::init`]:
+//!
+//! ```rust,ignore
+//! let report = bsp::nucleo::BOARD.init()?;
+//! let console = bsp::nucleo::BOARD.find("CONSOLE")?;
+//! ```
+//!
+//! The devices of a library crate are only linked into the application if the application refers
+//! to them, which the inventory does on behalf of the application.
+
+use crate::{Descriptor, Device, Driver, Error, InitReport, Result};
+
+/// A pin of a board, i.e. a line of a device (e.g. a GPIO port).
+pub struct Pin<D: Driver + 'static> {
+    device: &'static Device<D>,
+    line: u32,
+}
+
+impl<D: Driver> Pin<D> {
+    /// Create a new pin, on a line of a device.
+    pub const fn new(device: &'static Device<D>, line: u32) -> Self {
+        Pin { device, line }
+    }
+
+    /// Get the device of the pin.
+    pub const fn device(&self) -> &'static Device<D> {
+        self.device
+    }
+
+    /// Get the line of the pin on its device.
+    pub const fn line(&self) -> u32 {
+        self.line
+    }
+}
+
+impl<D: Driver> Clone for Pin<D> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<D: Driver> Copy for Pin<D> {}
+
+/// The device inventory of a board, as declared by the [`board!`](crate::board!) macro.
+#[derive(Debug, Clone, Copy)]
+pub struct Board {
+    name: &'static str,
+    devices: &'static [&'static str],
+    aliases: &'static [(&'static str, &'static str)],
+    pins: &'static [(&'static str, &'static str, u32)],
+    link: fn(),
+}
+
+impl Board {
+    /// Create a new board with the paths of its devices, where `link` refers to the device
+    /// instances so they are linked into the application.
+    pub const fn new(name: &'static str, devices: &'static [&'static str], link: fn()) -> Self {
+        Board {
+            name,
+            devices,
+            aliases: &[],
+            pins: &[],
+            link,
+        }
+    }
+
+    /// Set the aliases of the board, i.e. pairs of alias and device path.
+    pub const fn with_aliases(mut self, aliases: &'static [(&'static str, &'static str)]) -> Self {
+        self.aliases = aliases;
+        self
+    }
+
+    /// Set the pin assignments of the board, i.e. the name, the device path and the line of each
+    /// pin.
+    pub const fn with_pins(mut self, pins: &'static [(&'static str, &'static str, u32)]) -> Self {
+        self.pins = pins;
+        self
+    }
+
+    /// Get the name of the board.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Iterate over the path of each device of the board, in declaration order.
+    pub fn devices(&self) -> impl Iterator<Item = &'static str> {
+        self.devices.iter().copied()
+    }
+
+    /// Iterate over the aliases of the board, with the path of their device.
+    pub fn aliases(&self) -> impl Iterator<Item = (&'static str, &'static str)> {
+        self.aliases.iter().copied()
+    }
+
+    /// Iterate over the pin assignments of the board, with the path of their device and their
+    /// line.
+    pub fn pins(&self) -> impl Iterator<Item = (&'static str, &'static str, u32)> {
+        self.pins.iter().copied()
+    }
+
+    /// Resolve an alias or a device path of the board into the device path.
+    pub fn resolve(&self, name: &str) -> Option<&'static str> {
+        self.aliases
+            .iter()
+            .find(|(alias, _)| *alias == name)
+            .map(|(_, path)| *path)
+            .or_else(|| self.devices.iter().copied().find(|x| *x == name))
+    }
+
+    /// Find the descriptor of a device of the board, by alias or by path.
+    ///
+    /// This returns [`Error::DeviceNotFound`] if the device is not part of the board.
+    pub fn find(&self, name: &str) -> Result<&'static Descriptor> {
+        crate::find(self.resolve(name).ok_or(Error::DeviceNotFound)?)
+    }
+
+    /// Initialize the devices of the application (see [`init`](crate::init)), including the ones
+    /// of the board.
+    pub fn init(&self) -> Result<InitReport> {
+        (self.link)();
+        crate::init()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use googletest::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn it_should_resolve_aliases() -> googletest::Result<()> {
+        const BOARD: Board =
+            Board::new("test", &["/uart0", "/gpio0"], || {}).with_aliases(&[("CONSOLE", "/uart0")]);
+
+        verify_that!(BOARD.resolve("CONSOLE"), some(eq("/uart0")))?;
+        verify_that!(BOARD.resolve("/gpio0"), some(eq("/gpio0")))?;
+        verify_that!(BOARD.resolve("/spi0"), none())?;
+        verify_that!(BOARD.find("LED").err(), some(eq(&Error::DeviceNotFound)))
+    }
+}
@@ -0,0 +1,298 @@
+//! The CAN bus class, with the filter banks that are shared by the users of a controller.
+//!
+//! A CAN controller is typically shared by several protocol stacks (e.g. a diagnostic stack and
+//! an application protocol), which each need their own acceptance filters. Rather than letting
+//! each user pick a filter bank of the hardware, the banks are allocated by the framework:
+//!
+//! ```rust,ignore
+//! let mut can = CAN0.can();
+//! let diag = can.add_filter(Filter::new(Id::Standard(0x7df), 0x7ff)?)?;
+//!
+//! can.transmit(&Frame::new(Id::Standard(0x7e8), &[0x02, 0x01, 0x0c])?)?;
+//! while let Some(frame) = can.receive() {
+//!     // ...
+//! }
+//!
+//! can.remove_filter(diag)?;
+//! ```
+//!
+//! The driver implements the [`Can`] class over the hardware, where the received frames are
+//! typically pushed by the interrupt handler into a bounded queue of the driver state (see
+//! [`Spsc`](crate::queue::Spsc)), then it gives the framework its [`FilterBanks`] (see
+//! [`Filtered`]). A driver that also gives its reception queue (see
+//! [`Streamed`](crate::queue::Streamed)) lets the users check for lost frames with
+//! `can.queue_stats()`.
+
+use crate::{Accessor, Error, Result};
+
+/// The identifier of a CAN frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Id {
+    /// A standard 11-bit identifier.
+    Standard(u16),
+
+    /// An extended 29-bit identifier.
+    Extended(u32),
+}
+
+impl Id {
+    /// The largest standard identifier.
+    pub const MAX_STANDARD: u16 = 0x7ff;
+
+    /// The largest extended identifier.
+    pub const MAX_EXTENDED: u32 = 0x1fff_ffff;
+
+    /// Get the raw value of the identifier.
+    pub const fn raw(&self) -> u32 {
+        match *self {
+            Id::Standard(x) => x as u32,
+            Id::Extended(x) => x,
+        }
+    }
+
+    /// Check whether this is an extended identifier.
+    pub const fn is_extended(&self) -> bool {
+        matches!(self, Id::Extended(_))
+    }
+
+    /// Check whether the identifier fits in its width.
+    const fn is_valid(&self) -> bool {
+        match *self {
+            Id::Standard(x) => x <= Id::MAX_STANDARD,
+            Id::Extended(x) => x <= Id::MAX_EXTENDED,
+        }
+    }
+}
+
+/// A classic CAN data frame, with up to 8 bytes of data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Frame {
+    id: Id,
+    len: u8,
+    data: [u8; 8],
+}
+
+impl Frame {
+    /// Create a new data frame.
+    ///
+    /// This returns [`Error::InvalidArgument`] if the identifier does not fit in its width, or
+    /// [`Error::BufferTooSmall`] if there are more than 8 bytes of data.
+    pub fn new(id: Id, data: &[u8]) -> Result<Self> {
+        if !id.is_valid() {
+            return Err(Error::InvalidArgument);
+        }
+
+        let mut frame = Frame {
+            id,
+            len: 0,
+            data: [0; 8],
+        };
+
+        frame
+            .data
+            .get_mut(..data.len())
+            .ok_or(Error::BufferTooSmall)?
+            .copy_from_slice(data);
+        frame.len = data.len() as u8;
+
+        Ok(frame)
+    }
+
+    /// The identifier of the frame.
+    pub const fn id(&self) -> Id {
+        self.id
+    }
+
+    /// The data of the frame.
+    pub fn data(&self) -> &[u8] {
+        &self.data[..self.len as usize]
+    }
+}
+
+/// An acceptance filter, which accepts the frames whose identifier matches `id` on the bits of
+/// `mask`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Filter {
+    id: Id,
+    mask: u32,
+}
+
+impl Filter {
+    /// Create a new filter, which only accepts the identifiers of the same width as `id`.
+    ///
+    /// This returns [`Error::InvalidArgument`] if the identifier does not fit in its width.
+    pub fn new(id: Id, mask: u32) -> Result<Self> {
+        match id.is_valid() {
+            true => Ok(Filter { id, mask }),
+            false => Err(Error::InvalidArgument),
+        }
+    }
+
+    /// The identifier of the filter.
+    pub const fn id(&self) -> Id {
+        self.id
+    }
+
+    /// The mask of the filter, whose set bits must match the identifier.
+    pub const fn mask(&self) -> u32 {
+        self.mask
+    }
+
+    /// Check whether the filter accepts a frame, as the hardware would do.
+    pub fn matches(&self, frame: &Frame) -> bool {
+        self.id.is_extended() == frame.id.is_extended()
+            && (self.id.raw() ^ frame.id.raw()) & self.mask == 0
+    }
+}
+
+/// A CAN controller.
+#[crate::class]
+pub trait Can {
+    /// Queue a frame for transmission.
+    ///
+    /// This returns [`Error::Busy`] if every transmit mailbox of the controller is full.
+    fn transmit(&mut self, frame: &Frame) -> Result<()>;
+
+    /// Pop the next received frame out of the reception queue, without blocking.
+    fn receive(&mut self) -> Option<Frame>;
+
+    /// Configure a filter bank of the controller, or disable it.
+    ///
+    /// The banks are allocated by the framework (see [`Accessor::add_filter`]), so this is not
+    /// meant to be called by the users of the controller.
+    fn configure_filter(&mut self, bank: usize, filter: Option<Filter>) -> Result<()>;
+}
+
+/// The allocation of the filter banks of a controller, which lives in the driver state.
+///
+/// A zeroed allocation is a valid allocation where every bank is free.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FilterBanks(u32);
+
+impl FilterBanks {
+    /// Create a new allocation where every bank is free.
+    pub const fn new() -> Self {
+        FilterBanks(0)
+    }
+
+    /// Allocate the first free bank among the first `banks` ones.
+    fn allocate(&mut self, banks: usize) -> Option<usize> {
+        let bank = (0..banks.min(u32::BITS as usize)).find(|&x| self.0 & (1 << x) == 0)?;
+        self.0 |= 1 << bank;
+        Some(bank)
+    }
+
+    /// Free a bank, then return whether it was allocated.
+    fn free(&mut self, bank: usize) -> bool {
+        if bank >= u32::BITS as usize {
+            return false;
+        }
+
+        let allocated = self.0 & (1 << bank) != 0;
+        self.0 &= !(1 << bank);
+        allocated
+    }
+}
+
+/// A CAN driver whose filter banks are allocated by the framework.
+pub trait Filtered: driver::Can {
+    /// The number of filter banks of the controller, which is at most 32.
+    const BANKS: usize;
+
+    /// Get the allocation of the filter banks out of the driver state.
+    fn banks(state: &mut Self::StateType) -> &mut FilterBanks;
+}
+
+/// The handle of a filter that has been added to a controller.
+#[derive(Debug, PartialEq, Eq)]
+pub struct FilterId(usize);
+
+impl FilterId {
+    /// The filter bank that holds the filter.
+    pub const fn bank(&self) -> usize {
+        self.0
+    }
+}
+
+impl<D: Filtered> Accessor<'_, D, tag::Can> {
+    /// Add an acceptance filter to the controller, in a free filter bank.
+    ///
+    /// This returns [`Error::NoFilterBank`] if every bank is allocated.
+    pub fn add_filter(&mut self, filter: Filter) -> Result<FilterId> {
+        let bank = critical_section::with(|cs| {
+            D::banks(&mut self.inner().state.borrow_ref_mut(cs)).allocate(D::BANKS)
+        })
+        .ok_or(Error::NoFilterBank)?;
+
+        match Can::configure_filter(self, bank, Some(filter)) {
+            Ok(()) => Ok(FilterId(bank)),
+            Err(e) => {
+                self.free_bank(bank);
+                Err(e)
+            }
+        }
+    }
+
+    /// Remove a filter from the controller, which frees its filter bank.
+    pub fn remove_filter(&mut self, id: FilterId) -> Result<()> {
+        Can::configure_filter(self, id.0, None)?;
+        self.free_bank(id.0);
+        Ok(())
+    }
+
+    /// Free a filter bank of the controller.
+    fn free_bank(&self, bank: usize) {
+        critical_section::with(|cs| {
+            D::banks(&mut self.inner().state.borrow_ref_mut(cs)).free(bank);
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use googletest::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn it_should_reject_invalid_frames() -> googletest::Result<()> {
+        verify_that!(
+            Frame::new(Id::Standard(0x800), &[]),
+            err(eq(&Error::InvalidArgument))
+        )?;
+        verify_that!(
+            Frame::new(Id::Extended(0x100), &[0; 9]),
+            err(eq(&Error::BufferTooSmall))
+        )?;
+        verify_that!(
+            Frame::new(Id::Standard(0x100), &[1, 2]).map(|x| x.data().len()),
+            ok(eq(&2))
+        )
+    }
+
+    #[test]
+    fn it_should_match_frames() -> googletest::Result<()> {
+        let filter = Filter::new(Id::Standard(0x7e0), 0x7f8)?;
+
+        for (id, expected) in [
+            (Id::Standard(0x7e8), false),
+            (Id::Standard(0x7e7), true),
+            (Id::Extended(0x7e0), false),
+        ] {
+            verify_that!(filter.matches(&Frame::new(id, &[])?), eq(expected))?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_allocate_banks() -> googletest::Result<()> {
+        let mut banks = FilterBanks::new();
+
+        verify_that!(banks.allocate(2), some(eq(0)))?;
+        verify_that!(banks.allocate(2), some(eq(1)))?;
+        verify_that!(banks.allocate(2), none())?;
+        verify_that!((banks.free(0), banks.free(0)), (eq(true), eq(false)))?;
+        verify_that!(banks.allocate(2), some(eq(0)))
+    }
+}
@@ -0,0 +1,42 @@
+//! The optional capabilities of the device classes.
+//!
+//! A class may declare the capabilities that only part of the hardware supports, which are listed
+//! in a generated enumeration (e.g. `GpioCapability`):
+//!
+//! ```rust,ignore
+//! #[dedrv::class]
+//! #[dedrv::capability(pull_up, open_drain)]
+//! pub trait Gpio {
+//!     fn set(&mut self, high: bool);
+//! }
+//! ```
+//!
+//! Then each driver advertises the capabilities it supports with the `CAPABILITIES` constant of
+//! the class driver trait (e.g. `const CAPABILITIES: u32 = GpioCapability::PullUp.bit();`), which
+//! generic code queries at runtime with [`Accessor::has_capability`], so it adapts to partial
+//! hardware support.
+
+use crate::{Accessor, ClassTag, Driver};
+
+/// A capability of a device class, which is a bit of the capability set of the drivers.
+pub trait Capability: Copy {
+    /// Get the bit of the capability in the capability set of a driver.
+    fn bit(self) -> u32;
+}
+
+/// The capability set of the driver `D` for the class of a tag, which is implemented by the
+/// [`class`](crate::class) attribute.
+pub trait Capabilities<D: Driver>: ClassTag<D> {
+    /// The capabilities of the class.
+    type Capability: Capability;
+
+    /// The capabilities that are supported by the driver.
+    const CAPABILITIES: u32;
+}
+
+impl<'d, D: Driver, Tag: Capabilities<D>> Accessor<'d, D, Tag> {
+    /// Check whether the driver of the device supports a capability of the class.
+    pub fn has_capability(&self, capability: Tag::Capability) -> bool {
+        Tag::CAPABILITIES & capability.bit() != 0
+    }
+}
@@ -0,0 +1,32 @@
+//! The monotonic clock of the framework.
+//!
+//! A single user-supplied tick counter (e.g. a millisecond timer, or the DWT cycle counter on
+//! Cortex-M) is registered with [`set_clock`], then it paces the polled devices (see
+//! [`poll`](crate::poll)), times the class methods (see [`profile`](crate::profile)) and
+//! timestamps the error records (see [`history`](crate::history)), so all of them are expressed
+//! in the same unit.
+
+use core::cell::Cell;
+
+use critical_section::Mutex;
+
+/// The monotonic clock function, which returns the current time in ticks.
+pub type ClockFn = fn() -> u64;
+
+/// The registered clock.
+static CLOCK: Mutex<Cell<Option<ClockFn>>> = Mutex::new(Cell::new(None));
+
+/// Register the clock of the framework.
+pub fn set_clock(clock: ClockFn) {
+    critical_section::with(|cs| CLOCK.borrow(cs).set(Some(clock)));
+}
+
+/// Unregister the clock of the framework.
+pub fn clear_clock() {
+    critical_section::with(|cs| CLOCK.borrow(cs).set(None));
+}
+
+/// Get the current time in ticks, if a clock has been registered.
+pub fn now() -> Option<u64> {
+    critical_section::with(|cs| CLOCK.borrow(cs).get()).map(|clock| clock())
+}
@@ -0,0 +1,169 @@
+//! The runtime key-value configuration store.
+//!
+//! The store lets builds tweak driver parameters (e.g. baud rates or feature flags) without
+//! recompiling the drivers. The application installs a [`Config`] before calling
+//! [`init`](crate::init), then each [`Driver::init`](crate::Driver::init) queries its parameters
+//! with [`get`] or [`parse`].
+//!
+//! A configuration is either a static table of pairs, or a blob of `key=value` entries separated
+//! by NUL or newline characters (e.g. boot arguments). A blob may be provided by the bootloader in
+//! a dedicated memory region, or stored in the `.dedrv.config` linker section of the image.
+//!
+//! By convention, the keys of a device are prefixed by its path (e.g. `/uart0.baud`).
+
+use core::cell::Cell;
+use core::str::FromStr;
+
+use critical_section::Mutex;
+
+/// The installed configuration.
+static CONFIG: Mutex<Cell<Config>> = Mutex::new(Cell::new(Config::empty()));
+
+/// The source of a configuration.
+#[derive(Debug, Clone, Copy)]
+enum Source {
+    Pairs(&'static [(&'static str, &'static str)]),
+    Blob(&'static [u8]),
+}
+
+/// A read-only key-value configuration.
+#[derive(Debug, Clone, Copy)]
+pub struct Config(Source);
+
+impl Config {
+    /// Create an empty configuration.
+    pub const fn empty() -> Self {
+        Config(Source::Pairs(&[]))
+    }
+
+    /// Create a configuration out of a static table of pairs.
+    pub const fn from_pairs(pairs: &'static [(&'static str, &'static str)]) -> Self {
+        Config(Source::Pairs(pairs))
+    }
+
+    /// Create a configuration out of a blob of `key=value` entries.
+    ///
+    /// The entries are separated by NUL or newline characters, and the blob may be padded with
+    /// NUL characters. An entry without `=` is a flag, whose value is empty. Entries that are not
+    /// valid UTF-8 are ignored.
+    pub const fn from_blob(blob: &'static [u8]) -> Self {
+        Config(Source::Blob(blob))
+    }
+
+    /// Create a configuration out of a blob in a raw memory region (e.g. provided by the
+    /// bootloader).
+    ///
+    /// # Safety
+    ///
+    /// The memory region must be valid for reads of `len` bytes for the rest of the program, and
+    /// it must not be written meanwhile.
+    pub unsafe fn from_raw(ptr: *const u8, len: usize) -> Self {
+        Self::from_blob(core::slice::from_raw_parts(ptr, len))
+    }
+
+    /// Create a configuration out of the `.dedrv.config` linker section.
+    ///
+    /// The section is populated by statics of the application, such as:
+    ///
+    /// ```rust,ignore
+    /// #[used]
+    /// #[link_section = ".dedrv.config"]
+    /// static BOOTARGS: [u8; 17] = *b"/uart0.baud=9600\0";
+    /// ```
+    ///
+    /// On hosted targets, which do not use the linker script, the configuration is empty.
+    pub fn from_linker() -> Self {
+        #[cfg(target_os = "none")]
+        {
+            unsafe extern "C" {
+                static __DEDRV_MARKER_CONFIG_START: u8;
+                static __DEDRV_MARKER_CONFIG_END: u8;
+            }
+
+            let start = &raw const __DEDRV_MARKER_CONFIG_START;
+            let end = &raw const __DEDRV_MARKER_CONFIG_END;
+
+            // SAFETY: The markers are defined by the linker script and delimit the read-only
+            // configuration section.
+            unsafe { Self::from_raw(start, (end as usize).saturating_sub(start as usize)) }
+        }
+
+        #[cfg(not(target_os = "none"))]
+        Self::empty()
+    }
+
+    /// Get the value of the given key.
+    ///
+    /// If a key is defined more than once, the last definition wins, so that a configuration may
+    /// be overridden by appending entries.
+    pub fn get(&self, key: &str) -> Option<&'static str> {
+        match self.0 {
+            Source::Pairs(pairs) => pairs.iter().rev().find(|(k, _)| *k == key).map(|x| x.1),
+            Source::Blob(blob) => blob
+                .split(|&b| b == 0 || b == b'\n')
+                .rev()
+                .filter_map(|x| core::str::from_utf8(x).ok())
+                .filter(|x| !x.is_empty())
+                .map(|x| x.split_once('=').unwrap_or((x, "")))
+                .find(|(k, _)| *k == key)
+                .map(|x| x.1),
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+/// Install the configuration that is queried by [`get`] and [`parse`].
+pub fn install(config: Config) {
+    critical_section::with(|cs| CONFIG.borrow(cs).set(config));
+}
+
+/// Get the value of the given key in the installed configuration.
+pub fn get(key: &str) -> Option<&'static str> {
+    critical_section::with(|cs| CONFIG.borrow(cs).get()).get(key)
+}
+
+/// Get the value of the given key in the installed configuration, parsed as a `T`.
+///
+/// This returns `None` if the key is not defined or if its value cannot be parsed.
+pub fn parse<T: FromStr>(key: &str) -> Option<T> {
+    get(key).and_then(|x| x.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use googletest::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn it_should_get_value_from_pairs() -> googletest::Result<()> {
+        let config = Config::from_pairs(&[("/uart0.baud", "9600"), ("/uart0.baud", "115200")]);
+
+        verify_that!(config.get("/uart0.baud"), some(eq("115200")))?;
+        verify_that!(config.get("/uart1.baud"), none())
+    }
+
+    #[test]
+    fn it_should_get_value_from_blob() -> googletest::Result<()> {
+        let config = Config::from_blob(b"/uart0.baud=9600\0/spi0.dma\n/gpio0.pull=up\0\0\0");
+
+        verify_that!(config.get("/uart0.baud"), some(eq("9600")))?;
+        verify_that!(config.get("/spi0.dma"), some(eq("")))?;
+        verify_that!(config.get("/gpio0.pull"), some(eq("up")))?;
+        verify_that!(config.get(""), none())
+    }
+
+    #[test]
+    fn it_should_query_installed_config() -> googletest::Result<()> {
+        install(Config::from_blob(b"/uart0.baud=9600\0/uart0.parity=odd"));
+
+        verify_that!(parse::<u32>("/uart0.baud"), some(eq(9600)))?;
+        verify_that!(parse::<u32>("/uart0.parity"), none())?;
+        verify_that!(get("/uart0.parity"), some(eq("odd")))
+    }
+}
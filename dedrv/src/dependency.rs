@@ -0,0 +1,92 @@
+use crate::{tag, Accessor, ClassTag, Device, Driver, Error, Result, Slot};
+
+/// An accessor to another device, which is held in the driver state (e.g. a GPIO that drives the
+/// reset line of an external PHY).
+///
+/// The dependency is acquired by [`Driver::probe`] or [`Driver::init`], where the framework
+/// initializes the other device first if it has not been initialized yet, so the driver never
+/// operates a device that has not been brought up. Then it is released by [`Driver::cleanup`].
+/// The dependencies between devices must not be cyclic, and they are declared on the device with
+/// the `depends_on(...)` argument of the [`device`](crate::device) attribute, so that the init
+/// checks that each one belongs to an earlier stage (see [`stage`](crate::stage)).
+///
+/// The accessor is held in a [`Slot`], which is empty when zeroed, so a dependency has not been
+/// acquired once its device is created (see [`Device::new`]).
+pub struct Dependency<D: Driver + 'static, Tag = tag::NoTag>(Slot<Accessor<'static, D, Tag>>);
+
+// SAFETY: The accessor only refers to a static device, which is shared between threads (or
+// cores) through its own lock.
+unsafe impl<D: Driver, Tag> Send for Dependency<D, Tag> where Device<D>: Sync {}
+
+impl<D: Driver, Tag: ClassTag<D>> Dependency<D, Tag> {
+    /// Create a new dependency, which has not been acquired.
+    pub const fn new() -> Self {
+        Dependency(Slot::new())
+    }
+
+    /// Acquire an accessor to `device`, which is initialized first if needed (see
+    /// [`Device::try_init`]).
+    ///
+    /// This returns the error of the device init if it fails (e.g. its hardware is missing), or
+    /// [`Error::Busy`] if the limit of open accessors of the device is reached or if `device` is
+    /// being initialized (e.g. by another core).
+    pub fn acquire(&mut self, device: &'static Device<D>) -> Result<()> {
+        device.try_init()?;
+        self.0.write(device.try_accessor()?);
+        Ok(())
+    }
+
+    /// Release the accessor, if any.
+    pub fn release(&mut self) {
+        self.0.take();
+    }
+
+    /// Check whether the dependency has been acquired.
+    pub fn is_acquired(&self) -> bool {
+        self.0.is_init()
+    }
+
+    /// Get the accessor to the other device.
+    ///
+    /// This returns [`Error::NotReady`] if the dependency has not been acquired.
+    pub fn get(&mut self) -> Result<&mut Accessor<'static, D, Tag>> {
+        self.0.get_mut().ok_or(Error::NotReady)
+    }
+}
+
+impl<D: Driver, Tag: ClassTag<D>> Default for Dependency<D, Tag> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use googletest::prelude::*;
+
+    use super::*;
+    use crate::testing::NoopDriver;
+
+    #[test]
+    fn it_should_not_be_acquired_when_zeroed() -> googletest::Result<()> {
+        let mut dependency: Dependency<NoopDriver> = unsafe { core::mem::zeroed() };
+
+        verify_that!(dependency.is_acquired(), eq(false))?;
+        verify_that!(dependency.get().err(), some(eq(&Error::NotReady)))
+    }
+
+    #[test]
+    fn it_should_close_accessor_on_release() -> googletest::Result<()> {
+        static DEVICE: Device<NoopDriver> = Device::new();
+
+        let mut dependency: Dependency<NoopDriver> = Dependency::new();
+        dependency.acquire(&DEVICE)?;
+        verify_that!(DEVICE.accessors(), eq(1))?;
+
+        dependency.release();
+        verify_that!(
+            (dependency.is_acquired(), DEVICE.accessors()),
+            (eq(false), eq(0))
+        )
+    }
+}
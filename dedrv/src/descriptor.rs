@@ -0,0 +1,777 @@
+use core::mem::{align_of, size_of};
+use core::ptr::addr_of;
+
+use core::any::Any;
+use core::fmt::Write;
+
+use crate::path::Path;
+use crate::{
+    Accessor, Class, ClassId, ClassTag, Device, DeviceId, Driver, Error, Lifecycle, Result,
+};
+
+/// The magic number that starts every device descriptor (i.e. `DDRV` in ASCII).
+pub const DESCRIPTOR_MAGIC: u32 = u32::from_be_bytes(*b"DDRV");
+
+/// The version of the device descriptor layout.
+///
+/// This version must be bumped each time the layout of [`Descriptor`] changes, so that objects
+/// built against another version of the crate are detected at runtime.
+const LAYOUT_VERSION: u32 = 1;
+
+/// The features that add fields to [`Descriptor`], one bit each, so that objects built with
+/// another feature set are detected at runtime too.
+const LAYOUT_FEATURES: u32 = (cfg!(feature = "path-id") as u32)
+    | (cfg!(feature = "method-duration") as u32) << 1
+    | (cfg!(feature = "error-history") as u32) << 2;
+
+/// The version of the device descriptor layout, along with the features that change it.
+///
+/// The lower half is the version of the layout, and the upper half is the set of features that
+/// add fields to [`Descriptor`] (i.e. `path-id`, `method-duration` and `error-history`).
+pub const DESCRIPTOR_VERSION: u32 = LAYOUT_VERSION | LAYOUT_FEATURES << 16;
+
+/// The flags of a device, which tell [`init`](crate::init) how to handle it at boot.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct DeviceFlags(u32);
+
+impl DeviceFlags {
+    /// The device is optional, so a failed init does not count as a failure of the boot (see
+    /// [`InitReport::is_complete`](crate::InitReport::is_complete)), nor does it abort the boot.
+    pub const OPTIONAL: DeviceFlags = DeviceFlags(1 << 0);
+
+    /// The device ships in the image, but it is not initialized at boot until it is explicitly
+    /// enabled at runtime (see [`Descriptor::enable`]).
+    pub const DISABLED: DeviceFlags = DeviceFlags(1 << 1);
+
+    /// The device is initialized after every device of its registry that is not deferred (e.g.
+    /// a slow peripheral that is not needed to boot), in stage order.
+    pub const DEFER: DeviceFlags = DeviceFlags(1 << 2);
+
+    /// The device is a default one (e.g. of a board support package), which is overridden by any
+    /// other device at the same path (see the `weak` argument of [`device`](crate::device)).
+    pub const WEAK: DeviceFlags = DeviceFlags(1 << 3);
+
+    /// Get the empty set of flags.
+    pub const fn empty() -> Self {
+        DeviceFlags(0)
+    }
+
+    /// Get the union of two sets of flags.
+    pub const fn union(self, other: DeviceFlags) -> Self {
+        DeviceFlags(self.0 | other.0)
+    }
+
+    /// Check whether every flag of `other` is set.
+    pub const fn contains(&self, other: DeviceFlags) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Get the raw bits of the flags.
+    pub const fn bits(&self) -> u32 {
+        self.0
+    }
+}
+
+impl core::ops::BitOr for DeviceFlags {
+    type Output = DeviceFlags;
+
+    fn bitor(self, rhs: DeviceFlags) -> DeviceFlags {
+        self.union(rhs)
+    }
+}
+
+/// A type-erased reference to a device, which declares a dependency of another device (see
+/// [`Descriptor::with_dependencies`]).
+#[derive(Clone, Copy)]
+pub struct DeviceRef(&'static (dyn Any + Send + Sync));
+
+impl DeviceRef {
+    /// Create a new reference to `device`.
+    pub const fn new<D: Driver>(device: &'static Device<D>) -> Self
+    where
+        Device<D>: Send + Sync,
+    {
+        DeviceRef(device)
+    }
+
+    /// Get the identifier of the referenced device (see [`Device::id`]).
+    pub fn id(&self) -> DeviceId {
+        DeviceId::of(self.0)
+    }
+}
+
+/// Device descriptor to be stored in the `.dedrv.device.*` sections inside the linker script.
+#[repr(C)]
+pub struct Descriptor {
+    magic: u32,
+    version: u32,
+    path: &'static Path,
+    #[cfg(feature = "path-id")]
+    path_id: crate::PathId,
+    classes: &'static [ClassId],
+    dependencies: &'static [DeviceRef],
+    stage: u8,
+    flags: DeviceFlags,
+    driver: fn() -> &'static str,
+    lifecycle: fn(&Descriptor) -> Lifecycle,
+    init: fn(&Descriptor) -> Result<()>,
+    init_result: fn(&Descriptor) -> Option<Result<()>>,
+    reset: fn(&Descriptor) -> Result<()>,
+    save: fn(&Descriptor, &mut [u8]) -> Result<usize>,
+    restore: fn(&Descriptor, &[u8]) -> Result<()>,
+    selftest: fn(&Descriptor) -> Result<()>,
+    dump: fn(&Descriptor, &mut dyn Write) -> core::fmt::Result,
+    #[cfg(feature = "method-duration")]
+    max_duration: fn(&Descriptor) -> u64,
+    #[cfg(feature = "error-history")]
+    error_history: fn(&Descriptor) -> crate::history::History,
+    device: &'static (dyn Any + Send + Sync),
+}
+
+impl Descriptor {
+    /// Create a new device descriptor.
+    ///
+    /// The `path` is a unique and short string identifier for the device. It provides a key to
+    /// look up on the device in the static table (i.e. linker section). It is validated as a
+    /// [`Path`], so a malformed path fails the build of a static descriptor.
+    ///
+    /// The device is type-erased as `dyn Any`, and the driver hooks are trampolines that are
+    /// generated for `D`. As a result, the device is only ever downcast back to `Device<D>` (see
+    /// [`Descriptor::device`]).
+    pub const fn new<D: Driver>(path: &'static str, device: &'static Device<D>) -> Self
+    where
+        Device<D>: Send + Sync,
+    {
+        Descriptor {
+            magic: DESCRIPTOR_MAGIC,
+            version: DESCRIPTOR_VERSION,
+            path: Path::from_static(path),
+            #[cfg(feature = "path-id")]
+            path_id: Path::from_static(path).id(),
+            classes: &[],
+            dependencies: &[],
+            stage: 0,
+            flags: DeviceFlags::empty(),
+            driver: core::any::type_name::<D>,
+            lifecycle: lifecycle::<D>,
+            init: init::<D>,
+            init_result: init_result::<D>,
+            reset: reset::<D>,
+            save: save::<D>,
+            restore: restore::<D>,
+            selftest: selftest::<D>,
+            dump: dump::<D>,
+            #[cfg(feature = "method-duration")]
+            max_duration: max_duration::<D>,
+            #[cfg(feature = "error-history")]
+            error_history: error_history::<D>,
+            device,
+        }
+    }
+
+    /// Record the classes that are implemented by the driver of the device.
+    ///
+    /// The [`device`](crate::device) attribute records the classes that are listed in its
+    /// `classes(...)` argument.
+    pub const fn with_classes(mut self, classes: &'static [ClassId]) -> Self {
+        self.classes = classes;
+        self
+    }
+
+    /// Record the devices that the device depends on (see [`Dependency`](crate::Dependency)), which
+    /// must be initialized before it (see [`stage`](crate::stage)).
+    ///
+    /// The [`device`](crate::device) attribute records the devices that are listed in its
+    /// `depends_on(...)` argument.
+    pub const fn with_dependencies(mut self, dependencies: &'static [DeviceRef]) -> Self {
+        self.dependencies = dependencies;
+        self
+    }
+
+    /// Set the init stage of the device (see [`stage`](crate::stage)).
+    ///
+    /// The [`device`](crate::device) attribute sets the stage that is given by its `stage`
+    /// argument.
+    pub const fn with_stage(mut self, stage: u8) -> Self {
+        self.stage = stage;
+        self
+    }
+
+    /// Set the flags of the device (see [`DeviceFlags`]).
+    ///
+    /// The [`device`](crate::device) attribute sets the flags that are listed in its `flags(...)`
+    /// argument.
+    pub const fn with_flags(mut self, flags: DeviceFlags) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    /// The path of the device described by this descriptor.
+    #[inline(always)]
+    pub fn path(&self) -> &'static Path {
+        self.path
+    }
+
+    /// The type name of the driver of the device described by this descriptor.
+    #[inline(always)]
+    pub fn driver(&self) -> &'static str {
+        (self.driver)()
+    }
+
+    /// The current lifecycle of the device described by this descriptor.
+    #[inline(always)]
+    pub fn lifecycle(&self) -> Lifecycle {
+        (self.lifecycle)(self)
+    }
+
+    /// The init stage of the device described by this descriptor.
+    #[inline(always)]
+    pub fn stage(&self) -> u8 {
+        self.stage
+    }
+
+    /// The flags of the device described by this descriptor.
+    #[inline(always)]
+    pub fn flags(&self) -> DeviceFlags {
+        self.flags
+    }
+
+    /// The classes that have been recorded for the device described by this descriptor.
+    #[inline(always)]
+    pub fn classes(&self) -> &'static [ClassId] {
+        self.classes
+    }
+
+    /// The devices that have been recorded as dependencies of the device described by this
+    /// descriptor.
+    #[inline(always)]
+    pub fn dependencies(&self) -> &'static [DeviceRef] {
+        self.dependencies
+    }
+
+    /// Check whether the class of tag `Tag` has been recorded for the device described by this
+    /// descriptor.
+    pub fn supports<Tag: Class>(&self) -> bool {
+        self.classes.contains(&Tag::ID)
+    }
+
+    /// Get the device described by this descriptor, if its driver is `D`.
+    pub fn device<D: Driver + 'static>(&self) -> Option<&'static Device<D>> {
+        self.device.downcast_ref()
+    }
+
+    /// Get the identifier of the device described by this descriptor (see [`Device::id`]).
+    pub fn id(&self) -> DeviceId {
+        DeviceId::of(self.device)
+    }
+
+    /// Get a new accessor for the class of tag `Tag` on the device described by this descriptor,
+    /// if its driver is `D`.
+    ///
+    /// This is not available for a driver with limited accessors (see
+    /// [`Driver::LIMITED_ACCESSORS`]).
+    ///
+    /// # Panics
+    ///
+    /// Panics if an exclusive accessor of the device is open (see [`crate::ExclusiveAccessor`]).
+    pub fn accessor<D: Driver + 'static, Tag: ClassTag<D>>(
+        &self,
+    ) -> Option<Accessor<'static, D, Tag>> {
+        self.device::<D>().map(|x| x.accessor::<Tag>())
+    }
+
+    /// Probe then initialize the device described by this descriptor (see [`Device::try_init`]).
+    #[inline(always)]
+    pub(crate) fn init(&self) -> Result<()> {
+        (self.init)(self)
+    }
+
+    /// Enable the device described by this descriptor at runtime, i.e. probe then initialize it
+    /// if it has not been initialized yet (e.g. a device that is [`DeviceFlags::DISABLED`] at
+    /// boot).
+    ///
+    /// This returns the error of the probe if it fails.
+    pub fn enable(&self) -> Result<()> {
+        self.init()
+    }
+
+    /// Get the result of the last init of the device described by this descriptor, if any.
+    #[inline(always)]
+    pub fn init_result(&self) -> Option<Result<()>> {
+        (self.init_result)(self)
+    }
+
+    /// Re-initialize the device described by this descriptor (see [`Device::reset`]).
+    #[inline(always)]
+    pub fn reset(&self) -> Result<()> {
+        (self.reset)(self)
+    }
+
+    /// Save the state of the device described by this descriptor (see [`Driver::save`]).
+    #[inline(always)]
+    pub(crate) fn save(&self, buf: &mut [u8]) -> Result<usize> {
+        (self.save)(self, buf)
+    }
+
+    /// Restore the state of the device described by this descriptor (see [`Driver::restore`]).
+    #[inline(always)]
+    pub(crate) fn restore(&self, data: &[u8]) -> Result<()> {
+        (self.restore)(self, data)
+    }
+
+    /// Run the selftest of the device described by this descriptor (see [`Driver::selftest`]).
+    #[inline(always)]
+    pub fn selftest(&self) -> Result<()> {
+        (self.selftest)(self)
+    }
+
+    /// Write the snapshot of the driver state of the device described by this descriptor (see
+    /// [`Driver::dump`]).
+    #[inline(always)]
+    pub fn dump(&self, out: &mut dyn Write) -> core::fmt::Result {
+        (self.dump)(self, out)
+    }
+
+    /// Get the maximum class method duration of the device described by this descriptor.
+    #[cfg(feature = "method-duration")]
+    #[inline(always)]
+    pub(crate) fn max_duration(&self) -> u64 {
+        (self.max_duration)(self)
+    }
+
+    /// Get the error history of the device described by this descriptor.
+    #[cfg(feature = "error-history")]
+    #[inline(always)]
+    pub(crate) fn error_history(&self) -> crate::history::History {
+        (self.error_history)(self)
+    }
+
+    /// Check the header of the descriptor that `ptr` points to.
+    ///
+    /// # Safety
+    ///
+    /// The pointer must be valid for reads of `size_of::<Descriptor>()` bytes. However, the
+    /// content of the pointed memory is not required to be a valid descriptor, which is exactly
+    /// what this function checks before any other field is trusted.
+    unsafe fn validate(ptr: *const Descriptor, index: usize) -> Result<()> {
+        let magic = addr_of!((*ptr).magic).read_unaligned();
+        if magic != DESCRIPTOR_MAGIC {
+            return Err(Error::InvalidDescriptorMagic {
+                index,
+                found: magic,
+            });
+        }
+
+        let version = addr_of!((*ptr).version).read_unaligned();
+        if version != DESCRIPTOR_VERSION {
+            return Err(Error::DescriptorVersionMismatch {
+                index,
+                found: version,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// Get the device of a descriptor whose driver is known to be `D`.
+fn device<D: Driver + 'static>(desc: &Descriptor) -> &'static Device<D> {
+    desc.device::<D>()
+        .expect("descriptor trampoline called with another driver")
+}
+
+/// The trampoline to [`Device::lifecycle`].
+fn lifecycle<D: Driver + 'static>(desc: &Descriptor) -> Lifecycle {
+    device::<D>(desc).lifecycle()
+}
+
+/// The trampoline to [`Device::try_init`].
+fn init<D: Driver + 'static>(desc: &Descriptor) -> Result<()> {
+    device::<D>(desc).try_init()
+}
+
+/// The trampoline to [`Device::init_result`].
+fn init_result<D: Driver + 'static>(desc: &Descriptor) -> Option<Result<()>> {
+    device::<D>(desc).init_result()
+}
+
+/// The trampoline to [`Device::reset`].
+fn reset<D: Driver + 'static>(desc: &Descriptor) -> Result<()> {
+    device::<D>(desc).reset()
+}
+
+/// The trampoline to [`Device::save`].
+fn save<D: Driver + 'static>(desc: &Descriptor, buf: &mut [u8]) -> Result<usize> {
+    device::<D>(desc).save(buf)
+}
+
+/// The trampoline to [`Device::restore`].
+fn restore<D: Driver + 'static>(desc: &Descriptor, data: &[u8]) -> Result<()> {
+    device::<D>(desc).restore(data)
+}
+
+/// The trampoline to [`Device::selftest`].
+fn selftest<D: Driver + 'static>(desc: &Descriptor) -> Result<()> {
+    device::<D>(desc).selftest()
+}
+
+/// The trampoline to [`Device::dump`].
+fn dump<D: Driver + 'static>(desc: &Descriptor, out: &mut dyn Write) -> core::fmt::Result {
+    device::<D>(desc).dump(out)
+}
+
+/// The trampoline to [`Device::max_duration`].
+#[cfg(feature = "method-duration")]
+fn max_duration<D: Driver + 'static>(desc: &Descriptor) -> u64 {
+    device::<D>(desc).max_duration()
+}
+
+/// The trampoline to [`Device::error_history`].
+#[cfg(feature = "error-history")]
+fn error_history<D: Driver + 'static>(desc: &Descriptor) -> crate::history::History {
+    device::<D>(desc).error_history()
+}
+
+/// Validate the descriptor table that lies between `start` and `end`.
+///
+/// # Safety
+///
+/// The memory between `start` and `end` must be readable.
+pub(crate) unsafe fn validate_table<'a>(
+    start: *const Descriptor,
+    end: *const Descriptor,
+) -> Result<&'a [Descriptor]> {
+    let size = (end as usize).saturating_sub(start as usize);
+
+    if size % size_of::<Descriptor>() != 0 || (start as usize) % align_of::<Descriptor>() != 0 {
+        return Err(Error::CorruptedDescriptorTable);
+    }
+
+    let len = size / size_of::<Descriptor>();
+    for index in 0..len {
+        Descriptor::validate(start.add(index), index)?;
+    }
+
+    // SAFETY: At this point, every entry of the table has been checked to be a descriptor with the
+    // expected layout, so the table may be trusted.
+    let table = core::slice::from_raw_parts(start, len);
+
+    // The linker script sorts the descriptors by path, which is required by the lookup.
+    if let Some(index) = table.windows(2).position(|w| w[0].path > w[1].path) {
+        return Err(Error::UnsortedDescriptorTable { index: index + 1 });
+    }
+
+    Ok(table)
+}
+
+/// Get the descriptors of the devices at `path` in a table that is sorted by path.
+fn equal_range<'a>(table: &'a [Descriptor], path: &Path) -> &'a [Descriptor] {
+    let start = table.partition_point(|desc| desc.path < path);
+    let len = table[start..].partition_point(|desc| desc.path == path);
+    &table[start..start + len]
+}
+
+/// Check whether a descriptor of a table that is sorted by path is a weak default that is
+/// overridden by another device at the same path.
+pub(crate) fn is_overridden(table: &[Descriptor], desc: &Descriptor) -> bool {
+    desc.flags.contains(DeviceFlags::WEAK)
+        && equal_range(table, desc.path)
+            .iter()
+            .any(|x| !x.flags.contains(DeviceFlags::WEAK))
+}
+
+/// Look up the descriptor of the device at `path` in a table that is sorted by path.
+///
+/// A weak default device is only found if no other device overrides it.
+pub(crate) fn find<'a>(table: &'a [Descriptor], path: &Path) -> Result<&'a Descriptor> {
+    let found = equal_range(table, path);
+
+    found
+        .iter()
+        .find(|desc| !desc.flags.contains(DeviceFlags::WEAK))
+        .or(found.first())
+        .ok_or(Error::DeviceNotFound)
+}
+
+/// Look up the descriptor of the device whose path has the identifier `id` in a validated table.
+///
+/// The scan stops at the first device with the identifier, so the identifiers are expected to be
+/// unique, which is checked once at init (see [`check_ids`]) and by `dedrv-build`.
+#[cfg(feature = "path-id")]
+pub(crate) fn find_id(table: &[Descriptor], id: crate::PathId) -> Result<&Descriptor> {
+    // A weak default device comes after the device that overrides it, so the latter is found.
+    table
+        .iter()
+        .find(|desc| desc.path_id == id)
+        .ok_or(Error::DeviceNotFound)
+}
+
+/// The number of path identifiers that are sorted at once by [`check_ids`], in a stack buffer.
+#[cfg(feature = "path-id")]
+const ID_CHUNK_LEN: usize = 64;
+
+/// Check that no two devices of the given validated tables share a path identifier, except a
+/// weak default device and the device that overrides it, which share their path.
+///
+/// The identifiers are sorted by chunks of [`ID_CHUNK_LEN`], so the shared identifiers of a chunk
+/// are adjacent, while the identifiers of the following devices are looked up by binary search.
+/// Only the devices of a shared identifier have their paths compared.
+#[cfg(feature = "path-id")]
+pub(crate) fn check_ids(tables: &[&[Descriptor]]) -> Result<()> {
+    let descriptors = || tables.iter().flat_map(|x| x.iter());
+    let mut ids = [0u32; ID_CHUNK_LEN];
+    let mut start = 0;
+
+    loop {
+        let mut len = 0;
+        for desc in descriptors().skip(start).take(ID_CHUNK_LEN) {
+            ids[len] = desc.path_id.get();
+            len += 1;
+        }
+
+        if len == 0 {
+            return Ok(());
+        }
+
+        ids[..len].sort_unstable();
+        let chunk = &ids[..len];
+
+        let adjacent = chunk.windows(2).filter(|w| w[0] == w[1]).map(|w| w[0]);
+        let following = descriptors()
+            .skip(start + len)
+            .map(|x| x.path_id.get())
+            .filter(|x| chunk.binary_search(x).is_ok());
+
+        if adjacent.chain(following).any(|id| collides(tables, id)) {
+            return Err(Error::PathCollision);
+        }
+
+        start += len;
+    }
+}
+
+/// Check whether the devices of the given tables whose path identifier is `id` have different
+/// paths.
+#[cfg(feature = "path-id")]
+fn collides(tables: &[&[Descriptor]], id: u32) -> bool {
+    let mut paths = tables
+        .iter()
+        .flat_map(|x| x.iter())
+        .filter(|x| x.path_id.get() == id)
+        .map(|x| x.path);
+
+    let first = paths.next();
+    paths.any(|x| Some(x) != first)
+}
+
+#[cfg(test)]
+mod tests {
+    use googletest::prelude::*;
+
+    use super::*;
+    use crate::testing::NoopDriver;
+
+    static DEVICE: Device<NoopDriver> = Device::new();
+
+    #[test]
+    fn it_should_validate_table() -> googletest::Result<()> {
+        let table = [
+            Descriptor::new("/a", &DEVICE),
+            Descriptor::new("/b", &DEVICE),
+        ];
+
+        let range = table.as_ptr_range();
+        let table = unsafe { validate_table(range.start, range.end) };
+
+        verify_that!(table.map(|t| t.len()), ok(eq(&2)))
+    }
+
+    #[test]
+    fn it_should_reject_invalid_magic() -> googletest::Result<()> {
+        let mut table = [
+            Descriptor::new("/a", &DEVICE),
+            Descriptor::new("/b", &DEVICE),
+        ];
+        table[1].magic = 0xdeadbeef;
+
+        let range = table.as_ptr_range();
+        let result = unsafe { validate_table(range.start, range.end) }.map(|_| ());
+
+        verify_that!(
+            result,
+            err(eq(&Error::InvalidDescriptorMagic {
+                index: 1,
+                found: 0xdeadbeef
+            }))
+        )
+    }
+
+    #[test]
+    fn it_should_reject_version_mismatch() -> googletest::Result<()> {
+        let mut table = [Descriptor::new("/a", &DEVICE)];
+        table[0].version = DESCRIPTOR_VERSION + 1;
+
+        let range = table.as_ptr_range();
+        let result = unsafe { validate_table(range.start, range.end) }.map(|_| ());
+
+        verify_that!(
+            result,
+            err(eq(&Error::DescriptorVersionMismatch {
+                index: 0,
+                found: DESCRIPTOR_VERSION + 1
+            }))
+        )
+    }
+
+    #[test]
+    fn it_should_reject_feature_mismatch() -> googletest::Result<()> {
+        // The same layout version, as built with another set of the `error-history` feature.
+        let version = DESCRIPTOR_VERSION ^ 1 << 18;
+
+        let mut table = [Descriptor::new("/a", &DEVICE)];
+        table[0].version = version;
+
+        let range = table.as_ptr_range();
+        let result = unsafe { validate_table(range.start, range.end) }.map(|_| ());
+
+        verify_that!(
+            result,
+            err(eq(&Error::DescriptorVersionMismatch {
+                index: 0,
+                found: version
+            }))
+        )
+    }
+
+    #[test]
+    fn it_should_reject_unsorted_table() -> googletest::Result<()> {
+        let table = [
+            Descriptor::new("/a", &DEVICE),
+            Descriptor::new("/c", &DEVICE),
+            Descriptor::new("/b", &DEVICE),
+        ];
+
+        let range = table.as_ptr_range();
+        let result = unsafe { validate_table(range.start, range.end) }.map(|_| ());
+
+        verify_that!(
+            result,
+            err(eq(&Error::UnsortedDescriptorTable { index: 2 }))
+        )
+    }
+
+    #[test]
+    fn it_should_find_descriptor_by_path() -> googletest::Result<()> {
+        let table = [
+            Descriptor::new("/gpio0", &DEVICE),
+            Descriptor::new("/gpio1", &DEVICE),
+            Descriptor::new("/uart0", &DEVICE),
+        ];
+
+        let path = Path::from_static;
+
+        verify_that!(
+            find(&table, path("/gpio1")).map(|d| d.path().as_str()),
+            ok(eq(&"/gpio1"))
+        )?;
+        verify_that!(
+            find(&table, path("/uart1")).map(|d| d.path()),
+            err(eq(&Error::DeviceNotFound))
+        )
+    }
+
+    #[test]
+    fn it_should_override_weak_descriptor() -> googletest::Result<()> {
+        let table = [
+            Descriptor::new("/gpio0", &DEVICE).with_flags(DeviceFlags::WEAK),
+            Descriptor::new("/uart0", &DEVICE).with_stage(1),
+            Descriptor::new("/uart0", &DEVICE).with_flags(DeviceFlags::WEAK),
+        ];
+
+        let range = table.as_ptr_range();
+        let table = unsafe { validate_table(range.start, range.end) }?;
+        let path = Path::from_static;
+
+        verify_that!(find(table, path("/uart0")).map(|d| d.stage()), ok(eq(&1)))?;
+        verify_that!(
+            find(table, path("/gpio0")).map(|d| d.path().as_str()),
+            ok(eq(&"/gpio0"))
+        )?;
+        verify_that!(
+            table
+                .iter()
+                .map(|d| is_overridden(table, d))
+                .collect::<Vec<_>>(),
+            elements_are![eq(&false), eq(&false), eq(&true)]
+        )
+    }
+
+    #[test]
+    fn it_should_reject_truncated_table() -> googletest::Result<()> {
+        let table = [Descriptor::new("/a", &DEVICE)];
+
+        let start = table.as_ptr();
+        let end = (start as *const u8).wrapping_add(size_of::<Descriptor>() - 1) as *const _;
+        let result = unsafe { validate_table(start, end) }.map(|_| ());
+
+        verify_that!(result, err(eq(&Error::CorruptedDescriptorTable)))
+    }
+
+    #[test]
+    fn it_should_discover_device_classes() -> googletest::Result<()> {
+        struct Probe;
+
+        impl Class for Probe {
+            const ID: ClassId = ClassId::new("tests::Probe");
+            const NAME: &'static str = "Probe";
+        }
+
+        struct OtherDriver;
+
+        impl Driver for OtherDriver {
+            type StateType = ();
+
+            fn init(_: &crate::StateLock<Self>) {}
+            fn cleanup(_: &crate::StateLock<Self>) {}
+        }
+
+        let desc = Descriptor::new("/a", &DEVICE).with_classes(&[Probe::ID]);
+
+        verify_that!(desc.supports::<Probe>(), eq(true))?;
+        verify_that!(desc.device::<NoopDriver>().is_some(), eq(true))?;
+        verify_that!(desc.device::<OtherDriver>().is_some(), eq(false))?;
+        verify_that!(
+            desc.accessor::<NoopDriver, crate::tag::NoTag>().is_some(),
+            eq(true)
+        )
+    }
+
+    #[test]
+    fn it_should_init_device_through_trampoline() -> googletest::Result<()> {
+        static DEVICE: Device<NoopDriver> = Device::new();
+
+        let desc = Descriptor::new("/a", &DEVICE);
+        desc.init()?;
+
+        verify_that!(DEVICE.lifecycle(), eq(crate::Lifecycle::Initialized))?;
+        verify_that!(desc.init_result(), some(ok(eq(&()))))
+    }
+
+    #[test]
+    #[cfg(feature = "path-id")]
+    fn it_should_check_ids_across_chunks() -> googletest::Result<()> {
+        let path = |x: String| -> &'static str { Box::leak(x.into_boxed_str()) };
+        let mut table: Vec<_> = (0..2 * ID_CHUNK_LEN)
+            .map(|i| Descriptor::new(path(format!("/dev{i}")), &DEVICE))
+            .collect();
+        table.push(Descriptor::new("/dev1", &DEVICE).with_flags(DeviceFlags::WEAK));
+
+        verify_that!(check_ids(&[&table]), ok(eq(&())))?;
+
+        // These paths share the same FNV-1a hash, in the first and the last chunk.
+        table[0] = Descriptor::new("/dev1399086", &DEVICE);
+        table.push(Descriptor::new("/dev64059", &DEVICE));
+        verify_that!(check_ids(&[&table]), err(eq(&Error::PathCollision)))
+    }
+}
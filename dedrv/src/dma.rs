@@ -0,0 +1,236 @@
+//! The split transactions of the classes, for zero-copy I/O with DMA.
+//!
+//! A class method that returns a [`Transfer`] is a split transaction: the driver only starts the
+//! transfer (e.g. programs a DMA channel with the buffer), then the interrupt handler of the
+//! driver completes it, while the caller does something else in the meantime. The transfer is
+//! named by its full path, so another type named `Transfer` (e.g. the DMA transfer of a HAL) may
+//! still be returned as is:
+//!
+//! ```rust,ignore
+//! #[dedrv::class]
+//! pub trait Uart {
+//!     fn start_read(&mut self, buf: &'static mut [u8]) -> dedrv::dma::Transfer<'_, &'static mut [u8]>;
+//! }
+//!
+//! let mut uart = UART0.uart();
+//! let transfer = uart.start_read(BUF.take());
+//! // ...
+//! let (result, buf) = transfer.wait();
+//! ```
+//!
+//! The buffer is moved into the transfer, so it cannot be touched until the transfer is complete,
+//! then it is given back by [`Transfer::wait`]. The buffer is the last argument of the method,
+//! which the driver receives by mutable reference, along with the driver state:
+//!
+//! ```rust,ignore
+//! impl driver::Uart for UartDriver {
+//!     fn start_read(state: &StateLock<Self>, buf: &mut &'static mut [u8]) -> Result<()> {
+//!         // Program the DMA channel with `buf.as_mut_ptr()` and `buf.len()`.
+//!     }
+//! }
+//! ```
+//!
+//! The driver keeps a [`Completion`] in its state (see [`Transferred`]), which its interrupt
+//! handler signals with [`Completion::complete`]. The interrupt handler is the one that releases
+//! the buffer, so a transfer that is dropped before its completion leaves the buffer to the
+//! hardware for good.
+
+use core::future::poll_fn;
+use core::task::{Poll, Waker};
+
+use crate::{Device, Driver, Error, Result, Slot, StateLock};
+
+/// A buffer whose memory does not move when the buffer itself is moved, so it may be handed to
+/// the hardware (e.g. a DMA channel) while it is owned by a [`Transfer`].
+///
+/// # Safety
+///
+/// The memory that is referred by the buffer must stay valid and at the same address for the
+/// rest of the program, whether the buffer is moved, forgotten or dropped.
+pub unsafe trait Buffer: 'static {}
+
+// SAFETY: The memory is borrowed for the rest of the program.
+unsafe impl<T: ?Sized + 'static> Buffer for &'static mut T {}
+
+// SAFETY: The memory is borrowed for the rest of the program.
+unsafe impl<T: ?Sized + 'static> Buffer for &'static T {}
+
+/// The completion of the transfer of a device, which lives in the driver state.
+///
+/// A device runs one transfer at a time. A zeroed completion is a valid completion without any
+/// transfer.
+#[derive(Debug)]
+pub struct Completion {
+    pending: bool,
+    result: Slot<Result<()>>,
+    waker: Slot<Waker>,
+}
+
+impl Completion {
+    /// Create a new completion without any transfer.
+    pub const fn new() -> Self {
+        Completion {
+            pending: false,
+            result: Slot::new(),
+            waker: Slot::new(),
+        }
+    }
+
+    /// Check whether a transfer is in progress.
+    pub fn is_pending(&self) -> bool {
+        self.pending
+    }
+
+    /// Complete the transfer in progress with its result (e.g. from the interrupt handler of the
+    /// driver), then wake up the task that awaits it, if any.
+    ///
+    /// This does nothing if no transfer is in progress.
+    pub fn complete(&mut self, result: Result<()>) {
+        if !core::mem::take(&mut self.pending) {
+            return;
+        }
+
+        self.result.write(result);
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+
+    /// Begin a new transfer, where the result of the previous one is discarded.
+    ///
+    /// This returns [`Error::Busy`] if a transfer is in progress.
+    fn begin(&mut self) -> Result<()> {
+        if self.pending {
+            return Err(Error::Busy);
+        }
+
+        self.pending = true;
+        self.result.take();
+        self.waker.take();
+        Ok(())
+    }
+
+    /// Abort the transfer in progress, which the driver failed to start.
+    fn abort(&mut self) {
+        self.pending = false;
+    }
+
+    /// Get the result of the transfer once it is complete, or register the waker of the task
+    /// that awaits it.
+    fn poll(&mut self, waker: Option<&Waker>) -> Option<Result<()>> {
+        if let Some(result) = self.result.get() {
+            return Some(result.clone());
+        }
+
+        if let Some(waker) = waker.filter(|_| self.pending) {
+            self.waker.write(waker.clone());
+        }
+
+        None
+    }
+}
+
+impl Default for Completion {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A driver whose state holds the completion of its transfers, which is required by the classes
+/// with split transactions.
+pub trait Transferred: Driver {
+    /// Get the completion out of the driver state.
+    fn completion(state: &mut Self::StateType) -> &mut Completion;
+}
+
+/// The type-erased completion of a device, so a transfer does not name the driver.
+trait Channel {
+    /// Get the result of the transfer once it is complete (see [`Completion::poll`]).
+    fn poll(&self, waker: Option<&Waker>) -> Option<Result<()>>;
+}
+
+impl<D: Transferred> Channel for Device<D> {
+    fn poll(&self, waker: Option<&Waker>) -> Option<Result<()>> {
+        self.with_completion(|x| x.poll(waker))
+    }
+}
+
+impl<D: Transferred> Device<D> {
+    /// Call `f` with the completion of the device, within a critical section.
+    fn with_completion<R>(&self, f: impl FnOnce(&mut Completion) -> R) -> R {
+        critical_section::with(|cs| f(D::completion(&mut self.state.borrow_ref_mut(cs))))
+    }
+}
+
+/// A transfer in progress, which owns its buffer until it is complete.
+#[must_use = "the buffer is only given back by waiting for the transfer"]
+pub struct Transfer<'a, B: Buffer> {
+    channel: &'a dyn Channel,
+    buf: B,
+    failed: Option<Error>,
+}
+
+impl<'a, B: Buffer> Transfer<'a, B> {
+    /// Start a transfer of `buf` on `device` with the `start` function of its driver, which is
+    /// how the class methods that return a transfer are implemented.
+    ///
+    /// A transfer that cannot be started is complete right away, with the error of the driver or
+    /// [`Error::Busy`] if another transfer is in progress on the device.
+    pub fn start<D: Transferred>(
+        device: &'a Device<D>,
+        mut buf: B,
+        start: impl FnOnce(&StateLock<D>, &mut B) -> Result<()>,
+    ) -> Self {
+        let failed = match device.with_completion(Completion::begin) {
+            Ok(()) => start(&device.state, &mut buf)
+                .inspect_err(|_| device.with_completion(Completion::abort))
+                .err(),
+            Err(e) => Some(e),
+        };
+
+        Transfer {
+            channel: device,
+            buf,
+            failed,
+        }
+    }
+
+    /// Check whether the transfer is complete, without waiting.
+    pub fn is_done(&self) -> bool {
+        self.try_result().is_some()
+    }
+
+    /// Wait for the transfer to complete, then give the buffer back with the result.
+    pub fn wait(self) -> (Result<()>, B) {
+        loop {
+            if let Some(result) = self.try_result() {
+                return (result, self.buf);
+            }
+
+            core::hint::spin_loop();
+        }
+    }
+
+    /// Wait for the transfer to complete like [`Transfer::wait`], without blocking the other
+    /// tasks.
+    pub async fn wait_async(self) -> (Result<()>, B) {
+        let result = poll_fn(|cx| match &self.failed {
+            Some(e) => Poll::Ready(Err(e.clone())),
+            None => match self.channel.poll(Some(cx.waker())) {
+                Some(result) => Poll::Ready(result),
+                None => Poll::Pending,
+            },
+        })
+        .await;
+
+        (result, self.buf)
+    }
+
+    /// Get the result of the transfer, if it is complete.
+    fn try_result(&self) -> Option<Result<()>> {
+        match &self.failed {
+            Some(e) => Some(Err(e.clone())),
+            None => self.channel.poll(None),
+        }
+    }
+}
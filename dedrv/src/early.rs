@@ -0,0 +1,102 @@
+//! The early console, which is usable while the devices are being initialized.
+//!
+//! An early device (see the `early` option of the [`device`](crate::device) attribute) is
+//! initialized before every other device. Its driver may then register a console with
+//! [`set_console`], so that [`early_print!`](crate::early_print) works during
+//! [`init`](crate::init), and failures in device bring-up can be logged rather than ending in a
+//! silent hang.
+
+use core::cell::Cell;
+use core::fmt::{Arguments, Write};
+
+use critical_section::Mutex;
+
+/// A function that writes a string to the early console.
+pub type ConsoleFn = fn(&str);
+
+/// The function that writes to the early console, if any.
+static CONSOLE: Mutex<Cell<Option<ConsoleFn>>> = Mutex::new(Cell::new(None));
+
+/// Register the function that writes a string to the early console.
+///
+/// The function must not rely on any device that is not initialized yet, and it should be
+/// blocking (e.g. polling a UART transmitter) because nothing else runs meanwhile.
+pub fn set_console(write: ConsoleFn) {
+    critical_section::with(|cs| CONSOLE.borrow(cs).set(Some(write)));
+}
+
+/// Unregister the early console (e.g. when the regular logger takes over).
+pub fn clear_console() {
+    critical_section::with(|cs| CONSOLE.borrow(cs).set(None));
+}
+
+/// Check whether an early console is registered.
+pub fn has_console() -> bool {
+    critical_section::with(|cs| CONSOLE.borrow(cs).get()).is_some()
+}
+
+/// A writer to the early console.
+pub(crate) struct Console(ConsoleFn);
+
+impl Write for Console {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        (self.0)(s);
+        Ok(())
+    }
+}
+
+/// Get a writer to the early console, if any.
+///
+/// The console is called outside of the critical section, so it may use one on its own.
+pub(crate) fn console() -> Option<Console> {
+    critical_section::with(|cs| CONSOLE.borrow(cs).get()).map(Console)
+}
+
+/// Print formatted arguments to the early console, or do nothing if there is none.
+///
+/// This is the implementation of [`early_print!`](crate::early_print).
+pub fn print(args: Arguments<'_>) {
+    if let Some(mut console) = console() {
+        let _ = console.write_fmt(args);
+    }
+}
+
+/// Print to the early console, which works during [`init`](crate::init).
+///
+/// This does nothing until an early device registers a console with
+/// [`early::set_console`](crate::early::set_console).
+#[macro_export]
+macro_rules! early_print {
+    ($($arg:tt)*) => {
+        $crate::early::print(::core::format_args!($($arg)*))
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use googletest::prelude::*;
+
+    use super::*;
+
+    static OUTPUT: Mutex<String> = Mutex::new(String::new());
+
+    fn console(s: &str) {
+        OUTPUT.lock().unwrap().push_str(s);
+    }
+
+    #[test]
+    fn it_should_print_to_early_console() -> googletest::Result<()> {
+        early_print!("lost {}\n", 0);
+
+        set_console(console);
+        verify_that!(has_console(), eq(true))?;
+        early_print!("init {} ({:#x})\n", "/uart0", 16);
+
+        clear_console();
+        early_print!("lost {}\n", 1);
+
+        verify_that!(*OUTPUT.lock().unwrap(), eq("init /uart0 (0x10)\n"))
+    }
+}
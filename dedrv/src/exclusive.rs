@@ -0,0 +1,106 @@
+use core::mem::ManuallyDrop;
+use core::ops::{Deref, DerefMut};
+
+use crate::{Accessor, ClassTag, Device, Driver, Error, Lifecycle, Result};
+
+/// The only open accessor of a device, which keeps any other accessor from being opened while it
+/// lives (e.g. while a task configures the device during setup).
+///
+/// An exclusive accessor is used as a regular [`Accessor`], which it dereferences to. Once the
+/// configuration is done, it is downgraded into a regular accessor with
+/// [`ExclusiveAccessor::downgrade`], so the device is shared again without re-plumbing types. A
+/// regular accessor is upgraded back with [`Accessor::upgrade`], as long as it is the only open
+/// accessor of its device.
+pub struct ExclusiveAccessor<'d, D: Driver + 'static, Tag = crate::tag::NoTag> {
+    accessor: ManuallyDrop<Accessor<'d, D, Tag>>,
+}
+
+impl<'d, D: Driver, Tag> ExclusiveAccessor<'d, D, Tag> {
+    /// Downgrade into a regular accessor, so other accessors may be opened again.
+    pub fn downgrade(self) -> Accessor<'d, D, Tag> {
+        let mut this = ManuallyDrop::new(self);
+        this.accessor.inner().clear_exclusive();
+
+        // SAFETY: The accessor is moved out once, since the exclusive accessor is not dropped.
+        unsafe { ManuallyDrop::take(&mut this.accessor) }
+    }
+}
+
+impl<'d, D: Driver, Tag> Deref for ExclusiveAccessor<'d, D, Tag> {
+    type Target = Accessor<'d, D, Tag>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.accessor
+    }
+}
+
+impl<D: Driver, Tag> DerefMut for ExclusiveAccessor<'_, D, Tag> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.accessor
+    }
+}
+
+impl<D: Driver, Tag> Drop for ExclusiveAccessor<'_, D, Tag> {
+    fn drop(&mut self) {
+        self.accessor.inner().clear_exclusive();
+
+        // SAFETY: The accessor is dropped once, along with the exclusive accessor.
+        unsafe { ManuallyDrop::drop(&mut self.accessor) }
+    }
+}
+
+impl<'d, D: Driver, Tag> Accessor<'d, D, Tag> {
+    /// Upgrade into an exclusive accessor, if this is the only open accessor of the device.
+    ///
+    /// Otherwise, the device is busy, and the accessor is given back as an error.
+    pub fn upgrade(self) -> core::result::Result<ExclusiveAccessor<'d, D, Tag>, Self> {
+        match self.inner().try_set_exclusive() {
+            true => Ok(ExclusiveAccessor {
+                accessor: ManuallyDrop::new(self),
+            }),
+            false => Err(self),
+        }
+    }
+}
+
+impl<D: Driver> Device<D> {
+    /// Get the only open accessor of this device for the given class.
+    ///
+    /// This returns [`Error::NotReady`] if the driver has not been initialized on this device, or
+    /// [`Error::Busy`] if another accessor is open.
+    pub fn exclusive_accessor<Tag: ClassTag<D>>(&self) -> Result<ExclusiveAccessor<'_, D, Tag>> {
+        if self.lifecycle() != Lifecycle::Initialized {
+            return Err(Error::NotReady);
+        }
+
+        Accessor::try_new(self)?.upgrade().map_err(|_| Error::Busy)
+    }
+
+    /// Check whether an exclusive accessor of this device is open.
+    pub fn is_exclusive(&self) -> bool {
+        critical_section::with(|cs| self.exclusive.borrow(cs).get())
+    }
+
+    /// Mark the single open accessor of this device as exclusive, then return whether it could.
+    fn try_set_exclusive(&self) -> bool {
+        critical_section::with(|cs| {
+            let exclusive = self.exclusive.borrow(cs);
+            let single = self.accessors.borrow(cs).get() == 1 && !exclusive.get();
+            if single {
+                exclusive.set(true);
+            }
+            single
+        })
+    }
+
+    /// Mark this device as exclusive on behalf of the framework (e.g. for a [`Device::reset`]),
+    /// then return whether it could, i.e. unless it is exclusive already.
+    pub(crate) fn try_claim_exclusive(&self) -> bool {
+        critical_section::with(|cs| !self.exclusive.borrow(cs).replace(true))
+    }
+
+    /// Mark the exclusive accessor of this device as a regular one.
+    pub(crate) fn clear_exclusive(&self) {
+        critical_section::with(|cs| self.exclusive.borrow(cs).set(false))
+    }
+}
@@ -0,0 +1,171 @@
+//! The external interrupt (EXTI) controller class, and its demultiplexer.
+//!
+//! On most microcontrollers, several GPIO lines share a single interrupt vector (e.g. `EXTI9_5`
+//! for the lines 5 to 9). The [`ExtiController`] class lets the pin users register a handler per
+//! line through an accessor, while the driver stores these handlers in a [`Lines`] table of its
+//! state. The interrupt handler of each shared vector then calls [`dispatch`] with the mask of
+//! its lines:
+//!
+//! ```rust,ignore
+//! use dedrv::exti::{Edge, ExtiControllerExt};
+//!
+//! EXTI.exti_controller().listen(7, Edge::Falling, on_button)?;
+//!
+//! #[interrupt]
+//! fn EXTI9_5() {
+//!     dedrv::exti::dispatch(&EXTI.exti_controller(), 0x0000_03e0);
+//! }
+//! ```
+//!
+//! The pending flags are acknowledged before the handlers are called, and the handlers are called
+//! outside of the critical section of the device, so they may use the controller themselves (e.g.
+//! to stop listening to their line).
+
+use crate::{Accessor, Error, Result};
+
+/// The handler of an external interrupt line, which is given the line number.
+pub type Handler = fn(line: u8);
+
+/// The edge that triggers an external interrupt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Edge {
+    /// The rising edge of the line.
+    Rising,
+
+    /// The falling edge of the line.
+    Falling,
+
+    /// Both edges of the line.
+    Both,
+}
+
+/// An external interrupt controller, whose lines may be shared between interrupt vectors.
+#[crate::class]
+pub trait ExtiController {
+    /// Register the handler of a line, then enable its interrupt on the given edge.
+    ///
+    /// This returns [`Error::InvalidLine`] if the line does not exist, or [`Error::Busy`] if a
+    /// handler is already registered for the line.
+    fn listen(&mut self, line: u8, edge: Edge, handler: Handler) -> Result<()>;
+
+    /// Disable the interrupt of a line, then unregister its handler.
+    fn unlisten(&mut self, line: u8);
+
+    /// Acknowledge the pending lines among `mask`, then return them as a mask.
+    fn take_pending(&self, mask: u32) -> u32;
+
+    /// Get the handler that is registered for a line.
+    fn handler(&self, line: u8) -> Option<Handler>;
+}
+
+/// The table of line handlers, to be stored in the state of a controller driver.
+///
+/// A table with `N` lines supports at most 32 lines, which is the width of the masks. A zeroed
+/// table is a valid empty table.
+#[derive(Debug, Clone, Copy)]
+pub struct Lines<const N: usize = 16> {
+    handlers: [Option<Handler>; N],
+}
+
+impl<const N: usize> Lines<N> {
+    /// Create a new table without any handler.
+    pub const fn new() -> Self {
+        const { assert!(N <= 32, "an EXTI controller supports at most 32 lines") };
+        Lines {
+            handlers: [None; N],
+        }
+    }
+
+    /// Register the handler of a line.
+    ///
+    /// This returns [`Error::InvalidLine`] if the line does not exist, or [`Error::Busy`] if a
+    /// handler is already registered for the line.
+    pub fn set(&mut self, line: u8, handler: Handler) -> Result<()> {
+        match self.handlers.get_mut(line as usize) {
+            None => Err(Error::InvalidLine),
+            Some(Some(_)) => Err(Error::Busy),
+            Some(x) => {
+                *x = Some(handler);
+                Ok(())
+            }
+        }
+    }
+
+    /// Unregister the handler of a line, then return it.
+    pub fn clear(&mut self, line: u8) -> Option<Handler> {
+        self.handlers.get_mut(line as usize)?.take()
+    }
+
+    /// Get the handler that is registered for a line.
+    pub fn get(&self, line: u8) -> Option<Handler> {
+        self.handlers.get(line as usize).copied().flatten()
+    }
+
+    /// The mask of the lines that have a handler.
+    pub fn mask(&self) -> u32 {
+        self.handlers
+            .iter()
+            .enumerate()
+            .filter(|(_, x)| x.is_some())
+            .fold(0, |acc, (i, _)| acc | 1 << i)
+    }
+}
+
+impl<const N: usize> Default for Lines<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Demultiplex the pending lines among `mask` to their handlers.
+///
+/// This is meant to be called by the interrupt handler of a (possibly shared) vector, with the
+/// mask of the lines of this vector. The pending lines are acknowledged even if they have no
+/// handler, so a spurious interrupt does not fire forever. This returns the mask of the lines
+/// that have been handled.
+pub fn dispatch<D: driver::ExtiController>(
+    exti: &Accessor<'_, D, tag::ExtiController>,
+    mask: u32,
+) -> u32 {
+    let pending = exti.take_pending(mask);
+
+    (0..32u8)
+        .filter(|&line| pending & 1 << line != 0)
+        .filter_map(|line| exti.handler(line).map(|f| (line, f)))
+        .fold(0, |acc, (line, f)| {
+            f(line);
+            acc | 1 << line
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use googletest::prelude::*;
+
+    use super::*;
+
+    fn noop(_line: u8) {}
+
+    #[test]
+    fn it_should_be_valid_when_zeroed() -> googletest::Result<()> {
+        let lines: Lines<4> = unsafe { core::mem::zeroed() };
+
+        verify_that!(lines.get(0).is_none(), eq(true))?;
+        verify_that!(lines.mask(), eq(0))
+    }
+
+    #[test]
+    fn it_should_register_one_handler_per_line() -> googletest::Result<()> {
+        let mut lines: Lines<4> = Lines::new();
+
+        verify_that!(lines.set(1, noop), ok(eq(&())))?;
+        verify_that!(lines.set(3, noop), ok(eq(&())))?;
+        verify_that!(lines.set(1, noop), err(eq(&Error::Busy)))?;
+        verify_that!(lines.set(4, noop), err(eq(&Error::InvalidLine)))?;
+        verify_that!(lines.mask(), eq(0b1010))?;
+
+        verify_that!(lines.clear(1).is_some(), eq(true))?;
+        verify_that!(lines.clear(1).is_none(), eq(true))?;
+        verify_that!(lines.mask(), eq(0b1000))
+    }
+}
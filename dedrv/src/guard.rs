@@ -0,0 +1,127 @@
+use core::cell::{Ref, RefCell, RefMut};
+use core::fmt::Display;
+use core::ops::{Deref, DerefMut};
+
+use critical_section::{CriticalSection, Mutex};
+
+use crate::{Error, Result};
+
+/// A borrow of a driver internal state within a critical section.
+///
+/// A class method may return a guard (e.g. `fn rx_buffer<'cs>(&'cs self, cs: CriticalSection<'cs>)
+/// -> StateGuard<'cs, [u8; 64]>`), so callers can inspect a driver buffer without copying it. The
+/// driver implementation creates the guard out of its state with [`StateGuard::new`], then narrows
+/// it down with [`StateGuard::map`].
+///
+/// The guard borrows the critical section of the caller (i.e. the token given by
+/// `critical_section::with`), so it cannot outlive it, and it should be kept as short-lived as
+/// possible because interrupts are masked meanwhile (on single-core targets).
+pub struct StateGuard<'cs, T: ?Sized> {
+    value: Ref<'cs, T>,
+}
+
+impl<'cs, T> StateGuard<'cs, T> {
+    /// Borrow the lock-protected state within the given critical section.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the state is currently mutably borrowed.
+    pub fn new(state: &'cs Mutex<RefCell<T>>, cs: CriticalSection<'cs>) -> Self {
+        StateGuard {
+            value: state.borrow(cs).borrow(),
+        }
+    }
+
+    /// Try to borrow the lock-protected state within the given critical section.
+    ///
+    /// This returns [`Error::Busy`] instead of panicking if the state is mutably borrowed.
+    pub fn try_new(state: &'cs Mutex<RefCell<T>>, cs: CriticalSection<'cs>) -> Result<Self> {
+        match state.borrow(cs).try_borrow() {
+            Ok(value) => Ok(StateGuard { value }),
+            Err(_) => Err(Error::Busy),
+        }
+    }
+}
+
+impl<'cs, T: ?Sized> StateGuard<'cs, T> {
+    /// Make a new guard for a component of the borrowed state (e.g. a buffer of the state).
+    ///
+    /// This is an associated function that needs to be used as `StateGuard::map(...)`, so it does
+    /// not conflict with a method of the same name on the borrowed state.
+    pub fn map<U: ?Sized>(guard: Self, f: impl FnOnce(&T) -> &U) -> StateGuard<'cs, U> {
+        StateGuard {
+            value: Ref::map(guard.value, f),
+        }
+    }
+}
+
+impl<T: ?Sized> Deref for StateGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T: ?Sized + Display> Display for StateGuard<'_, T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+/// A mutable borrow of a driver internal state within a critical section.
+///
+/// This is the mutable counterpart of [`StateGuard`], which borrows the critical section of the
+/// caller in the same way.
+pub struct StateGuardMut<'cs, T: ?Sized> {
+    value: RefMut<'cs, T>,
+}
+
+impl<'cs, T> StateGuardMut<'cs, T> {
+    /// Mutably borrow the lock-protected state within the given critical section.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the state is currently borrowed.
+    pub fn new(state: &'cs Mutex<RefCell<T>>, cs: CriticalSection<'cs>) -> Self {
+        StateGuardMut {
+            value: state.borrow(cs).borrow_mut(),
+        }
+    }
+
+    /// Try to mutably borrow the lock-protected state within the given critical section.
+    ///
+    /// This returns [`Error::Busy`] instead of panicking if the state is borrowed.
+    pub fn try_new(state: &'cs Mutex<RefCell<T>>, cs: CriticalSection<'cs>) -> Result<Self> {
+        match state.borrow(cs).try_borrow_mut() {
+            Ok(value) => Ok(StateGuardMut { value }),
+            Err(_) => Err(Error::Busy),
+        }
+    }
+}
+
+impl<'cs, T: ?Sized> StateGuardMut<'cs, T> {
+    /// Make a new guard for a component of the mutably borrowed state.
+    ///
+    /// This is an associated function that needs to be used as `StateGuardMut::map(...)`, so it
+    /// does not conflict with a method of the same name on the borrowed state.
+    pub fn map<U: ?Sized>(guard: Self, f: impl FnOnce(&mut T) -> &mut U) -> StateGuardMut<'cs, U> {
+        StateGuardMut {
+            value: RefMut::map(guard.value, f),
+        }
+    }
+}
+
+impl<T: ?Sized> Deref for StateGuardMut<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T: ?Sized> DerefMut for StateGuardMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
@@ -0,0 +1,120 @@
+//! The async bus classes, and their `embedded-hal-async` adapters.
+//!
+//! Each class of this module is a plain dedrv device class with async methods, which mirrors one
+//! of the `embedded-hal-async` traits. The accessors of these classes implement the corresponding
+//! trait, so the async driver crates of the ecosystem (e.g. sensor drivers that are generic over
+//! an `I2c` bus) run unmodified over dedrv devices:
+//!
+//! ```rust,ignore
+//! use dedrv::hal_async::i2c::I2cExt;
+//!
+//! let mut sensor = Bmp280::new(I2C0.i2c());
+//! let pressure = sensor.read_pressure().await?;
+//! ```
+//!
+//! The words of the buses are bytes, and the errors are the generic error kinds of
+//! `embedded-hal`, which every driver crate is able to handle.
+
+/// The async I2C bus class, which is adapted to [`embedded_hal_async::i2c::I2c`].
+pub mod i2c {
+    use embedded_hal_async::i2c::{self, ErrorKind, Operation};
+
+    use crate::Accessor;
+
+    /// An async I2C bus controller, which addresses its targets with 7-bit addresses.
+    #[crate::class]
+    pub trait I2c {
+        /// Execute the given operations on the target at `address`, as a single transaction.
+        async fn transaction(
+            &mut self,
+            address: u8,
+            operations: &mut [Operation<'_>],
+        ) -> Result<(), ErrorKind>;
+    }
+
+    impl<D: driver::I2c> i2c::ErrorType for Accessor<'_, D, tag::I2c> {
+        type Error = ErrorKind;
+    }
+
+    impl<D: driver::I2c> i2c::I2c for Accessor<'_, D, tag::I2c> {
+        async fn transaction(
+            &mut self,
+            address: u8,
+            operations: &mut [Operation<'_>],
+        ) -> Result<(), ErrorKind> {
+            I2c::transaction(self, address, operations).await
+        }
+    }
+}
+
+/// The async SPI bus class, which is adapted to [`embedded_hal_async::spi::SpiBus`].
+pub mod spi {
+    use embedded_hal_async::spi::{self, ErrorKind};
+
+    use crate::Accessor;
+
+    /// An async SPI bus controller, which owns the bus but not the chip select lines.
+    #[crate::class]
+    pub trait SpiBus {
+        /// Read words from the bus, while writing implementation-defined words.
+        async fn read(&mut self, words: &mut [u8]) -> Result<(), ErrorKind>;
+
+        /// Write words to the bus, while discarding the read words.
+        async fn write(&mut self, words: &[u8]) -> Result<(), ErrorKind>;
+
+        /// Write and read words simultaneously, where the shorter buffer is padded.
+        async fn transfer(&mut self, read: &mut [u8], write: &[u8]) -> Result<(), ErrorKind>;
+
+        /// Write and read words simultaneously, using the same buffer.
+        async fn transfer_in_place(&mut self, words: &mut [u8]) -> Result<(), ErrorKind>;
+
+        /// Wait until all the operations have completed and the bus is idle.
+        async fn flush(&mut self) -> Result<(), ErrorKind>;
+    }
+
+    impl<D: driver::SpiBus> spi::ErrorType for Accessor<'_, D, tag::SpiBus> {
+        type Error = ErrorKind;
+    }
+
+    impl<D: driver::SpiBus> spi::SpiBus for Accessor<'_, D, tag::SpiBus> {
+        async fn read(&mut self, words: &mut [u8]) -> Result<(), ErrorKind> {
+            SpiBus::read(self, words).await
+        }
+
+        async fn write(&mut self, words: &[u8]) -> Result<(), ErrorKind> {
+            SpiBus::write(self, words).await
+        }
+
+        async fn transfer(&mut self, read: &mut [u8], write: &[u8]) -> Result<(), ErrorKind> {
+            SpiBus::transfer(self, read, write).await
+        }
+
+        async fn transfer_in_place(&mut self, words: &mut [u8]) -> Result<(), ErrorKind> {
+            SpiBus::transfer_in_place(self, words).await
+        }
+
+        async fn flush(&mut self) -> Result<(), ErrorKind> {
+            SpiBus::flush(self).await
+        }
+    }
+}
+
+/// The async delay class, which is adapted to [`embedded_hal_async::delay::DelayNs`].
+pub mod delay {
+    use embedded_hal_async::delay;
+
+    use crate::Accessor;
+
+    /// An async delay provider (e.g. a timer).
+    #[crate::class]
+    pub trait Delay {
+        /// Wait for at least `ns` nanoseconds.
+        async fn delay_ns(&mut self, ns: u32);
+    }
+
+    impl<D: driver::Delay> delay::DelayNs for Accessor<'_, D, tag::Delay> {
+        async fn delay_ns(&mut self, ns: u32) {
+            Delay::delay_ns(self, ns).await
+        }
+    }
+}
@@ -0,0 +1,181 @@
+//! The per-device error history.
+//!
+//! With the `error-history` feature, the last [`HISTORY_LEN`] errors that have been returned by
+//! the class methods of each device are recorded, along with the name of the method and a
+//! timestamp from the clock of the framework (see [`clock`](crate::clock)). The history of a device is read with
+//! [`Device::error_history`] (e.g. through [`Accessor::inner`](crate::Accessor::inner)), and the
+//! history of every device is dumped with [`dump`], so post-mortem debugging can see what a flaky
+//! device was doing before the fault.
+//!
+//! Only the class methods that return a `Result` with an [`Error`] are recorded (i.e. not the
+//! methods whose error type is another type or a generic parameter). Without the feature, the
+//! class methods do not even call the history.
+
+#[cfg(feature = "error-history")]
+use crate::clock;
+use crate::Error;
+#[cfg(feature = "error-history")]
+use crate::{Device, Driver};
+
+/// The number of errors that are recorded per device.
+pub const HISTORY_LEN: usize = 4;
+
+/// An error that has been returned by a class method.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorRecord {
+    /// The name of the class method.
+    pub method: &'static str,
+
+    /// The returned error.
+    pub error: Error,
+
+    /// The timestamp of the error, in the unit of the registered clock.
+    pub timestamp: u64,
+}
+
+/// The last errors of a device, in a ring buffer.
+#[derive(Debug, Clone)]
+pub struct History {
+    records: [Option<ErrorRecord>; HISTORY_LEN],
+    next: usize,
+}
+
+impl History {
+    /// Create an empty history.
+    pub const fn new() -> Self {
+        History {
+            records: [const { None }; HISTORY_LEN],
+            next: 0,
+        }
+    }
+
+    /// Record an error, which overwrites the oldest one if the history is full.
+    pub fn push(&mut self, record: ErrorRecord) {
+        self.records[self.next] = Some(record);
+        self.next = (self.next + 1) % HISTORY_LEN;
+    }
+
+    /// Iterate over the recorded errors, from the oldest to the newest.
+    pub fn iter(&self) -> impl Iterator<Item = &ErrorRecord> {
+        (0..HISTORY_LEN).filter_map(move |i| self.records[(self.next + i) % HISTORY_LEN].as_ref())
+    }
+
+    /// Get the newest recorded error.
+    pub fn last(&self) -> Option<&ErrorRecord> {
+        self.records[(self.next + HISTORY_LEN - 1) % HISTORY_LEN].as_ref()
+    }
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The error of a class method, which is given to [`FrameworkError::error`] or
+/// [`OtherError::error`] depending on its type.
+///
+/// The generated code calls `(&Recorded(e)).error()` with both traits in scope: the method of
+/// [`FrameworkError`] is selected first if the error is an [`Error`], otherwise the method of
+/// [`OtherError`] is selected through one more reference, so the error type is not constrained.
+#[cfg(feature = "error-history")]
+#[doc(hidden)]
+pub struct Recorded<'a, E>(pub &'a E);
+
+/// Get the error of a class method that is an [`Error`], which is recorded.
+#[cfg(feature = "error-history")]
+#[doc(hidden)]
+pub trait FrameworkError {
+    fn error(&self) -> Option<Error>;
+}
+
+#[cfg(feature = "error-history")]
+impl FrameworkError for Recorded<'_, Error> {
+    #[inline(always)]
+    fn error(&self) -> Option<Error> {
+        Some(self.0.clone())
+    }
+}
+
+/// Get the error of a class method that is not an [`Error`], which is not recorded.
+#[cfg(feature = "error-history")]
+#[doc(hidden)]
+pub trait OtherError {
+    fn error(&self) -> Option<Error>;
+}
+
+#[cfg(feature = "error-history")]
+impl<E> OtherError for &Recorded<'_, E> {
+    #[inline(always)]
+    fn error(&self) -> Option<Error> {
+        None
+    }
+}
+
+/// Record the error of a class method of the driver on `device`, if any.
+///
+/// This is called by the code that is generated by the [`class`](crate::class) attribute for the
+/// methods that return a `Result`, with the error given by [`Recorded`].
+#[doc(hidden)]
+#[cfg(feature = "error-history")]
+pub fn record<D: Driver>(device: &Device<D>, method: &'static str, error: Option<Error>) {
+    if let Some(error) = error {
+        // Until a clock is registered, the timestamps are zero.
+        let timestamp = clock::now().unwrap_or(0);
+
+        critical_section::with(|cs| {
+            device.history.borrow_ref_mut(cs).push(ErrorRecord {
+                method,
+                error,
+                timestamp,
+            });
+        });
+    }
+}
+
+/// Call `f` with the path and the error history of each device.
+#[cfg(feature = "error-history")]
+pub fn dump(mut f: impl FnMut(&'static crate::Path, &History)) -> crate::Result<()> {
+    let (early, devices) = (
+        crate::Registry::early().table()?,
+        crate::Registry::devices().table()?,
+    );
+
+    for desc in early.iter().chain(devices) {
+        f(desc.path(), &desc.error_history());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use googletest::prelude::*;
+
+    use super::*;
+
+    fn record(method: &'static str) -> ErrorRecord {
+        ErrorRecord {
+            method,
+            error: Error::Busy,
+            timestamp: 0,
+        }
+    }
+
+    #[test]
+    fn it_should_keep_last_errors() -> googletest::Result<()> {
+        let mut history = History::new();
+        verify_that!(history.last(), none())?;
+
+        for method in ["a", "b", "c", "d", "e", "f"] {
+            history.push(record(method));
+        }
+
+        let methods: Vec<_> = history.iter().map(|x| x.method).collect();
+        verify_that!(
+            methods,
+            elements_are![eq(&"c"), eq(&"d"), eq(&"e"), eq(&"f")]
+        )?;
+        verify_that!(history.last(), some(eq(&record("f"))))
+    }
+}
@@ -0,0 +1,134 @@
+//! The interrupt lines of the devices.
+//!
+//! A device may declare its interrupt line with `#[dedrv::device(path = "/uart0", irq =
+//! Interrupt::USART1, irq_priority = 32)]`, where `Interrupt` is the interrupt enumeration of the
+//! PAC. Then the line is enabled with its priority right after the driver init function, and it is
+//! disabled right before the driver cleanup function, so a driver can neither forget to unmask its
+//! interrupt nor leave it firing on a cleaned-up state.
+//!
+//! The interrupt lines are managed by the installed [`Controller`]. With the `cortex-m` feature,
+//! the NVIC is used by default.
+
+use core::cell::Cell;
+
+use critical_section::Mutex;
+
+/// An interrupt line, with an optional priority.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Irq {
+    number: u16,
+    priority: Option<u8>,
+}
+
+impl Irq {
+    /// Create a new interrupt line out of its number (e.g. `Interrupt::USART1 as u16`).
+    ///
+    /// The priority of the line is left unchanged.
+    pub const fn new(number: u16) -> Self {
+        Irq {
+            number,
+            priority: None,
+        }
+    }
+
+    /// Set the priority of the line, which is given as the raw value of the priority register
+    /// (e.g. the NVIC only implements the upper bits).
+    pub const fn with_priority(mut self, priority: u8) -> Self {
+        self.priority = Some(priority);
+        self
+    }
+
+    /// The number of the line.
+    pub const fn number(&self) -> u16 {
+        self.number
+    }
+
+    /// The priority of the line, if any.
+    pub const fn priority(&self) -> Option<u8> {
+        self.priority
+    }
+}
+
+/// An interrupt controller, which enables and disables the interrupt lines of the devices.
+pub trait Controller: Sync {
+    /// Set the priority of the line, if any, then enable the line.
+    fn enable(&self, irq: Irq);
+
+    /// Disable the line.
+    fn disable(&self, irq: Irq);
+}
+
+/// The installed controller.
+static CONTROLLER: Mutex<Cell<Option<&'static dyn Controller>>> = Mutex::new(Cell::new(None));
+
+/// Install the controller of the interrupt lines, which replaces the default one (if any).
+pub fn set_controller(controller: &'static dyn Controller) {
+    critical_section::with(|cs| CONTROLLER.borrow(cs).set(Some(controller)));
+}
+
+/// Get the installed controller, or the default one of the architecture.
+fn controller() -> Option<&'static dyn Controller> {
+    #[cfg(feature = "cortex-m")]
+    let default: Option<&'static dyn Controller> = Some(&Nvic);
+
+    #[cfg(not(feature = "cortex-m"))]
+    let default = None;
+
+    critical_section::with(|cs| CONTROLLER.borrow(cs).get()).or(default)
+}
+
+/// Enable a line through the controller, if any.
+pub(crate) fn enable(irq: Irq) {
+    if let Some(controller) = controller() {
+        controller.enable(irq);
+    }
+}
+
+/// Disable a line through the controller, if any.
+pub(crate) fn disable(irq: Irq) {
+    if let Some(controller) = controller() {
+        controller.disable(irq);
+    }
+}
+
+/// The NVIC of the Cortex-M cores.
+#[cfg(feature = "cortex-m")]
+pub struct Nvic;
+
+#[cfg(feature = "cortex-m")]
+impl Controller for Nvic {
+    fn enable(&self, irq: Irq) {
+        use cortex_m::peripheral::NVIC;
+
+        if let Some(priority) = irq.priority {
+            // SAFETY: The NVIC is only used for setting the priority of a line that belongs to
+            // the device, which is not enabled yet.
+            unsafe {
+                cortex_m::Peripherals::steal()
+                    .NVIC
+                    .set_priority(Line(irq.number), priority)
+            };
+        }
+
+        // SAFETY: The line is enabled after the driver init function, so its handler may access
+        // the driver state.
+        unsafe { NVIC::unmask(Line(irq.number)) };
+    }
+
+    fn disable(&self, irq: Irq) {
+        cortex_m::peripheral::NVIC::mask(Line(irq.number));
+    }
+}
+
+/// A raw interrupt number of the NVIC.
+#[cfg(feature = "cortex-m")]
+#[derive(Clone, Copy)]
+struct Line(u16);
+
+// SAFETY: The number is the one of a line of the PAC enumeration.
+#[cfg(feature = "cortex-m")]
+unsafe impl cortex_m::interrupt::InterruptNumber for Line {
+    fn number(self) -> u16 {
+        self.0
+    }
+}
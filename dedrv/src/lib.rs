@@ -2,26 +2,196 @@
 #![deny(missing_docs)]
 #![cfg_attr(not(test), no_std)]
 
-use core::cell::{Ref, RefCell, RefMut};
-use core::fmt::Display;
+use core::cell::{Cell, Ref, RefCell, RefMut};
+use core::fmt::{Display, Write};
+use core::hash::Hash;
 use core::marker::PhantomData;
-use core::ptr::NonNull;
 
 use critical_section::{CriticalSection, Mutex};
 
+// The device class macro refers to the items of the crate as `::dedrv::*`.
+extern crate self as dedrv;
+
+mod accessor;
+mod atomic;
+mod dependency;
+mod descriptor;
+mod exclusive;
+mod guard;
+mod registry;
+mod report;
+mod slot;
+mod snapshot;
+#[cfg(test)]
+mod testing;
+mod timing;
+
+#[cfg(feature = "board")]
+pub mod board;
+#[cfg(feature = "can")]
+pub mod can;
+pub mod capability;
+pub mod clock;
+pub mod config;
+#[cfg(feature = "dma")]
+pub mod dma;
+pub mod early;
+#[cfg(feature = "exti")]
+pub mod exti;
+#[cfg(feature = "hal-async")]
+pub mod hal_async;
+pub mod history;
+pub mod irq;
+#[cfg(feature = "mailbox")]
+pub mod mailbox;
+pub mod path;
+#[cfg(feature = "poll")]
+pub mod poll;
+pub mod profile;
+pub mod queue;
+#[cfg(feature = "rand")]
+pub mod rand;
+#[doc(hidden)]
+pub mod sealed;
+#[cfg(feature = "sensor")]
+pub mod sensor;
+#[cfg(feature = "shell")]
+pub mod shell;
+pub mod stage;
+#[cfg(feature = "time")]
+pub mod time;
+#[cfg(feature = "watch")]
+pub mod watch;
+#[cfg(feature = "work")]
+pub mod work;
+
 /// Defines the errors at the crate level.
 pub mod error {
     #[doc(hidden)]
     pub type Result<T, E = Error> = ::core::result::Result<T, E>;
 
     #[doc(hidden)]
-    #[derive(Debug, PartialEq, Eq, thiserror::Error)]
+    #[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
     pub enum Error {
         #[error("undefined error")]
         Undefined,
+
+        #[error("corrupted device descriptor table")]
+        CorruptedDescriptorTable,
+
+        #[error("invalid magic {found:#010x} for device descriptor #{index}")]
+        InvalidDescriptorMagic { index: usize, found: u32 },
+
+        #[error("unsupported version {found} for device descriptor #{index}")]
+        DescriptorVersionMismatch { index: usize, found: u32 },
+
+        #[error("unsorted device descriptor table at #{index}")]
+        UnsortedDescriptorTable { index: usize },
+
+        #[error("device not found")]
+        DeviceNotFound,
+
+        #[error("device is busy")]
+        Busy,
+
+        #[error("buffer too small")]
+        BufferTooSmall,
+
+        #[error("invalid state snapshot")]
+        InvalidSnapshot,
+
+        #[error("work queue is full")]
+        WorkQueueFull,
+
+        #[error("poll list is full")]
+        PollListFull,
+
+        #[error("device is not ready")]
+        NotReady,
+
+        #[error("invalid interrupt line")]
+        InvalidLine,
+
+        #[error("invalid date or time")]
+        InvalidDateTime,
+
+        #[error("invalid device path")]
+        InvalidPath,
+
+        #[error("colliding device path identifiers")]
+        PathCollision,
+
+        #[error("dependency on a device of the same or a later init stage")]
+        UnorderedDependency,
+
+        #[error("no free CAN filter bank")]
+        NoFilterBank,
+
+        #[error("no entropy source")]
+        NoEntropySource,
+
+        #[error("invalid shell command")]
+        InvalidCommand,
+
+        #[error("invalid shell argument")]
+        InvalidArgument,
+
+        #[error("output error")]
+        Output,
+    }
+
+    impl From<core::fmt::Error> for Error {
+        fn from(_: core::fmt::Error) -> Self {
+            Error::Output
+        }
+    }
+
+    impl From<core::cell::BorrowError> for Error {
+        fn from(_: core::cell::BorrowError) -> Self {
+            Error::Busy
+        }
+    }
+
+    impl From<core::cell::BorrowMutError> for Error {
+        fn from(_: core::cell::BorrowMutError) -> Self {
+            Error::Busy
+        }
     }
 }
 
+// Re-exports of device class accessors.
+pub use accessor::Accessor;
+
+// Re-exports of multi-device borrows.
+pub use atomic::{atomic_with, AtomicStates};
+
+// Re-exports of device dependencies.
+pub use dependency::Dependency;
+
+// Re-exports of exclusive accessors.
+pub use exclusive::ExclusiveAccessor;
+
+// Re-exports of descriptors.
+pub use descriptor::{Descriptor, DeviceFlags, DeviceRef, DESCRIPTOR_MAGIC, DESCRIPTOR_VERSION};
+
+// Re-exports of paths.
+pub use path::{Path, PathId};
+
+// Re-exports of runtime-constructed states.
+pub use slot::Slot;
+
+// Re-exports of state guards.
+pub use guard::{StateGuard, StateGuardMut};
+
+// Re-exports of registries.
+pub use registry::{DeviceTable, Registry};
+
+// Re-exports of init reports.
+pub use report::{InitPolicy, InitReport, REPORT_LEN};
+
+// Re-exports of init timings.
+pub use timing::InitTiming;
+
 // Re-exports of errors.
 pub use error::{Error, Result};
 
@@ -34,19 +204,152 @@ pub use dedrv_macros::*;
 /// state is stored by a [`Device`] instance.
 pub trait Driver {
     /// The type of the internal driver state.
+    ///
+    /// The state must be `Send`, since it may be borrowed from any thread (or core) that enters
+    /// the critical section. With the `single-core` feature on a bare-metal target, this
+    /// requirement is relaxed, so the state may hold `!Send` HAL singletons.
+    #[cfg(not(all(feature = "single-core", target_os = "none")))]
     type StateType: Send + Sized;
 
+    /// The type of the internal driver state.
+    ///
+    /// With the `single-core` feature on a bare-metal target, the state is not required to be
+    /// `Send`, since it is only borrowed from the critical section of the single core (i.e. with
+    /// interrupts masked).
+    #[cfg(all(feature = "single-core", target_os = "none"))]
+    type StateType: Sized;
+
+    /// The compile-time invariants of the driver (e.g. on its [`DriverConfig`]), which are
+    /// evaluated in a const context for each instance that is declared with the [`device`]
+    /// attribute.
+    ///
+    /// A failed assertion (e.g. `const CHECK: () = assert!(Self::CONFIG.depth.is_power_of_two(),
+    /// "the FIFO depth must be a power of two");`) fails the build of the device instance, so a
+    /// board configuration mistake is not a runtime fault. By default, nothing is checked.
+    const CHECK: () = ();
+
+    /// Whether the number of open accessors of the devices of this driver may be limited (see
+    /// [`Device::with_max_accessors`]).
+    ///
+    /// The accessors of such a driver may run out, so they are only obtained with
+    /// [`Device::try_accessor`], and [`Device::accessor`] fails the build instead of panicking
+    /// once the limit is reached. By default, the number of accessors is not limited.
+    ///
+    /// ```compile_fail
+    /// # use dedrv::{Accessor, Device, Driver, StateLock};
+    /// #[dedrv::class]
+    /// pub trait Sequencer {
+    ///     fn start(&mut self);
+    /// }
+    ///
+    /// struct OneShotDriver;
+    ///
+    /// impl Driver for OneShotDriver {
+    ///     type StateType = ();
+    ///
+    ///     const LIMITED_ACCESSORS: bool = true;
+    ///
+    ///     fn init(_state: &StateLock<Self>) {}
+    ///     fn cleanup(_state: &StateLock<Self>) {}
+    /// }
+    ///
+    /// impl driver::Sequencer for OneShotDriver {
+    ///     fn start(_state: &StateLock<Self>) {}
+    /// }
+    ///
+    /// static SEQ0: Device<OneShotDriver> = Device::new().with_max_accessors(1);
+    ///
+    /// fn main() {
+    ///     // Only `SEQ0.try_accessor::<tag::Sequencer>()` builds.
+    ///     let _seq = SEQ0.accessor::<tag::Sequencer>();
+    /// }
+    /// ```
+    const LIMITED_ACCESSORS: bool = false;
+
     /// The init function of the driver.
     ///
     /// This function initializes the driver internal state. It may include any side-effect that
     /// is required by the underlying hardware device to set up.
     fn init(state: &StateLock<Self>);
 
+    /// The probe function of the driver, which checks that the underlying hardware is present
+    /// before init (e.g. the identification register of an optional sensor).
+    ///
+    /// A device whose probe fails is left uninitialized, and the failure is listed in the
+    /// [`InitReport`] of [`init`]. By default, the hardware is assumed to be present.
+    fn probe(_state: &StateLock<Self>) -> Result<()> {
+        Ok(())
+    }
+
     /// The cleanup function of the driver.
     ///
     /// This function cleans up the driver internal state. This may include any side-effect that
     /// is required by the underlying hardware device to go back to a default state.
     fn cleanup(state: &StateLock<Self>);
+
+    /// The open function of the driver, which is called when the first accessor is created.
+    ///
+    /// This function may lazily enable what is only required while someone actually holds the
+    /// device (e.g. clocks or interrupts). By default, this does nothing.
+    fn open(_state: &StateLock<Self>) {}
+
+    /// The close function of the driver, which is called when the last accessor is dropped.
+    ///
+    /// This function reverts what has been done by [`Driver::open`]. By default, this does
+    /// nothing.
+    fn close(_state: &StateLock<Self>) {}
+
+    /// The save function of the driver, which serializes the state into `buf` before entering a
+    /// low-power mode where the peripheral registers are lost.
+    ///
+    /// This returns the number of bytes written, or [`Error::BufferTooSmall`] if `buf` cannot hold
+    /// the serialized state. By default, nothing is saved.
+    fn save(_state: &StateLock<Self>, _buf: &mut [u8]) -> Result<usize> {
+        Ok(0)
+    }
+
+    /// The restore function of the driver, which deserializes the state out of `data` (i.e. the
+    /// bytes written by [`Driver::save`]) when leaving a low-power mode.
+    ///
+    /// By default, nothing is restored.
+    fn restore(_state: &StateLock<Self>, _data: &[u8]) -> Result<()> {
+        Ok(())
+    }
+
+    /// The selftest function of the driver, which checks the underlying hardware after init (e.g.
+    /// a loopback transfer or an identification register).
+    ///
+    /// This is called by [`Device::selftest`] and [`selftest_all`]. By default, nothing is checked.
+    fn selftest(_state: &StateLock<Self>) -> Result<()> {
+        Ok(())
+    }
+
+    /// The dump function of the driver, which writes a short snapshot of the state to `out` (e.g.
+    /// the last register values or the pending transfer) for post-crash triage.
+    ///
+    /// This is called by [`dump`] and [`dump_on_panic`], possibly from a panic handler or a fault
+    /// handler, so it must neither panic nor rely on other devices. By default, nothing is
+    /// written.
+    fn dump(_state: &Self::StateType, _out: &mut dyn Write) -> core::fmt::Result {
+        Ok(())
+    }
+}
+
+/// The static configuration of a driver.
+///
+/// Driver-wide tuning parameters (e.g. FIFO sizes or timings) are expressed in the type of the
+/// driver rather than stored in its state at runtime, so they are known at compile time and cost
+/// nothing in RAM. The driver implementation reads them with `Self::CONFIG`, and a driver that is
+/// generic over its configuration (e.g. `Uart<Board>`) may be instantiated with several ones.
+///
+/// The runtime counterpart, for parameters that may change without recompiling, is the
+/// [`config`] store.
+pub trait DriverConfig: Driver {
+    /// The type of the driver configuration.
+    type Config: 'static;
+
+    /// The configuration of the driver.
+    const CONFIG: Self::Config;
 }
 
 /// Lock-protected driver internal state.
@@ -58,6 +361,34 @@ pub trait Driver {
 /// implements a portable lock-based mechanism.
 pub type StateLock<D> = Mutex<RefCell<<D as Driver>::StateType>>;
 
+/// Panic-free borrow operations of a [`StateLock`].
+///
+/// Borrowing a [`RefCell`] panics if it is already borrowed in a conflicting way (e.g. a class
+/// method that is called from an interrupt handler while the state is held by a
+/// [`StateGuardMut`]). A driver may opt for these operations instead, so that its class methods
+/// return [`Error::Busy`] rather than panicking, for builds that forbid panics.
+pub trait TryStateLock<T> {
+    /// Try to borrow the lock-protected state, or return [`Error::Busy`] if it is mutably
+    /// borrowed.
+    fn try_borrow_ref<'cs>(&'cs self, cs: CriticalSection<'cs>) -> Result<Ref<'cs, T>>;
+
+    /// Try to mutably borrow the lock-protected state, or return [`Error::Busy`] if it is
+    /// borrowed.
+    fn try_borrow_ref_mut<'cs>(&'cs self, cs: CriticalSection<'cs>) -> Result<RefMut<'cs, T>>;
+}
+
+impl<T> TryStateLock<T> for Mutex<RefCell<T>> {
+    #[inline(always)]
+    fn try_borrow_ref<'cs>(&'cs self, cs: CriticalSection<'cs>) -> Result<Ref<'cs, T>> {
+        self.borrow(cs).try_borrow().map_err(|_| Error::Busy)
+    }
+
+    #[inline(always)]
+    fn try_borrow_ref_mut<'cs>(&'cs self, cs: CriticalSection<'cs>) -> Result<RefMut<'cs, T>> {
+        self.borrow(cs).try_borrow_mut().map_err(|_| Error::Busy)
+    }
+}
+
 /// The device class tags.
 ///
 /// Tags are used to statically restrain an [`Accessor`] to a unique device class.
@@ -66,6 +397,166 @@ pub mod tag {
     pub struct NoTag;
 }
 
+/// Binds a device class tag to the drivers that implement the class.
+///
+/// This trait is implemented by the [`class`] attribute for the tag of each device class, so that
+/// requesting an [`Accessor`] for a class that is not implemented by the driver fails at the
+/// accessor creation site.
+#[diagnostic::on_unimplemented(
+    message = "the driver `{D}` does not implement the device class of tag `{Self}`",
+    label = "unsupported device class",
+    note = "implement the class trait from the generated driver module for `{D}`"
+)]
+pub trait ClassTag<D: Driver>: sealed::Tag {}
+
+impl<D: Driver> ClassTag<D> for tag::NoTag {}
+
+impl sealed::Tag for tag::NoTag {}
+
+/// A device class, as identified at runtime.
+///
+/// This trait is implemented by the [`class`] attribute for the tag of each device class, so that
+/// the classes of a device may be discovered out of its [`Descriptor`] (see
+/// [`Descriptor::supports`]).
+pub trait Class {
+    /// The identifier of the class.
+    const ID: ClassId;
+
+    /// The name of the class (i.e. the name of the class trait).
+    const NAME: &'static str;
+}
+
+/// The runtime identifier of a device class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ClassId(u32);
+
+impl ClassId {
+    /// Create the identifier of the class with the given fully qualified name.
+    ///
+    /// The identifier is the 32-bit FNV-1a hash of the name, so it is stable across builds.
+    pub const fn new(name: &str) -> Self {
+        ClassId(fnv1a(name.as_bytes()))
+    }
+
+    /// Get the identifier of the class of tag `Tag`, which must be implemented by the driver of
+    /// `device`.
+    ///
+    /// This is used by the [`device`] attribute for recording the classes of a device, so that a
+    /// class that is not implemented by the driver is rejected at compile time.
+    pub const fn of<D: Driver, Tag: Class + ClassTag<D>>(_device: &Device<D>) -> Self {
+        Tag::ID
+    }
+}
+
+/// Compute the 32-bit FNV-1a hash of `bytes`, which is stable across builds.
+pub(crate) const fn fnv1a(bytes: &[u8]) -> u32 {
+    let mut hash = 0x811c_9dc5u32;
+
+    let mut i = 0;
+    while i < bytes.len() {
+        hash ^= bytes[i] as u32;
+        hash = hash.wrapping_mul(0x0100_0193);
+        i += 1;
+    }
+
+    hash
+}
+
+/// Check that a class method of a checked class (see the `checked` option of the [`class`]
+/// attribute) may be called on `device`.
+///
+/// This returns [`Error::NotReady`] if the device has not been initialized, or [`Error::Busy`] if
+/// its state is already borrowed (mutably for a shared method, at all for an `exclusive` one).
+///
+/// The check is best-effort: the state is released before the driver is called, so an interrupt
+/// handler (or another core) may still borrow it in between. A driver that must never panic on a
+/// conflicting borrow borrows its state with [`TryStateLock`] instead.
+#[doc(hidden)]
+pub fn precheck<D: Driver>(device: &Device<D>, exclusive: bool) -> Result<()> {
+    if device.lifecycle() != Lifecycle::Initialized {
+        return Err(Error::NotReady);
+    }
+
+    critical_section::with(|cs| {
+        let state = device.state.borrow(cs);
+        match exclusive {
+            true => state.try_borrow_mut().map(|_| ())?,
+            false => state.try_borrow().map(|_| ())?,
+        }
+        Ok(())
+    })
+}
+
+/// Changes the class tag of an accessor, which models a typestate transition between device
+/// classes (e.g. a GPIO pin that is configured from input to output).
+///
+/// A consuming class method that returns an accessor for another tag, named by its full path
+/// (e.g. `fn into_output(self) -> dedrv::Accessor<'d, D, tag::GpioOutput>`), is rewritten by the
+/// [`class`] attribute on top of this trait, because the class trait cannot name the device nor
+/// the driver.
+/// The transition is only available if the driver implements the target class.
+pub trait Retag<Tag> {
+    /// The accessor for the target tag.
+    type Output;
+
+    /// Consume this accessor, then give it back with the target tag.
+    fn retag(self) -> Self::Output;
+}
+
+impl<'d, D: Driver, From, Tag: ClassTag<D>> Retag<Tag> for Accessor<'d, D, From> {
+    type Output = Accessor<'d, D, Tag>;
+
+    fn retag(self) -> Self::Output {
+        // The accessor is moved, so it is still accounted for by the device.
+        let this = core::mem::ManuallyDrop::new(self);
+
+        Accessor {
+            device: this.device,
+            _marker: PhantomData,
+            _tag: PhantomData,
+        }
+    }
+}
+
+/// The lifecycle of a device instance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lifecycle {
+    /// The driver has not been initialized on the device yet, or it has been cleaned up since.
+    Uninitialized,
+
+    /// The driver is being initialized on the device (see [`Device::try_init`]).
+    Initializing,
+
+    /// The driver has been initialized on the device.
+    Initialized,
+}
+
+impl Display for Lifecycle {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.pad(match self {
+            Lifecycle::Uninitialized => "uninitialized",
+            Lifecycle::Initializing => "initializing",
+            Lifecycle::Initialized => "initialized",
+        })
+    }
+}
+
+/// The identifier of a device instance, which is stable for the whole program.
+///
+/// The identifier is the address of the device, which is never moved since it is a static. As a
+/// result, it is a cheap key for the tables of higher layers (e.g. waker maps or caches), while
+/// the devices themselves are not comparable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DeviceId(usize);
+
+impl DeviceId {
+    /// Get the identifier of the device at `device` (e.g. the type-erased device of a
+    /// descriptor).
+    pub(crate) fn of<T: ?Sized>(device: &T) -> Self {
+        DeviceId(device as *const T as *const () as usize)
+    }
+}
+
 /// A device instance.
 ///
 /// Stores every device driver internal state and resources that are related to a given device
@@ -75,10 +566,49 @@ pub struct Device<D: Driver + 'static> {
     /// The lock-protected state for the driver that is related to this device instance.
     pub state: StateLock<D>,
 
+    #[doc(hidden)]
+    lifecycle: Mutex<Cell<Lifecycle>>,
+
+    #[doc(hidden)]
+    accessors: Mutex<Cell<usize>>,
+
+    #[doc(hidden)]
+    exclusive: Mutex<Cell<bool>>,
+
+    #[doc(hidden)]
+    max_accessors: usize,
+
+    #[doc(hidden)]
+    irq: Option<irq::Irq>,
+
+    #[doc(hidden)]
+    init_result: Mutex<RefCell<Option<Result<()>>>>,
+
+    #[doc(hidden)]
+    selftest: Mutex<RefCell<Option<Result<()>>>>,
+
+    #[doc(hidden)]
+    #[cfg(feature = "method-duration")]
+    profile: profile::Profile,
+
+    #[doc(hidden)]
+    #[cfg(feature = "error-history")]
+    history: Mutex<RefCell<history::History>>,
+
     #[doc(hidden)]
     _drv: PhantomData<&'static D>,
 }
 
+// SAFETY: The relaxation only applies to bare-metal targets (i.e. without an OS, so without
+// threads), whose application enables the `single-core` feature to vouch that a single core runs
+// it. Every access to the shared fields of a device (i.e. the driver state and the bookkeeping
+// cells) goes through a `critical_section::Mutex`, so it is only made while the interrupts of the
+// single core are masked. Hence, no two execution contexts ever access a driver state at once,
+// whether the state is `Send` or not, as long as the driver does not leak a part of its state
+// (e.g. a clone of an `Rc`) out of the critical section.
+#[cfg(all(feature = "single-core", target_os = "none"))]
+unsafe impl<D: Driver> Sync for Device<D> {}
+
 impl<D: Driver> Device<D> {
     /// Create a new device instance.
     ///
@@ -89,20 +619,249 @@ impl<D: Driver> Device<D> {
     pub const fn new() -> Self {
         Device {
             state: Mutex::new(RefCell::new(unsafe { core::mem::zeroed() })),
+            lifecycle: Mutex::new(Cell::new(Lifecycle::Uninitialized)),
+            accessors: Mutex::new(Cell::new(0)),
+            exclusive: Mutex::new(Cell::new(false)),
+            max_accessors: usize::MAX,
+            irq: None,
+            init_result: Mutex::new(RefCell::new(None)),
+            selftest: Mutex::new(RefCell::new(None)),
+            #[cfg(feature = "method-duration")]
+            profile: profile::Profile::new(),
+            #[cfg(feature = "error-history")]
+            history: Mutex::new(RefCell::new(history::History::new())),
             _drv: PhantomData,
         }
     }
 
+    /// Declare the interrupt line of this device instance, which is managed by the framework (see
+    /// [`irq`]).
+    ///
+    /// The [`device`](crate::device) attribute declares the line that is given by its `irq` and
+    /// `irq_priority` arguments.
+    pub const fn with_irq(mut self, irq: irq::Irq) -> Self {
+        self.irq = Some(irq);
+        self
+    }
+
+    /// Get the interrupt line of this device instance, if any.
+    pub const fn irq(&self) -> Option<irq::Irq> {
+        self.irq
+    }
+
     /// Call the [`Driver::init`] function of the driver on this device instance.
+    ///
+    /// The interrupt line of the device, if any, is enabled afterwards.
     #[inline(always)]
     pub fn init(&self) {
-        D::init(&self.state)
+        D::init(&self.state);
+        self.set_lifecycle(Lifecycle::Initialized);
+
+        if let Some(irq) = self.irq {
+            irq::enable(irq);
+        }
+    }
+
+    /// Call the [`Driver::probe`] function of the driver on this device instance, then
+    /// initialize it like [`Device::init`] if its hardware is present, and record the result.
+    ///
+    /// A device whose probe fails is left uninitialized, while a device that is already
+    /// initialized (e.g. as the [`Dependency`] of another device) is left as is, and recorded as
+    /// such.
+    ///
+    /// The device is claimed before its probe (see [`Lifecycle::Initializing`]), so this returns
+    /// [`Error::Busy`] if it is being initialized already (e.g. by another core).
+    pub fn try_init(&self) -> Result<()> {
+        let claimed = critical_section::with(|cs| {
+            let lifecycle = self.lifecycle.borrow(cs);
+            match lifecycle.get() {
+                Lifecycle::Uninitialized => {
+                    lifecycle.set(Lifecycle::Initializing);
+                    Ok(true)
+                }
+                Lifecycle::Initializing => Err(Error::Busy),
+                Lifecycle::Initialized => {
+                    *self.init_result.borrow_ref_mut(cs) = Some(Ok(()));
+                    Ok(false)
+                }
+            }
+        })?;
+
+        if !claimed {
+            return Ok(());
+        }
+
+        let result = D::probe(&self.state);
+        critical_section::with(|cs| *self.init_result.borrow_ref_mut(cs) = Some(result.clone()));
+
+        match result {
+            Ok(()) => self.init(),
+            Err(_) => self.set_lifecycle(Lifecycle::Uninitialized),
+        }
+
+        result
+    }
+
+    /// Get the result of the last [`Device::try_init`] of this device instance, if any.
+    pub fn init_result(&self) -> Option<Result<()>> {
+        critical_section::with(|cs| self.init_result.borrow_ref(cs).clone())
     }
 
     /// Call the [`Driver::cleanup`] function of the driver on this device instance.
+    ///
+    /// The interrupt line of the device, if any, is disabled beforehand.
     #[inline(always)]
     pub fn cleanup(&self) {
-        D::cleanup(&self.state)
+        if let Some(irq) = self.irq {
+            irq::disable(irq);
+        }
+
+        D::cleanup(&self.state);
+        self.set_lifecycle(Lifecycle::Uninitialized);
+    }
+
+    /// Re-initialize this device instance, i.e. call [`Driver::cleanup`] then [`Driver::init`],
+    /// so that a wedged peripheral (e.g. a stuck I2C bus or an errored DMA) is recovered in one
+    /// call.
+    ///
+    /// This returns [`Error::NotReady`] if the device has not been initialized, or
+    /// [`Error::Busy`] if its state is held (e.g. by a [`StateGuard`] or a [`StateGuardMut`]) or
+    /// if an exclusive accessor is open (see [`ExclusiveAccessor`]), in which case nothing is
+    /// done. The open accessors remain valid across the reset.
+    ///
+    /// The device is marked exclusive from the cleanup to the end of the init, so no accessor is
+    /// opened nor upgraded to an exclusive one, and no other reset runs, in between (e.g. from an
+    /// interrupt handler or another core).
+    pub fn reset(&self) -> Result<()> {
+        precheck(self, true)?;
+
+        if !self.try_claim_exclusive() {
+            return Err(Error::Busy);
+        }
+
+        self.cleanup();
+        self.init();
+        self.clear_exclusive();
+        Ok(())
+    }
+
+    /// Call the [`Driver::save`] function of the driver on this device instance.
+    #[inline(always)]
+    pub fn save(&self, buf: &mut [u8]) -> Result<usize> {
+        D::save(&self.state, buf)
+    }
+
+    /// Call the [`Driver::restore`] function of the driver on this device instance.
+    #[inline(always)]
+    pub fn restore(&self, data: &[u8]) -> Result<()> {
+        D::restore(&self.state, data)
+    }
+
+    /// Get the maximum duration of the class methods that have been called on this device
+    /// instance, in the unit of the registered clock (see [`profile`]).
+    #[cfg(feature = "method-duration")]
+    pub fn max_duration(&self) -> u64 {
+        self.profile.max()
+    }
+
+    /// Reset the maximum duration of the class methods of this device instance.
+    #[cfg(feature = "method-duration")]
+    pub fn reset_max_duration(&self) {
+        self.profile.reset()
+    }
+
+    /// Get the last errors that have been returned by the class methods of this device instance
+    /// (see [`history`]).
+    #[cfg(feature = "error-history")]
+    pub fn error_history(&self) -> history::History {
+        critical_section::with(|cs| self.history.borrow_ref(cs).clone())
+    }
+
+    /// Call the [`Driver::selftest`] function of the driver on this device instance, then record
+    /// its result.
+    ///
+    /// This returns [`Error::NotReady`] without calling the driver if the device has not been
+    /// initialized.
+    pub fn selftest(&self) -> Result<()> {
+        let result = match self.lifecycle() {
+            Lifecycle::Initialized => D::selftest(&self.state),
+            Lifecycle::Uninitialized | Lifecycle::Initializing => Err(Error::NotReady),
+        };
+
+        critical_section::with(|cs| *self.selftest.borrow_ref_mut(cs) = Some(result.clone()));
+        result
+    }
+
+    /// Write the snapshot of the driver state of this device instance (see [`Driver::dump`]).
+    ///
+    /// The state is borrowed without panicking, so `<busy>` is written instead if it is already
+    /// borrowed (e.g. the crash happened in a class method).
+    pub fn dump(&self, out: &mut dyn Write) -> core::fmt::Result {
+        critical_section::with(|cs| match self.state.try_borrow_ref(cs) {
+            Ok(state) => D::dump(&state, out),
+            Err(_) => out.write_str("<busy>"),
+        })
+    }
+
+    /// Get the result of the last selftest of this device instance, if any.
+    pub fn selftest_result(&self) -> Option<Result<()>> {
+        critical_section::with(|cs| self.selftest.borrow_ref(cs).clone())
+    }
+
+    /// Get the identifier of this device instance.
+    pub fn id(&self) -> DeviceId {
+        DeviceId::of(self)
+    }
+
+    /// Get the current lifecycle of this device instance.
+    pub fn lifecycle(&self) -> Lifecycle {
+        critical_section::with(|cs| self.lifecycle.borrow(cs).get())
+    }
+
+    fn set_lifecycle(&self, lifecycle: Lifecycle) {
+        critical_section::with(|cs| self.lifecycle.borrow(cs).set(lifecycle))
+    }
+
+    /// Get the number of accessors that are currently open on this device instance.
+    pub fn accessors(&self) -> usize {
+        critical_section::with(|cs| self.accessors.borrow(cs).get())
+    }
+
+    /// Account for a new accessor, unless the limit of open accessors is reached or an exclusive
+    /// accessor is open.
+    ///
+    /// The [`Driver::open`] function is called for the first accessor.
+    fn open_accessor(&self) -> Result<()> {
+        let first = critical_section::with(|cs| {
+            let count = self.accessors.borrow(cs);
+            if count.get() >= self.max_accessors || self.exclusive.borrow(cs).get() {
+                return Err(Error::Busy);
+            }
+
+            count.set(count.get() + 1);
+            Ok(count.get() == 1)
+        })?;
+
+        if first {
+            D::open(&self.state);
+        }
+
+        Ok(())
+    }
+
+    /// Account for a dropped accessor.
+    ///
+    /// The [`Driver::close`] function is called for the last accessor.
+    fn close_accessor(&self) {
+        let last = critical_section::with(|cs| {
+            let count = self.accessors.borrow(cs);
+            count.set(count.get().saturating_sub(1));
+            count.get() == 0
+        });
+
+        if last {
+            D::close(&self.state);
+        }
     }
 
     /// Helper function to get access to the internal driver state from a critical section.
@@ -123,13 +882,80 @@ impl<D: Driver> Device<D> {
         self.state.borrow_ref_mut(cs)
     }
 
+    /// Helper function to try to get access to the internal driver state from a critical section.
+    ///
+    /// This returns [`Error::Busy`] instead of panicking if the state is mutably borrowed.
+    #[inline(always)]
+    pub fn try_state_ref<'d, 'cs>(
+        &'d self,
+        cs: CriticalSection<'cs>,
+    ) -> Result<Ref<'d, D::StateType>>
+    where
+        'cs: 'd,
+    {
+        self.state.try_borrow_ref(cs)
+    }
+
+    /// Helper function to try to get access to the mutable internal driver state from a critical
+    /// section.
+    ///
+    /// This returns [`Error::Busy`] instead of panicking if the state is borrowed.
+    #[inline(always)]
+    pub fn try_state_ref_mut<'d, 'cs>(
+        &'d self,
+        cs: CriticalSection<'cs>,
+    ) -> Result<RefMut<'d, D::StateType>>
+    where
+        'cs: 'd,
+    {
+        self.state.try_borrow_ref_mut(cs)
+    }
+
     /// Get a new accessor for the given class from this device.
     ///
     /// The type of an [`Accessor`] is tagged with a device class tag. This prevent from obtaining
     /// an accessor for a class that is not implemented by the underlying driver.
-    pub fn accessor<Tag>(&self) -> Accessor<'_, D, Tag> {
+    ///
+    /// This is not available for a driver with limited accessors (see
+    /// [`Driver::LIMITED_ACCESSORS`]), whose accessors are only obtained with
+    /// [`Device::try_accessor`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if an exclusive accessor of the device is open (see [`ExclusiveAccessor`]).
+    pub fn accessor<Tag: ClassTag<D>>(&self) -> Accessor<'_, D, Tag> {
         Accessor::new(self)
     }
+
+    /// Try to get a new accessor for the given class from this device.
+    ///
+    /// This returns [`Error::NotReady`] if the driver has not been initialized on this device (or
+    /// if it has been cleaned up since), so that application code cannot silently operate on a
+    /// peripheral that has not been brought up. It returns [`Error::Busy`] if the limit of open
+    /// accessors is reached (see [`Device::with_max_accessors`]), or if an exclusive accessor is
+    /// open (see [`ExclusiveAccessor`]).
+    pub fn try_accessor<Tag: ClassTag<D>>(&self) -> Result<Accessor<'_, D, Tag>> {
+        if self.lifecycle() != Lifecycle::Initialized {
+            return Err(Error::NotReady);
+        }
+
+        Accessor::try_new(self)
+    }
+}
+
+impl<T, D: Driver<StateType = Slot<T>>> Device<D> {
+    /// Move a state that is constructed at runtime (e.g. out of HAL peripherals) into this device
+    /// instance, then call the [`Driver::init`] function of the driver.
+    ///
+    /// The `write` function is given the empty [`Slot`] of the state, which it fills with
+    /// [`Slot::write`] (e.g. `UART0.init_with(|slot| slot.write(state))`).
+    pub fn init_with(&self, write: impl FnOnce(&mut Slot<T>) -> &mut T) {
+        critical_section::with(|cs| {
+            write(&mut self.state.borrow_ref_mut(cs));
+        });
+
+        self.init();
+    }
 }
 
 impl<D: Driver> Default for Device<D> {
@@ -148,106 +974,234 @@ where
 }
 
 impl<D: Driver> Drop for Device<D> {
-    fn drop(&mut self) {}
+    /// With the `cleanup-on-drop` feature, dropping an initialized device (e.g. a test fixture or a
+    /// dynamically created device) calls the [`Driver::cleanup`] function once. Otherwise, this
+    /// does nothing.
+    fn drop(&mut self) {
+        #[cfg(feature = "cleanup-on-drop")]
+        if self.lifecycle.get_mut().get() == Lifecycle::Initialized {
+            self.cleanup();
+        }
+    }
 }
 
-/// An device class accessor.
-pub struct Accessor<'d, D: Driver + 'static, Tag = tag::NoTag> {
-    /// The owning device of this accessor.
-    pub device: NonNull<Device<D>>,
-
-    #[doc(hidden)]
-    _marker: PhantomData<&'d Device<D>>,
+/// The device types, which gives the driver of a device type.
+///
+/// This lets generated code name the driver of a device instance out of the type of the instance
+/// only (e.g. `<Device<GpioDriver> as DeviceType>::Driver`).
+pub trait DeviceType {
+    /// The driver of the device type.
+    type Driver: Driver;
+}
 
-    #[doc(hidden)]
-    _tag: PhantomData<Tag>,
+impl<D: Driver> DeviceType for Device<D> {
+    type Driver = D;
 }
 
-impl<'d, D: Driver, Tag> Accessor<'d, D, Tag> {
-    /// Create a new accessor from an owning [`Device`].
-    pub fn new(device: &'d Device<D>) -> Self {
-        let device = unsafe { NonNull::new_unchecked(device as *const _ as *mut _) };
-        Accessor {
-            device,
-            _marker: PhantomData,
-            _tag: PhantomData,
-        }
+/// The runtime-checked single owner of a device.
+///
+/// A device that is declared with `#[dedrv::device(path = "...", singleton)]` is given a module
+/// of the same name, whose `take` function hands out an accessor only once (e.g.
+/// `GPIO0::take::<tag::Gpio>()`), in the manner of `cortex_m::Peripherals::take`. The device
+/// remains owned after the accessor is dropped.
+pub struct Singleton(Mutex<Cell<bool>>);
+
+impl Singleton {
+    /// Create a new singleton that has not been taken yet.
+    pub const fn new() -> Self {
+        Singleton(Mutex::new(Cell::new(false)))
     }
 
-    /// Helper function to get access to the inner device.
-    #[inline(always)]
-    pub fn inner(&self) -> &'d Device<D> {
-        // SAFETY: The pointer is valid because of the lifetime of the accessor, which is at least
-        // as long as the inner device.
-        unsafe { self.device.as_ref() }
+    /// Take the only accessor of the device, or return `None` if it has been taken already.
+    ///
+    /// This also returns `None` if the accessor cannot be opened (e.g. an exclusive accessor of
+    /// the device is open), in which case the device is not taken, so it may be taken later.
+    pub fn take<D: Driver, Tag: ClassTag<D>>(
+        &self,
+        device: &'static Device<D>,
+    ) -> Option<Accessor<'static, D, Tag>> {
+        if critical_section::with(|cs| self.0.borrow(cs).replace(true)) {
+            return None;
+        }
+
+        let accessor = Accessor::try_new(device).ok();
+        if accessor.is_none() {
+            critical_section::with(|cs| self.0.borrow(cs).set(false));
+        }
+
+        accessor
     }
 
-    /// Helper function to get access to the internal driver state from a critical section.
-    #[inline(always)]
-    pub fn inner_state_ref<'a, 'cs>(&'a self, cs: CriticalSection<'cs>) -> Ref<'a, D::StateType>
-    where
-        'cs: 'a,
-    {
-        self.inner().state_ref(cs)
+    /// Check whether the device has been taken.
+    pub fn is_taken(&self) -> bool {
+        critical_section::with(|cs| self.0.borrow(cs).get())
     }
+}
 
-    /// Helper function to get access to the mutable internal driver state from a critical section.
-    #[inline(always)]
-    pub fn inner_state_ref_mut<'a, 'cs>(
-        &'a self,
-        cs: CriticalSection<'cs>,
-    ) -> RefMut<'a, D::StateType>
-    where
-        'cs: 'a,
-    {
-        self.inner().state_ref_mut(cs)
+impl Default for Singleton {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
-/// Device descriptor to be stored in the `.dedrv.device.*` sections inside the linker script.
-#[repr(C)]
-pub struct Descriptor {
-    path: &'static str,
-    init: fn(*const ()),
-    udata: *const (),
+/// Initialize all device drivers that are declared using the [`device`] attribute.
+///
+/// The whole descriptor table is validated before any driver is initialized. As a result, a stale
+/// object, a descriptor built against another version of this crate or a corrupted table is
+/// reported as an error instead of jumping through a garbage function pointer.
+///
+/// The early devices are initialized first, so that the device being initialized is reported to
+/// the early console (see [`early`]), if any.
+///
+/// A device whose hardware is missing (see [`Driver::probe`]) is left uninitialized, while the
+/// other ones are initialized anyway. The returned [`InitReport`] lists the result of each
+/// device, so a board with optional hardware boots degraded and knows exactly what is missing.
+///
+/// The flags of each device are honored (see [`DeviceFlags`]), e.g. a disabled device is left
+/// uninitialized until it is enabled at runtime with [`enable`].
+pub fn init() -> Result<InitReport> {
+    init_with_policy(InitPolicy::Continue)
 }
 
-impl Descriptor {
-    /// Create a new device descriptor.
-    ///
-    /// The `path` is a unique and short string identifier for the device. It provides a key to
-    /// look up on the device in the static table (i.e. linker section).
-    pub const fn new<D: Driver>(
-        path: &'static str,
-        device: &'static Device<D>,
-        init: fn(*const ()),
-    ) -> Self {
-        Descriptor {
-            path,
-            init,
-            udata: &raw const *device as *const _,
-        }
-    }
+/// Initialize all device drivers like [`init`], according to `policy` (e.g. stop at the first
+/// missing device).
+pub fn init_with_policy(policy: InitPolicy) -> Result<InitReport> {
+    let (early, devices) = (Registry::early().table()?, Registry::devices().table()?);
+    report::check_tables(&[early, devices])?;
+
+    Ok(report::init_tables([early, devices], policy))
 }
 
-unsafe impl Sync for Descriptor {}
+/// Get the inventory of every device (i.e. early devices first), which is meant to be printed at
+/// boot (e.g. `info!("{}", dedrv::table())`).
+pub fn table() -> DeviceTable {
+    DeviceTable::new([Registry::early(), Registry::devices()])
+}
 
-unsafe extern "C" {
-    static __DEDRV_MARKER_DEVICE_START: usize;
-    static __DEDRV_MARKER_DEVICE_END: usize;
+/// Look up the descriptor of the device at `path`.
+///
+/// The descriptor table is sorted by path at link time, so the lookup is a binary search. This
+/// returns [`Error::DeviceNotFound`] if no device is registered at `path`, or
+/// [`Error::InvalidPath`] if `path` is not a well-formed [`Path`]. The early devices are only
+/// looked up if the device is not found in the devices table, so any other error of the devices
+/// table (e.g. [`Error::CorruptedDescriptorTable`]) is returned as is.
+pub fn find(path: &str) -> Result<&'static Descriptor> {
+    Registry::devices().find(path).or_else(|e| match e {
+        Error::DeviceNotFound => Registry::early().find(path),
+        e => Err(e),
+    })
 }
 
-/// Initialize all device drivers that are declared using the [`device`] attribute.
-pub fn init() {
-    let mut cursor = &raw const __DEDRV_MARKER_DEVICE_START as *const Descriptor;
-    let end = &raw const __DEDRV_MARKER_DEVICE_END as *const Descriptor;
+/// Enable the device at `path` at runtime, which has been left uninitialized at boot (see
+/// [`DeviceFlags::DISABLED`]).
+///
+/// This returns [`Error::DeviceNotFound`] if no device is registered at `path`, or the error of
+/// the probe of the device if it fails (see [`Descriptor::enable`]).
+pub fn enable(path: &str) -> Result<()> {
+    find(path)?.enable()
+}
+
+/// Look up the descriptor of the device whose path has the identifier `id` (see [`PathId`]).
+///
+/// The identifiers are compared as integers, so this is cheaper than [`find`] on hot paths. This
+/// returns [`Error::DeviceNotFound`] if no device has the identifier `id`.
+///
+/// The identifiers are checked to be unique once, by [`init`] (which returns
+/// [`Error::PathCollision`] otherwise) and by `dedrv-build` on the linked image, so a lookup does
+/// not check for collisions.
+#[cfg(feature = "path-id")]
+pub fn find_id(id: PathId) -> Result<&'static Descriptor> {
+    Registry::devices().find_id(id).or_else(|e| match e {
+        Error::DeviceNotFound => Registry::early().find_id(id),
+        e => Err(e),
+    })
+}
+
+/// Initialize all device drivers like [`init_with_policy`], while measuring the init duration of
+/// each device.
+///
+/// The `now` function is the timestamp source (e.g. a cycle counter), which must be usable before
+/// any device is initialized. The `trace` hook is called with the [`InitTiming`] of each device
+/// right after its initialization, so boot-time regressions can be attributed to specific drivers.
+pub fn init_timed(
+    policy: InitPolicy,
+    now: impl FnMut() -> u64,
+    trace: impl FnMut(InitTiming),
+) -> Result<InitReport> {
+    let (early, devices) = (Registry::early().table()?, Registry::devices().table()?);
+    report::check_tables(&[early, devices])?;
 
-    while cursor < end {
-        // SAFETY: At this point we guarantee that the cursor actually points to a `Descriptor`.
-        // So, dereferencing the cursor is valid.
-        let desc: &'static Descriptor = unsafe { &*cursor };
-        (desc.init)(desc.udata);
+    Ok(timing::init_tables([early, devices], policy, now, trace))
+}
+
+/// Run the selftest of every device (see [`Driver::selftest`]), once they have been initialized.
+///
+/// The `report` hook is called with the path and the selftest result of each device, which is
+/// also recorded by the device (see [`Device::selftest_result`]). This returns the number of
+/// devices whose selftest failed.
+pub fn selftest_all(mut report: impl FnMut(&'static Path, &Result<()>)) -> Result<usize> {
+    let (early, devices) = (Registry::early().table()?, Registry::devices().table()?);
+
+    Ok(early
+        .iter()
+        .chain(devices)
+        .map(|desc| {
+            let result = desc.selftest();
+            report(desc.path(), &result);
+            result
+        })
+        .filter(|x| x.is_err())
+        .count())
+}
+
+/// Write the path, the lifecycle and the driver snapshot (see [`Driver::dump`]) of every device
+/// to `out`, one line per device.
+pub fn dump(out: &mut dyn Write) -> core::fmt::Result {
+    registry::dump_tables([Registry::early(), Registry::devices()], out)
+}
+
+/// Dump every device like [`dump`] over the early console (see [`early`]), for post-crash triage
+/// in the field.
+///
+/// This is meant to be called from a panic handler or a fault handler. It does nothing if there
+/// is no early console, or if it is re-entered (i.e. the dump itself crashed).
+pub fn dump_on_panic() {
+    static DUMPING: Mutex<Cell<bool>> = Mutex::new(Cell::new(false));
+
+    if critical_section::with(|cs| DUMPING.borrow(cs).replace(true)) {
+        return;
+    }
 
-        cursor = cursor.wrapping_add(1);
+    if let Some(mut console) = early::console() {
+        let _ = dump(&mut console);
     }
+
+    critical_section::with(|cs| DUMPING.borrow(cs).set(false));
+}
+
+/// Re-initialize every initialized device (see [`Device::reset`]), in stage order.
+///
+/// The devices that refuse to reset (i.e. whose state is held) are left as is. This returns the
+/// number of such devices.
+pub fn reset_all() -> Result<usize> {
+    Ok(Registry::early().reset()? + Registry::devices().reset()?)
+}
+
+/// Save the state of every device into `buf` (e.g. a retained-RAM region), before entering a
+/// deep-sleep mode where the peripheral registers are lost.
+///
+/// This returns the size of the snapshot, which is restored by [`restore_all`].
+pub fn save_all(buf: &mut [u8]) -> Result<usize> {
+    let (early, devices) = (Registry::early().table()?, Registry::devices().table()?);
+    snapshot::save(early.iter().chain(devices), buf)
+}
+
+/// Restore the state of every device out of a snapshot that has been taken by [`save_all`].
+///
+/// This returns [`Error::InvalidSnapshot`] if `data` does not hold a snapshot of the same devices
+/// (e.g. the retained RAM is not initialized after a cold boot, or the devices have changed with
+/// a firmware update), in which case no device is restored.
+pub fn restore_all(data: &[u8]) -> Result<()> {
+    let (early, devices) = (Registry::early().table()?, Registry::devices().table()?);
+    snapshot::restore(early.iter().chain(devices), data)
 }
@@ -0,0 +1,326 @@
+//! The command/response mailboxes, for the communication between devices.
+//!
+//! A device (i.e. the server, e.g. a bus controller) keeps a [`Mailbox`] in its state, where other
+//! devices or tasks (i.e. the clients, e.g. a sensor driver) post their requests, then await the
+//! responses through their accessor:
+//!
+//! ```rust,ignore
+//! let i2c = I2C0.i2c();
+//! let request = i2c.request::<Command, Response>(Command::Read { addr: 0x48, len: 2 })?;
+//!
+//! match request.response().await {
+//!     Response::Data(data) => info!("{:?}", data),
+//!     Response::Nack => warn!("no sensor"),
+//! }
+//! ```
+//!
+//! The server is notified of each new request (see [`Mailboxed::posted`]), then it takes the
+//! pending requests in order with [`Mailbox::take`] and answers them with [`Mailbox::reply`]
+//! (e.g. from its interrupt handler once the bus transaction is complete), which wakes up the
+//! waiting clients.
+//!
+//! The mailbox holds a bounded number of requests, so posting never allocates. A request that is
+//! dropped before its response is cancelled, so its slot is reused.
+
+use core::future::poll_fn;
+use core::marker::PhantomData;
+use core::task::{Poll, Waker};
+
+use crate::{Accessor, Device, Driver, Error, Result, Slot, StateLock};
+
+/// The states of a slot of a mailbox.
+const FREE: u8 = 0;
+const PENDING: u8 = 1;
+const SERVING: u8 = 2;
+const DONE: u8 = 3;
+const CANCELLED: u8 = 4;
+
+/// A request of a mailbox, along with its response.
+#[derive(Debug)]
+struct Letter<Req, Resp> {
+    state: u8,
+    seq: u32,
+    request: Slot<Req>,
+    response: Slot<Resp>,
+    waker: Slot<Waker>,
+}
+
+impl<Req, Resp> Letter<Req, Resp> {
+    const fn new() -> Self {
+        Letter {
+            state: FREE,
+            seq: 0,
+            request: Slot::new(),
+            response: Slot::new(),
+            waker: Slot::new(),
+        }
+    }
+
+    /// Free the slot, which drops its content.
+    fn free(&mut self) {
+        self.state = FREE;
+        self.request.take();
+        self.response.take();
+        self.waker.take();
+    }
+}
+
+/// The handle of a request that has been posted to a mailbox.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ticket {
+    index: usize,
+    seq: u32,
+}
+
+/// A mailbox of `N` requests of type `Req`, which are answered with responses of type `Resp`.
+///
+/// A zeroed mailbox is a valid empty mailbox.
+#[derive(Debug)]
+pub struct Mailbox<Req, Resp, const N: usize = 4> {
+    letters: [Letter<Req, Resp>; N],
+    seq: u32,
+}
+
+impl<Req, Resp, const N: usize> Mailbox<Req, Resp, N> {
+    /// Create a new empty mailbox.
+    pub const fn new() -> Self {
+        Mailbox {
+            letters: [const { Letter::new() }; N],
+            seq: 0,
+        }
+    }
+
+    /// Get the number of requests that have not been taken by the server yet.
+    pub fn pending(&self) -> usize {
+        self.letters.iter().filter(|x| x.state == PENDING).count()
+    }
+
+    /// Post a request into a free slot, or give it back if the mailbox is full.
+    pub fn post(&mut self, request: Req) -> core::result::Result<Ticket, Req> {
+        let Some(index) = self.letters.iter().position(|x| x.state == FREE) else {
+            return Err(request);
+        };
+
+        self.seq = self.seq.wrapping_add(1);
+
+        let letter = &mut self.letters[index];
+        letter.state = PENDING;
+        letter.seq = self.seq;
+        letter.request.write(request);
+
+        Ok(Ticket {
+            index,
+            seq: self.seq,
+        })
+    }
+
+    /// Take the oldest pending request, which is then being served until it is answered.
+    pub fn take(&mut self) -> Option<(Ticket, Req)> {
+        let seq = self.seq;
+        let (index, letter) = self
+            .letters
+            .iter_mut()
+            .enumerate()
+            .filter(|(_, x)| x.state == PENDING)
+            .max_by_key(|(_, x)| seq.wrapping_sub(x.seq))?;
+
+        letter.state = SERVING;
+        let ticket = Ticket {
+            index,
+            seq: letter.seq,
+        };
+
+        letter.request.take().map(|x| (ticket, x))
+    }
+
+    /// Answer a request that is being served, then wake up its client.
+    ///
+    /// This returns `false` if the request has been cancelled by its client meanwhile, in which
+    /// case the response is dropped.
+    pub fn reply(&mut self, ticket: Ticket, response: Resp) -> bool {
+        let Some(letter) = self.letter(ticket) else {
+            return false;
+        };
+
+        match letter.state {
+            SERVING => {
+                letter.state = DONE;
+                letter.response.write(response);
+                if let Some(waker) = letter.waker.take() {
+                    waker.wake();
+                }
+                true
+            }
+            _ => {
+                letter.free();
+                false
+            }
+        }
+    }
+
+    /// Take the response of a request once it has been answered, or register the waker of the
+    /// client that awaits it.
+    fn poll(&mut self, ticket: Ticket, waker: Option<&Waker>) -> Option<Resp> {
+        let letter = self.letter(ticket)?;
+
+        if letter.state == DONE {
+            let response = letter.response.take();
+            letter.free();
+            return response;
+        }
+
+        if let Some(waker) = waker {
+            letter.waker.write(waker.clone());
+        }
+
+        None
+    }
+
+    /// Cancel a request, whose slot is freed as soon as it is not served anymore.
+    fn cancel(&mut self, ticket: Ticket) {
+        if let Some(letter) = self.letter(ticket) {
+            match letter.state {
+                SERVING => letter.state = CANCELLED,
+                _ => letter.free(),
+            }
+        }
+    }
+
+    /// Get the slot of a request, unless it has been freed.
+    fn letter(&mut self, ticket: Ticket) -> Option<&mut Letter<Req, Resp>> {
+        self.letters
+            .get_mut(ticket.index)
+            .filter(|x| x.state != FREE && x.seq == ticket.seq)
+    }
+}
+
+impl<Req, Resp, const N: usize> Default for Mailbox<Req, Resp, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A driver whose state holds a mailbox of requests of type `Req`, for the default number of
+/// requests.
+pub trait Mailboxed<Req, Resp>: Driver {
+    /// Get the mailbox out of the driver state.
+    fn mailbox(state: &mut Self::StateType) -> &mut Mailbox<Req, Resp>;
+
+    /// Notify the driver that a new request has been posted (e.g. to start a bus transaction if
+    /// the bus is idle), outside of any critical section.
+    ///
+    /// The default implementation does nothing.
+    fn posted(_state: &StateLock<Self>) {}
+}
+
+/// A request that has been posted to the mailbox of a device, which is cancelled if it is dropped
+/// before its response.
+pub struct Request<'a, D: Mailboxed<Req, Resp> + 'static, Req, Resp> {
+    device: &'a Device<D>,
+    ticket: Ticket,
+    _marker: PhantomData<fn(Req) -> Resp>,
+}
+
+impl<'a, D: Mailboxed<Req, Resp>, Req, Resp> Request<'a, D, Req, Resp> {
+    /// Post a new request to the mailbox of the device of `accessor`.
+    ///
+    /// This returns [`Error::Busy`] if the mailbox is full.
+    pub fn new<Tag>(accessor: &'a Accessor<'_, D, Tag>, request: Req) -> Result<Self> {
+        let device = accessor.inner();
+        let ticket = critical_section::with(|cs| {
+            D::mailbox(&mut device.state.borrow_ref_mut(cs)).post(request)
+        })
+        .map_err(|_| Error::Busy)?;
+
+        D::posted(&device.state);
+
+        Ok(Request {
+            device,
+            ticket,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Get the response if the request has been answered, without waiting.
+    pub fn try_response(&mut self) -> Option<Resp> {
+        self.poll(None)
+    }
+
+    /// Wait for the response of the request, then return it.
+    pub async fn response(mut self) -> Resp {
+        poll_fn(|cx| match self.poll(Some(cx.waker())) {
+            Some(response) => Poll::Ready(response),
+            None => Poll::Pending,
+        })
+        .await
+    }
+
+    /// Take the response out of the mailbox, or register the waker of the client.
+    fn poll(&mut self, waker: Option<&Waker>) -> Option<Resp> {
+        critical_section::with(|cs| {
+            D::mailbox(&mut self.device.state.borrow_ref_mut(cs)).poll(self.ticket, waker)
+        })
+    }
+}
+
+impl<D: Mailboxed<Req, Resp>, Req, Resp> Drop for Request<'_, D, Req, Resp> {
+    fn drop(&mut self) {
+        critical_section::with(|cs| {
+            D::mailbox(&mut self.device.state.borrow_ref_mut(cs)).cancel(self.ticket)
+        })
+    }
+}
+
+impl<'d, D: Driver, Tag> Accessor<'d, D, Tag> {
+    /// Post a request to the mailbox of this device (see [`Mailboxed`]), whose response is then
+    /// awaited through the returned [`Request`].
+    ///
+    /// This returns [`Error::Busy`] if the mailbox is full.
+    pub fn request<Req, Resp>(&self, request: Req) -> Result<Request<'_, D, Req, Resp>>
+    where
+        D: Mailboxed<Req, Resp>,
+    {
+        Request::new(self, request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use googletest::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn it_should_be_empty_when_zeroed() -> googletest::Result<()> {
+        let mut mailbox: Mailbox<u32, u32> = unsafe { core::mem::zeroed() };
+
+        verify_that!(mailbox.pending(), eq(0))?;
+        verify_that!(mailbox.take(), none())
+    }
+
+    #[test]
+    fn it_should_serve_requests_in_order() -> googletest::Result<()> {
+        let mut mailbox: Mailbox<u32, u32, 2> = Mailbox::new();
+
+        let a = mailbox.post(1).map_err(|_| Error::Busy)?;
+        let b = mailbox.post(2).map_err(|_| Error::Busy)?;
+        verify_that!(mailbox.post(3), err(eq(3)))?;
+
+        // The first slot is reused by a later request, which is served last.
+        let (ticket, request) = mailbox.take().ok_or(Error::Undefined)?;
+        verify_that!((ticket, request), (eq(a), eq(1)))?;
+        verify_that!(mailbox.reply(ticket, 10), eq(true))?;
+        verify_that!(mailbox.poll(a, None), some(eq(10)))?;
+
+        let c = mailbox.post(3).map_err(|_| Error::Busy)?;
+        verify_that!(mailbox.take().map(|x| x.1), some(eq(2)))?;
+        verify_that!(mailbox.take().map(|x| x.1), some(eq(3)))?;
+
+        // A cancelled request is freed once it is answered.
+        mailbox.cancel(c);
+        verify_that!(mailbox.reply(c, 30), eq(false))?;
+        verify_that!(mailbox.reply(b, 20), eq(true))?;
+        verify_that!(mailbox.poll(c, None), none())?;
+        verify_that!(mailbox.poll(b, None), some(eq(20)))
+    }
+}
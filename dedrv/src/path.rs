@@ -0,0 +1,228 @@
+//! The device paths.
+//!
+//! A device path is a sequence of components that are separated by `/`, starting from the root
+//! (e.g. `/soc/i2c0/eeprom`). The [`Path`] type guarantees that a path is well-formed, then it
+//! offers the hierarchy operations that are required by lookups (e.g. the devices under a bus).
+
+use core::fmt::{Debug, Display};
+
+use crate::{fnv1a, Error, Result};
+
+/// A validated device path.
+///
+/// This is an unsized type, like `str`, so it is always used behind a reference. A path starts
+/// with `/`, it does not end with `/` (except the root path), it has no empty component, and it
+/// only contains alphanumeric characters, `/`, `_`, `-` and `.` (i.e. the paths that are accepted
+/// by the [`device`](crate::device) attribute).
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(transparent)]
+pub struct Path(str);
+
+impl Path {
+    /// The root path, which is the parent of every top-level device.
+    pub const ROOT: &'static Path = Path::from_static("/");
+
+    /// Validate a path.
+    ///
+    /// This returns [`Error::InvalidPath`] if the path is not well-formed.
+    pub const fn new(path: &str) -> Result<&Path> {
+        if !is_valid(path) {
+            return Err(Error::InvalidPath);
+        }
+
+        // SAFETY: A path is a transparent wrapper of a `str`.
+        Ok(unsafe { &*(path as *const str as *const Path) })
+    }
+
+    /// Validate a static path at compile time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the path is not well-formed, which fails the build in a `const` context.
+    pub const fn from_static(path: &'static str) -> &'static Path {
+        match Path::new(path) {
+            Ok(x) => x,
+            Err(_) => panic!("invalid device path"),
+        }
+    }
+
+    /// Get the path as a string.
+    pub const fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Get the compact identifier of the path.
+    pub const fn id(&self) -> PathId {
+        PathId(fnv1a(self.0.as_bytes()))
+    }
+
+    /// Check whether this is the root path.
+    pub const fn is_root(&self) -> bool {
+        self.0.len() == 1
+    }
+
+    /// Iterate over the components of the path, from the root (e.g. `soc`, `i2c0`, then `eeprom`
+    /// for `/soc/i2c0/eeprom`).
+    ///
+    /// The root path has no component.
+    pub fn components(&self) -> impl DoubleEndedIterator<Item = &str> {
+        self.0[1..].split('/').filter(|x| !x.is_empty())
+    }
+
+    /// Get the last component of the path, or `None` for the root path.
+    pub fn name(&self) -> Option<&str> {
+        self.components().next_back()
+    }
+
+    /// Get the parent of the path, or `None` for the root path.
+    pub fn parent(&self) -> Option<&Path> {
+        if self.is_root() {
+            return None;
+        }
+
+        let end = self.0.rfind('/').unwrap_or(0).max(1);
+
+        // SAFETY: The parent of a well-formed path is well-formed.
+        Some(unsafe { &*(&self.0[..end] as *const str as *const Path) })
+    }
+
+    /// Check whether `base` is this path or one of its ancestors.
+    ///
+    /// Only whole components are considered, so `/uart` is not an ancestor of `/uart0`.
+    pub fn starts_with(&self, base: &Path) -> bool {
+        let mut components = self.components();
+        base.components().all(|x| components.next() == Some(x))
+    }
+}
+
+impl AsRef<str> for Path {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq<str> for Path {
+    fn eq(&self, other: &str) -> bool {
+        &self.0 == other
+    }
+}
+
+impl PartialEq<&str> for Path {
+    fn eq(&self, other: &&str) -> bool {
+        &self.0 == *other
+    }
+}
+
+impl<'a> TryFrom<&'a str> for &'a Path {
+    type Error = Error;
+
+    fn try_from(path: &'a str) -> Result<Self> {
+        Path::new(path)
+    }
+}
+
+/// The compact identifier of a device path, which is the 32-bit FNV-1a hash of the path.
+///
+/// The identifier of a static path is computed at compile time (e.g. `const UART0: PathId =
+/// PathId::of("/uart0")`), so a lookup by identifier (i.e. `dedrv::find_id`, with the `path-id`
+/// feature) compares integers rather than strings in flash. Since two paths may share an
+/// identifier, the identifiers are checked to be unique once, at init.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PathId(u32);
+
+impl PathId {
+    /// Get the identifier of a static path at compile time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the path is not well-formed, which fails the build in a `const` context.
+    pub const fn of(path: &'static str) -> Self {
+        Path::from_static(path).id()
+    }
+
+    /// Get the raw value of the identifier.
+    pub const fn get(self) -> u32 {
+        self.0
+    }
+}
+
+impl Debug for Path {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        Debug::fmt(&self.0, f)
+    }
+}
+
+impl Display for Path {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.pad(&self.0)
+    }
+}
+
+/// Check that a path is well-formed, with the same rules as the [`device`](crate::device)
+/// attribute.
+const fn is_valid(path: &str) -> bool {
+    dedrv_path::check(path).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use googletest::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn it_should_reject_malformed_paths() -> googletest::Result<()> {
+        for path in ["", "gpio0", "/gpio0/", "/soc//gpio0"] {
+            verify_that!(Path::new(path), err(eq(&Error::InvalidPath)))?;
+        }
+
+        verify_that!(Path::new("/").map(Path::is_root), ok(eq(&true)))
+    }
+
+    #[test]
+    fn it_should_identify_paths_at_compile_time() -> googletest::Result<()> {
+        const UART0: PathId = PathId::of("/uart0");
+
+        verify_that!(Path::from_static("/uart0").id(), eq(UART0))?;
+        verify_that!(PathId::of("/uart1"), not(eq(UART0)))
+    }
+
+    #[test]
+    fn it_should_iterate_over_components() -> googletest::Result<()> {
+        let path = Path::from_static("/soc/i2c0/eeprom");
+
+        verify_that!(
+            path.components().collect::<Vec<_>>(),
+            elements_are![eq(&"soc"), eq(&"i2c0"), eq(&"eeprom")]
+        )?;
+        verify_that!(path.name(), some(eq("eeprom")))?;
+        verify_that!(Path::ROOT.components().count(), eq(0))?;
+        verify_that!(Path::ROOT.name(), none())
+    }
+
+    #[test]
+    fn it_should_walk_up_to_root() -> googletest::Result<()> {
+        let path = Path::from_static("/soc/i2c0");
+
+        verify_that!(path.parent().map(Path::as_str), some(eq("/soc")))?;
+        verify_that!(
+            path.parent().and_then(Path::parent).map(Path::as_str),
+            some(eq("/"))
+        )?;
+        verify_that!(Path::ROOT.parent(), none())
+    }
+
+    #[test]
+    fn it_should_match_whole_components() -> googletest::Result<()> {
+        let path = Path::from_static("/soc/uart0");
+
+        verify_that!(path.starts_with(Path::from_static("/soc")), eq(true))?;
+        verify_that!(path.starts_with(path), eq(true))?;
+        verify_that!(path.starts_with(Path::ROOT), eq(true))?;
+        verify_that!(path.starts_with(Path::from_static("/so")), eq(false))?;
+        verify_that!(
+            Path::from_static("/soc").starts_with(Path::from_static("/soc/uart0")),
+            eq(false)
+        )
+    }
+}
@@ -0,0 +1,242 @@
+//! The cooperative polling of the drivers without interrupts.
+//!
+//! Some drivers have no interrupt to rely on (e.g. a bit-banged bus or a slow sensor), so they
+//! must be serviced periodically instead. Such a driver declares its polling part as a static
+//! [`DevicePoll`] that is bound to a device, with the period between two polls, then the
+//! application registers it with [`register`]:
+//!
+//! ```rust,ignore
+//! static TEMP0_POLL: DevicePoll<TempDriver> = DevicePoll::new(&TEMP0, TempDriver::sample, 100);
+//!
+//! dedrv::clock::set_clock(|| monotonic_ms());
+//! dedrv::poll::register(&TEMP0_POLL)?;
+//!
+//! loop {
+//!     dedrv::poll::run_pending();
+//!     // ...
+//! }
+//! ```
+//!
+//! The periods are expressed in ticks of the clock of the framework (see [`clock`](crate::clock)). Until a clock
+//! is registered, every device is due at each call of [`run_pending`]. Only the initialized
+//! devices are polled, so a device may be registered before [`init`](crate::init).
+//!
+//! At most [`CAPACITY`] devices are registered at once, so registering never allocates.
+
+use core::cell::{Cell, RefCell};
+
+use critical_section::Mutex;
+
+use crate::clock::now;
+use crate::{Device, Driver, Error, Lifecycle, Result, StateLock};
+
+/// The maximum number of registered devices.
+pub const CAPACITY: usize = 16;
+
+/// The registered devices.
+static POLLED: Mutex<RefCell<[Option<&'static dyn Polled>; CAPACITY]>> =
+    Mutex::new(RefCell::new([None; CAPACITY]));
+
+/// A device that is polled periodically.
+pub trait Polled: Sync {
+    /// Poll the device, then return whether it has been polled (i.e. whether it is initialized).
+    fn poll(&self) -> bool;
+
+    /// The period between two polls, in clock ticks.
+    fn period(&self) -> u64;
+
+    /// The time of the next poll, in clock ticks.
+    fn deadline(&self) -> u64;
+
+    /// Set the time of the next poll, in clock ticks.
+    fn set_deadline(&self, deadline: u64);
+}
+
+/// A polled device, which runs a function on the driver state at a fixed period.
+pub struct DevicePoll<D: Driver + 'static> {
+    device: &'static Device<D>,
+    func: fn(&StateLock<D>),
+    period: u64,
+    deadline: Mutex<Cell<u64>>,
+}
+
+impl<D: Driver> DevicePoll<D> {
+    /// Create a new polled device that runs `func` on the driver state of `device` every `period`
+    /// clock ticks.
+    pub const fn new(device: &'static Device<D>, func: fn(&StateLock<D>), period: u64) -> Self {
+        DevicePoll {
+            device,
+            func,
+            period,
+            deadline: Mutex::new(Cell::new(0)),
+        }
+    }
+}
+
+impl<D: Driver> Polled for DevicePoll<D>
+where
+    Device<D>: Sync,
+{
+    fn poll(&self) -> bool {
+        if self.device.lifecycle() != Lifecycle::Initialized {
+            return false;
+        }
+
+        (self.func)(&self.device.state);
+        true
+    }
+
+    fn period(&self) -> u64 {
+        self.period
+    }
+
+    fn deadline(&self) -> u64 {
+        critical_section::with(|cs| self.deadline.borrow(cs).get())
+    }
+
+    fn set_deadline(&self, deadline: u64) {
+        critical_section::with(|cs| self.deadline.borrow(cs).set(deadline))
+    }
+}
+
+/// Register a polled device, which is then due right away.
+///
+/// Registering a device that is already registered does nothing, and this returns
+/// [`Error::PollListFull`] if [`CAPACITY`] devices are already registered.
+pub fn register(polled: &'static dyn Polled) -> Result<()> {
+    critical_section::with(|cs| {
+        let mut list = POLLED.borrow_ref_mut(cs);
+        if list
+            .iter()
+            .flatten()
+            .any(|x| core::ptr::addr_eq(*x, polled))
+        {
+            return Ok(());
+        }
+
+        let slot = list
+            .iter_mut()
+            .find(|x| x.is_none())
+            .ok_or(Error::PollListFull)?;
+
+        polled.set_deadline(0);
+        *slot = Some(polled);
+        Ok(())
+    })
+}
+
+/// Unregister a polled device, then return whether it was registered.
+pub fn unregister(polled: &'static dyn Polled) -> bool {
+    critical_section::with(|cs| {
+        POLLED
+            .borrow_ref_mut(cs)
+            .iter_mut()
+            .find(|x| x.is_some_and(|x| core::ptr::addr_eq(x, polled)))
+            .map(|x| x.take())
+            .is_some()
+    })
+}
+
+/// Poll every registered device, whether it is due or not, then return the number of devices
+/// that have been polled.
+pub fn run_once() -> usize {
+    run(|_| true)
+}
+
+/// Poll every registered device whose period has elapsed since its last poll, then return the
+/// number of devices that have been polled.
+///
+/// Each device is polled outside of the critical section, so a slow poll does not hold up the
+/// interrupts.
+pub fn run_pending() -> usize {
+    match now() {
+        Some(now) => run(|x| x.deadline() <= now),
+        None => run_once(),
+    }
+}
+
+/// Get the time of the next poll among the registered devices, in clock ticks (e.g. for sleeping
+/// until then), or `None` if no device is registered.
+pub fn next_deadline() -> Option<u64> {
+    critical_section::with(|cs| {
+        POLLED
+            .borrow_ref(cs)
+            .iter()
+            .flatten()
+            .map(|x| x.deadline())
+            .min()
+    })
+}
+
+/// Poll every registered device that is `due`, then schedule its next poll.
+fn run(due: impl Fn(&dyn Polled) -> bool) -> usize {
+    let mut count = 0;
+
+    for i in 0..CAPACITY {
+        let Some(polled) = critical_section::with(|cs| POLLED.borrow_ref(cs)[i]) else {
+            continue;
+        };
+
+        if !due(polled) {
+            continue;
+        }
+
+        if polled.poll() {
+            count += 1;
+        }
+
+        if let Some(now) = now() {
+            polled.set_deadline(now.saturating_add(polled.period()));
+        }
+    }
+
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use core::sync::atomic::{AtomicU64, Ordering};
+
+    use googletest::prelude::*;
+
+    use super::*;
+    use crate::testing::{bump, with_clock, CounterDriver};
+
+    static DEVICE: Device<CounterDriver> = Device::new();
+    static TIME: AtomicU64 = AtomicU64::new(0);
+
+    fn count() -> u32 {
+        critical_section::with(|cs| *DEVICE.state.borrow_ref(cs))
+    }
+
+    #[test]
+    fn it_should_poll_due_devices() -> googletest::Result<()> {
+        static POLL: DevicePoll<CounterDriver> = DevicePoll::new(&DEVICE, bump, 10);
+
+        register(&POLL)?;
+        register(&POLL)?;
+
+        // A device is not polled until it is initialized.
+        verify_that!(run_once(), eq(0))?;
+        DEVICE.init();
+
+        with_clock(
+            || TIME.load(Ordering::Relaxed),
+            || {
+                verify_that!(run_pending(), eq(1))?;
+                verify_that!(next_deadline(), some(eq(10)))?;
+
+                TIME.store(9, Ordering::Relaxed);
+                verify_that!(run_pending(), eq(0))?;
+                TIME.store(10, Ordering::Relaxed);
+                verify_that!(run_pending(), eq(1))
+            },
+        )?;
+        verify_that!(run_once(), eq(1))?;
+        verify_that!(count(), eq(3))?;
+
+        verify_that!(unregister(&POLL), eq(true))?;
+        verify_that!(unregister(&POLL), eq(false))?;
+        verify_that!(run_once(), eq(0))
+    }
+}
@@ -0,0 +1,122 @@
+//! The profiling of the class method durations.
+//!
+//! With the `method-duration` feature, each class method that is called through an [`Accessor`]
+//! is timed with the clock of the framework (see [`clock`](crate::clock)), and the maximum
+//! duration is recorded per device. The whole call is timed, from the accessor to the return of
+//! the driver, so a maximum is an upper bound of the time that the driver holds the critical
+//! section of the device state, not a measurement of it.
+//!
+//! Without the feature, or until a clock is registered, the class methods are not timed at all.
+//!
+//! [`Accessor`]: crate::Accessor
+
+#[cfg(feature = "method-duration")]
+use core::cell::Cell;
+
+#[cfg(feature = "method-duration")]
+use critical_section::Mutex;
+
+#[cfg(feature = "method-duration")]
+use crate::clock;
+use crate::{Device, Driver};
+
+/// The profiling record of a device instance.
+#[cfg(feature = "method-duration")]
+pub(crate) struct Profile {
+    max: Mutex<Cell<u64>>,
+}
+
+#[cfg(feature = "method-duration")]
+impl Profile {
+    pub(crate) const fn new() -> Self {
+        Profile {
+            max: Mutex::new(Cell::new(0)),
+        }
+    }
+
+    /// The maximum recorded duration.
+    pub(crate) fn max(&self) -> u64 {
+        critical_section::with(|cs| self.max.borrow(cs).get())
+    }
+
+    /// Reset the maximum recorded duration.
+    pub(crate) fn reset(&self) {
+        critical_section::with(|cs| self.max.borrow(cs).set(0));
+    }
+
+    fn record(&self, duration: u64) {
+        critical_section::with(|cs| {
+            let max = self.max.borrow(cs);
+            max.set(max.get().max(duration));
+        });
+    }
+}
+
+/// Call a class method of the driver on `device`, while timing it.
+///
+/// This is called by the code that is generated by the [`class`](crate::class) attribute.
+#[doc(hidden)]
+#[inline(always)]
+pub fn measure<D: Driver, R>(device: &Device<D>, f: impl FnOnce() -> R) -> R {
+    #[cfg(feature = "method-duration")]
+    if let Some(start) = clock::now() {
+        let result = f();
+        let end = clock::now().unwrap_or(start);
+        device.profile.record(end.wrapping_sub(start));
+        return result;
+    }
+
+    #[cfg(not(feature = "method-duration"))]
+    let _ = device;
+
+    f()
+}
+
+/// Call `f` with the path and the maximum class method duration of each device.
+#[cfg(feature = "method-duration")]
+pub fn report(mut f: impl FnMut(&'static crate::Path, u64)) -> crate::Result<()> {
+    let (early, devices) = (
+        crate::Registry::early().table()?,
+        crate::Registry::devices().table()?,
+    );
+
+    for desc in early.iter().chain(devices) {
+        f(desc.path(), desc.max_duration());
+    }
+
+    Ok(())
+}
+
+#[cfg(all(test, feature = "method-duration"))]
+mod tests {
+    use core::sync::atomic::{AtomicU64, Ordering};
+
+    use googletest::prelude::*;
+
+    use super::*;
+    use crate::testing::{with_clock, NoopDriver};
+
+    static TICKS: AtomicU64 = AtomicU64::new(0);
+
+    #[test]
+    fn it_should_record_max_duration() -> googletest::Result<()> {
+        let device: Device<NoopDriver> = Device::new();
+
+        with_clock(
+            || TICKS.load(Ordering::Relaxed),
+            || {
+                for duration in [3, 7, 5] {
+                    measure(&device, || TICKS.fetch_add(duration, Ordering::Relaxed));
+                }
+            },
+        );
+
+        // The class methods are not timed without a clock.
+        measure(&device, || TICKS.fetch_add(100, Ordering::Relaxed));
+
+        verify_that!(device.max_duration(), eq(7))?;
+
+        device.reset_max_duration();
+        verify_that!(device.max_duration(), eq(0))
+    }
+}
@@ -0,0 +1,312 @@
+//! The queue primitives to be used in a driver internal state.
+//!
+//! These queues are meant to live inside [`Driver::StateType`](crate::Driver::StateType), so they
+//! are protected by the [`StateLock`](crate::StateLock) of the device. As a result, they do not
+//! need any synchronization on their own, and they may be shared between an interrupt handler
+//! and a task as long as both borrow the driver state from a critical section.
+//!
+//! The queues keep their own statistics (see [`QueueStats`]), so a driver whose reception queue
+//! lives in its state (see [`Streamed`]) lets its users tune their flow control through any
+//! accessor, without a driver-specific method:
+//!
+//! ```rust,ignore
+//! let stats = CAN0.can().queue_stats();
+//! if stats.overruns > 0 {
+//!     warn!("{} frames lost, {} of {} slots used at most", stats.overruns, stats.high_watermark, stats.capacity);
+//! }
+//! ```
+
+use core::fmt::Debug;
+use core::mem::MaybeUninit;
+
+use crate::{Accessor, Driver};
+
+/// The statistics of a queue.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct QueueStats {
+    /// The number of elements in the queue.
+    pub pending: usize,
+
+    /// The maximum number of elements of the queue.
+    pub capacity: usize,
+
+    /// The largest number of elements that have been in the queue at once.
+    pub high_watermark: usize,
+
+    /// The number of elements that have been rejected because the queue was full.
+    pub overruns: u32,
+}
+
+/// A queue that keeps its statistics.
+pub trait Introspect {
+    /// Get the statistics of the queue.
+    fn stats(&self) -> QueueStats;
+
+    /// Reset the high watermark and the overrun count of the queue.
+    fn reset_stats(&mut self);
+}
+
+/// A single-producer single-consumer FIFO queue with a fixed capacity of `N` elements.
+///
+/// A driver internal state is zeroed when the device is created (see
+/// [`Device::new`](crate::Device::new)), which is a valid empty queue. Otherwise, the queue is
+/// `const`-constructible with [`Spsc::new`].
+///
+/// Typically, the producer is an interrupt handler (e.g. UART reception) and the consumer is a
+/// task that reads the received data through a device class method.
+pub struct Spsc<T, const N: usize> {
+    buffer: [MaybeUninit<T>; N],
+    head: usize,
+    len: usize,
+    high_watermark: usize,
+    overruns: u32,
+}
+
+impl<T, const N: usize> Spsc<T, N> {
+    /// Create a new empty queue.
+    pub const fn new() -> Self {
+        Spsc {
+            buffer: [const { MaybeUninit::uninit() }; N],
+            head: 0,
+            len: 0,
+            high_watermark: 0,
+            overruns: 0,
+        }
+    }
+
+    /// The maximum number of elements of the queue.
+    #[inline(always)]
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// The number of elements in the queue.
+    #[inline(always)]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Check whether the queue is empty.
+    #[inline(always)]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Check whether the queue is full.
+    #[inline(always)]
+    pub const fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    /// Push an element at the back of the queue.
+    ///
+    /// If the queue is full, the element is given back as an error, which counts as an overrun.
+    pub fn push(&mut self, value: T) -> Result<(), T> {
+        if self.is_full() {
+            self.overruns = self.overruns.saturating_add(1);
+            return Err(value);
+        }
+
+        let tail = (self.head + self.len) % N;
+        self.buffer[tail].write(value);
+        self.len += 1;
+        self.high_watermark = self.high_watermark.max(self.len);
+
+        Ok(())
+    }
+
+    /// Pop the element at the front of the queue.
+    pub fn pop(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+
+        // SAFETY: The queue is not empty, so the head slot has been initialized by a push. It is
+        // considered uninitialized again as soon as the head moves forward.
+        let value = unsafe { self.buffer[self.head].assume_init_read() };
+        self.head = (self.head + 1) % N;
+        self.len -= 1;
+
+        Some(value)
+    }
+
+    /// Get a reference to the element at the front of the queue, without popping it.
+    pub fn peek(&self) -> Option<&T> {
+        if self.is_empty() {
+            return None;
+        }
+
+        // SAFETY: The queue is not empty, so the head slot has been initialized by a push.
+        Some(unsafe { self.buffer[self.head].assume_init_ref() })
+    }
+
+    /// Drop every element of the queue.
+    pub fn clear(&mut self) {
+        while self.pop().is_some() {}
+    }
+
+    /// Iterate over the elements of the queue, from front to back.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        (0..self.len).map(move |i| {
+            // SAFETY: Every slot between the head and the tail has been initialized by a push.
+            unsafe { self.buffer[(self.head + i) % N].assume_init_ref() }
+        })
+    }
+}
+
+impl<T: Copy, const N: usize> Spsc<T, N> {
+    /// Push as many elements of `values` as possible, then return the number of pushed elements.
+    ///
+    /// Each element that does not fit counts as an overrun.
+    pub fn push_slice(&mut self, values: &[T]) -> usize {
+        let pushed = values.iter().take_while(|&&x| self.push(x).is_ok()).count();
+
+        // The first rejected element has been counted by its push already.
+        let rejected = values.len().saturating_sub(pushed + 1);
+        self.overruns = self.overruns.saturating_add(rejected as u32);
+
+        pushed
+    }
+
+    /// Pop as many elements as possible into `buf`, then return the number of popped elements.
+    pub fn pop_slice(&mut self, buf: &mut [T]) -> usize {
+        buf.iter_mut()
+            .map_while(|x| self.pop().map(|v| *x = v))
+            .count()
+    }
+}
+
+impl<T, const N: usize> Introspect for Spsc<T, N> {
+    fn stats(&self) -> QueueStats {
+        QueueStats {
+            pending: self.len,
+            capacity: N,
+            high_watermark: self.high_watermark,
+            overruns: self.overruns,
+        }
+    }
+
+    fn reset_stats(&mut self) {
+        self.high_watermark = self.len;
+        self.overruns = 0;
+    }
+}
+
+impl<T, const N: usize> Default for Spsc<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Debug, const N: usize> Debug for Spsc<T, N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T, const N: usize> Drop for Spsc<T, N> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+/// A driver of a stream class (e.g. a UART or a CAN controller) whose reception queue lives in
+/// its state.
+pub trait Streamed: Driver {
+    /// Get the reception queue out of the driver state.
+    fn rx_queue(state: &mut Self::StateType) -> &mut dyn Introspect;
+}
+
+impl<'d, D: Streamed, Tag> Accessor<'d, D, Tag> {
+    /// Get the statistics of the reception queue of this device (see [`Streamed`]).
+    pub fn queue_stats(&self) -> QueueStats {
+        critical_section::with(|cs| D::rx_queue(&mut self.inner().state.borrow_ref_mut(cs)).stats())
+    }
+
+    /// Reset the high watermark and the overrun count of the reception queue of this device.
+    pub fn reset_queue_stats(&self) {
+        critical_section::with(|cs| {
+            D::rx_queue(&mut self.inner().state.borrow_ref_mut(cs)).reset_stats()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use googletest::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn it_should_be_valid_when_zeroed() -> googletest::Result<()> {
+        let mut queue: Spsc<u8, 4> = unsafe { core::mem::zeroed() };
+
+        verify_that!(queue.is_empty(), eq(true))?;
+        verify_that!(queue.pop(), none())?;
+        verify_that!(queue.push(1), ok(eq(())))?;
+        verify_that!(queue.pop(), some(eq(1)))
+    }
+
+    #[test]
+    fn it_should_pop_in_fifo_order_across_wrap() -> googletest::Result<()> {
+        let mut queue: Spsc<u32, 3> = Spsc::new();
+
+        for round in 0..4 {
+            verify_that!(queue.push_slice(&[round, round + 1]), eq(2))?;
+            verify_that!(queue.pop(), some(eq(round)))?;
+            verify_that!(queue.pop(), some(eq(round + 1)))?;
+        }
+
+        verify_that!(queue.is_empty(), eq(true))
+    }
+
+    #[test]
+    fn it_should_reject_push_when_full() -> googletest::Result<()> {
+        let mut queue: Spsc<u8, 2> = Spsc::new();
+
+        verify_that!(queue.push_slice(b"abc"), eq(2))?;
+        verify_that!(queue.is_full(), eq(true))?;
+        verify_that!(queue.push(b'd'), err(eq(b'd')))?;
+        verify_that!(queue.peek(), some(eq(&b'a')))?;
+
+        let mut buf = [0u8; 4];
+        verify_that!(queue.pop_slice(&mut buf), eq(2))?;
+        verify_that!(&buf[..2], eq(b"ab"))
+    }
+
+    #[test]
+    fn it_should_keep_statistics() -> googletest::Result<()> {
+        let mut queue: Spsc<u8, 3> = Spsc::new();
+
+        verify_that!(queue.push_slice(b"abcde"), eq(3))?;
+        queue.pop();
+        verify_that!(
+            queue.stats(),
+            eq(QueueStats {
+                pending: 2,
+                capacity: 3,
+                high_watermark: 3,
+                overruns: 2,
+            })
+        )?;
+
+        queue.reset_stats();
+        verify_that!(
+            (queue.stats().high_watermark, queue.stats().overruns),
+            (eq(2), eq(0))
+        )
+    }
+
+    #[test]
+    fn it_should_drop_remaining_elements() -> googletest::Result<()> {
+        let marker = std::rc::Rc::new(());
+
+        let mut queue: Spsc<std::rc::Rc<()>, 4> = Spsc::new();
+        queue.push(marker.clone()).ok();
+        queue.push(marker.clone()).ok();
+        verify_that!(std::rc::Rc::strong_count(&marker), eq(3))?;
+
+        drop(queue);
+        verify_that!(std::rc::Rc::strong_count(&marker), eq(1))
+    }
+}
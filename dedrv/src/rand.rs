@@ -0,0 +1,101 @@
+//! The entropy class, and the crate-level randomness facade.
+//!
+//! The application registers its hardware RNG as the entropy source, then any code (e.g. a crypto
+//! or a network stack) pulls randomness through the registry, without knowing the RNG driver:
+//!
+//! ```rust,ignore
+//! dedrv::rand::set_source(&RNG0);
+//!
+//! let mut nonce = [0u8; 12];
+//! dedrv::rand::fill(&mut nonce)?;
+//! ```
+//!
+//! With the `rand-core` feature, [`Rng`] implements the `rand_core` traits over the source.
+
+use core::cell::Cell;
+
+use critical_section::Mutex;
+
+use crate::{Accessor, Device, Error, Result};
+
+/// A source of entropy (e.g. a true random number generator).
+#[crate::class]
+pub trait Entropy {
+    /// Fill `buf` with random bytes.
+    fn fill(&mut self, buf: &mut [u8]) -> Result<()>;
+}
+
+/// The entropy source of the facade, which is implemented by the devices of the [`Entropy`]
+/// class.
+pub trait Source: Sync {
+    /// Fill `buf` with random bytes.
+    fn fill(&self, buf: &mut [u8]) -> Result<()>;
+}
+
+impl<D: driver::Entropy> Source for Device<D>
+where
+    Device<D>: Sync,
+{
+    fn fill(&self, buf: &mut [u8]) -> Result<()> {
+        Entropy::fill(&mut self.try_accessor::<tag::Entropy>()?, buf)
+    }
+}
+
+/// The registered source.
+static SOURCE: Mutex<Cell<Option<&'static dyn Source>>> = Mutex::new(Cell::new(None));
+
+/// Register the entropy source of the facade (e.g. the RNG device), which replaces the previous
+/// one (if any).
+pub fn set_source(source: &'static dyn Source) {
+    critical_section::with(|cs| SOURCE.borrow(cs).set(Some(source)));
+}
+
+/// Fill `buf` with random bytes out of the registered entropy source.
+///
+/// This returns [`Error::NoEntropySource`] if no source has been registered, or
+/// [`Error::NotReady`] if the source device has not been initialized.
+pub fn fill(buf: &mut [u8]) -> Result<()> {
+    critical_section::with(|cs| SOURCE.borrow(cs).get())
+        .ok_or(Error::NoEntropySource)?
+        .fill(buf)
+}
+
+/// The random number generator of the facade, which pulls from the registered entropy source.
+///
+/// With the `rand-core` feature, this implements `rand_core::RngCore` and
+/// `rand_core::CryptoRng`, so the registered source must be cryptographically secure.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Rng;
+
+#[cfg(feature = "rand-core")]
+impl rand_core::RngCore for Rng {
+    fn next_u32(&mut self) -> u32 {
+        let mut buf = [0; 4];
+        self.fill_bytes(&mut buf);
+        u32::from_ne_bytes(buf)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut buf = [0; 8];
+        self.fill_bytes(&mut buf);
+        u64::from_ne_bytes(buf)
+    }
+
+    /// # Panics
+    ///
+    /// Panics if the entropy source fails (see [`Rng::try_fill_bytes`]).
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        fill(dest).expect("entropy source failure")
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> core::result::Result<(), rand_core::Error> {
+        fill(dest).map_err(|_| {
+            let code = core::num::NonZeroU32::new(rand_core::Error::CUSTOM_START)
+                .expect("non-zero error code");
+            rand_core::Error::from(code)
+        })
+    }
+}
+
+#[cfg(feature = "rand-core")]
+impl rand_core::CryptoRng for Rng {}
@@ -0,0 +1,383 @@
+//! The device registries.
+//!
+//! A registry is a table of device descriptors, which is independently initialized and queried.
+//! The devices that are declared with the [`device`](crate::device) attribute are stored in the
+//! early registry (see [`Registry::early`]) or in the default one (see [`Registry::devices`]),
+//! which are both handled by the crate-level functions (e.g. [`init`](crate::init)).
+//!
+//! Other registries are declared with `#[dedrv::device(path = "...", registry = "boot")]`, which
+//! stores the descriptor in a `.dedrv.boot.*` section instead. Such a section must be gathered by
+//! the linker script of the application between two markers, then the registry is created out of
+//! these markers with [`Registry::from_raw`]. This is useful for a bootloader that hands off to an
+//! application, each one owning its own devices. Likewise, a registry may be created out of a
+//! static table with [`Registry::from_slice`], which isolates the devices of a test.
+
+use core::fmt::{Debug, Display, Formatter, Write};
+use core::ptr::NonNull;
+
+use crate::path::Path;
+use crate::{
+    descriptor, report, snapshot, stage, timing, Descriptor, InitPolicy, InitReport, InitTiming,
+    Lifecycle, Result,
+};
+
+#[cfg(target_os = "none")]
+unsafe extern "C" {
+    static __DEDRV_MARKER_EARLY_START: usize;
+    static __DEDRV_MARKER_EARLY_END: usize;
+    static __DEDRV_MARKER_DEVICE_START: usize;
+    static __DEDRV_MARKER_DEVICE_END: usize;
+}
+
+// Weak definitions of the table markers, which all point to the same location. As a result, the
+// tables are empty unless the markers are defined by the linker script (i.e. `dedrv.x`), so one
+// may link without it when no device is registered.
+#[cfg(target_os = "none")]
+core::arch::global_asm!(
+    ".pushsection .dedrv.markers,\"a\"",
+    ".balign 4",
+    ".weak __DEDRV_MARKER_EARLY_START",
+    ".weak __DEDRV_MARKER_EARLY_END",
+    ".weak __DEDRV_MARKER_DEVICE_START",
+    ".weak __DEDRV_MARKER_DEVICE_END",
+    ".weak __DEDRV_MARKER_CONFIG_START",
+    ".weak __DEDRV_MARKER_CONFIG_END",
+    "__DEDRV_MARKER_EARLY_START:",
+    "__DEDRV_MARKER_EARLY_END:",
+    "__DEDRV_MARKER_DEVICE_START:",
+    "__DEDRV_MARKER_DEVICE_END:",
+    "__DEDRV_MARKER_CONFIG_START:",
+    "__DEDRV_MARKER_CONFIG_END:",
+    ".popsection",
+);
+
+/// A table of device descriptors.
+///
+/// The table is validated each time it is accessed, so a stale object, a descriptor built against
+/// another version of this crate or a corrupted table is reported as an error instead of jumping
+/// through a garbage function pointer.
+#[derive(Debug, Clone, Copy)]
+pub struct Registry {
+    start: *const Descriptor,
+    end: *const Descriptor,
+}
+
+impl Registry {
+    /// Create a registry out of a static table of descriptors, which must be sorted by path.
+    pub const fn from_slice(table: &'static [Descriptor]) -> Self {
+        let range = table.as_ptr_range();
+
+        Registry {
+            start: range.start,
+            end: range.end,
+        }
+    }
+
+    /// Create a registry out of the table that lies between two markers (e.g. the symbols that
+    /// delimit a `.dedrv.boot.*` section in the linker script).
+    ///
+    /// # Safety
+    ///
+    /// The memory between `start` and `end` must be readable for the rest of the program, and it
+    /// must not be written meanwhile.
+    pub const unsafe fn from_raw(start: *const Descriptor, end: *const Descriptor) -> Self {
+        Registry { start, end }
+    }
+
+    /// Create an empty registry.
+    pub const fn empty() -> Self {
+        let empty = NonNull::<Descriptor>::dangling().as_ptr() as *const Descriptor;
+
+        Registry {
+            start: empty,
+            end: empty,
+        }
+    }
+
+    /// Get the registry of the early devices, which are initialized before every other device.
+    ///
+    /// On hosted targets, which do not use the linker script, the registry is empty.
+    pub fn early() -> Self {
+        // SAFETY: The markers are defined by the linker script and delimit the early descriptors.
+        #[cfg(target_os = "none")]
+        return unsafe {
+            Self::from_raw(
+                &raw const __DEDRV_MARKER_EARLY_START as *const Descriptor,
+                &raw const __DEDRV_MARKER_EARLY_END as *const Descriptor,
+            )
+        };
+
+        #[cfg(not(target_os = "none"))]
+        Self::empty()
+    }
+
+    /// Get the default registry of the devices.
+    ///
+    /// On hosted targets, which do not use the linker script, the registry is empty.
+    pub fn devices() -> Self {
+        // SAFETY: The markers are defined by the linker script and delimit the device descriptors.
+        #[cfg(target_os = "none")]
+        return unsafe {
+            Self::from_raw(
+                &raw const __DEDRV_MARKER_DEVICE_START as *const Descriptor,
+                &raw const __DEDRV_MARKER_DEVICE_END as *const Descriptor,
+            )
+        };
+
+        #[cfg(not(target_os = "none"))]
+        Self::empty()
+    }
+
+    /// Get the validated descriptor table of this registry.
+    pub fn table(&self) -> Result<&'static [Descriptor]> {
+        // SAFETY: The bounds are either the ones of a static table, or the ones that the caller of
+        // `from_raw` vouched for.
+        unsafe { descriptor::validate_table(self.start, self.end) }
+    }
+
+    /// Initialize every device of this registry, then return the report of their init.
+    ///
+    /// The whole table is validated before any driver is initialized. A device that fails to
+    /// initialize does not prevent the other ones from initializing (see
+    /// [`Registry::init_with_policy`]).
+    pub fn init(&self) -> Result<InitReport<1>> {
+        self.init_with_policy(InitPolicy::Continue)
+    }
+
+    /// Initialize every device of this registry like [`Registry::init`], according to `policy`.
+    pub fn init_with_policy(&self, policy: InitPolicy) -> Result<InitReport<1>> {
+        let table = self.table()?;
+        report::check_tables(&[table])?;
+
+        Ok(report::init_tables([table], policy))
+    }
+
+    /// Initialize every device of this registry like [`Registry::init_with_policy`], while
+    /// measuring the init duration of each device (see [`init_timed`](crate::init_timed)).
+    pub fn init_timed(
+        &self,
+        policy: InitPolicy,
+        now: impl FnMut() -> u64,
+        trace: impl FnMut(InitTiming),
+    ) -> Result<InitReport<1>> {
+        let table = self.table()?;
+        report::check_tables(&[table])?;
+
+        Ok(timing::init_tables([table], policy, now, trace))
+    }
+
+    /// Re-initialize every initialized device of this registry (see [`reset_all`](crate::reset_all)),
+    /// then return the number of devices that refused to reset.
+    pub fn reset(&self) -> Result<usize> {
+        Ok(stage::ordered(self.table()?)
+            .filter(|x| x.lifecycle() == Lifecycle::Initialized)
+            .filter(|x| x.reset().is_err())
+            .count())
+    }
+
+    /// Look up the descriptor of the device at `path` in this registry.
+    ///
+    /// This returns [`Error::DeviceNotFound`](crate::Error::DeviceNotFound) if no device is
+    /// registered at `path`, or [`Error::InvalidPath`](crate::Error::InvalidPath) if `path` is
+    /// not a well-formed [`Path`].
+    pub fn find(&self, path: &str) -> Result<&'static Descriptor> {
+        descriptor::find(self.table()?, Path::new(path)?)
+    }
+
+    /// Look up the descriptor of the device whose path has the identifier `id` in this registry
+    /// (see [`find_id`](crate::find_id)).
+    #[cfg(feature = "path-id")]
+    pub fn find_id(&self, id: crate::PathId) -> Result<&'static Descriptor> {
+        descriptor::find_id(self.table()?, id)
+    }
+
+    /// Write the path, the lifecycle and the driver snapshot of every device of this registry to
+    /// `out` (see [`dump`](crate::dump)).
+    pub fn dump(&self, out: &mut dyn Write) -> core::fmt::Result {
+        dump_tables([*self], out)
+    }
+
+    /// Save the state of every device of this registry into `buf` (see
+    /// [`save_all`](crate::save_all)).
+    pub fn save(&self, buf: &mut [u8]) -> Result<usize> {
+        snapshot::save(self.table()?, buf)
+    }
+
+    /// Restore the state of every device of this registry out of a snapshot that has been taken
+    /// by [`Registry::save`].
+    pub fn restore(&self, data: &[u8]) -> Result<()> {
+        snapshot::restore(self.table()?, data)
+    }
+}
+
+/// The inventory of the devices of one or more registries.
+///
+/// The [`Display`](core::fmt::Display) implementation prints a table with the path, lifecycle and
+/// driver of each device, while the [`Debug`](core::fmt::Debug) implementation prints a list of
+/// devices. An invalid registry is printed as its error.
+#[derive(Clone, Copy)]
+pub struct DeviceTable<const N: usize = 2> {
+    registries: [Registry; N],
+}
+
+impl<const N: usize> DeviceTable<N> {
+    /// Create the inventory of the given registries.
+    pub const fn new(registries: [Registry; N]) -> Self {
+        DeviceTable { registries }
+    }
+
+    /// Iterate over the validated tables of the registries.
+    pub(crate) fn tables(&self) -> impl Iterator<Item = Result<&'static [Descriptor]>> + '_ {
+        self.registries.iter().map(|x| x.table())
+    }
+}
+
+impl From<Registry> for DeviceTable<1> {
+    fn from(registry: Registry) -> Self {
+        DeviceTable::new([registry])
+    }
+}
+
+impl<const N: usize> Display for DeviceTable<N> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        const HEADER: [&str; 3] = ["PATH", "STATE", "DRIVER"];
+
+        let descriptors = || self.tables().flat_map(|x| x.unwrap_or_default());
+        let width = descriptors()
+            .map(|x| x.path().as_str().len())
+            .fold(HEADER[0].len(), usize::max);
+
+        writeln!(f, "{:width$}  {:13}  {}", HEADER[0], HEADER[1], HEADER[2])?;
+
+        for table in self.tables() {
+            match table {
+                Ok(table) => {
+                    for desc in table {
+                        writeln!(
+                            f,
+                            "{:width$}  {:13}  {}",
+                            desc.path(),
+                            desc.lifecycle(),
+                            desc.driver()
+                        )?;
+                    }
+                }
+                Err(e) => writeln!(f, "<{e}>")?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<const N: usize> Debug for DeviceTable<N> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        struct Entry<'a>(&'a Descriptor);
+
+        impl Debug for Entry<'_> {
+            fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+                f.debug_struct("Device")
+                    .field("path", &self.0.path())
+                    .field("lifecycle", &self.0.lifecycle())
+                    .field("driver", &self.0.driver())
+                    .finish()
+            }
+        }
+
+        let mut list = f.debug_list();
+        for table in self.tables() {
+            match table {
+                Ok(table) => list.entries(table.iter().map(Entry)),
+                Err(e) => list.entry(&e),
+            };
+        }
+
+        list.finish()
+    }
+}
+
+/// Dump every device of the given registries, where an invalid registry is written as its error.
+pub(crate) fn dump_tables<const N: usize>(
+    registries: [Registry; N],
+    out: &mut dyn Write,
+) -> core::fmt::Result {
+    for table in DeviceTable::new(registries).tables() {
+        match table {
+            Ok(table) => {
+                for desc in table {
+                    write!(out, "{} ({}): ", desc.path(), desc.lifecycle())?;
+                    desc.dump(out)?;
+                    out.write_str("\n")?;
+                }
+            }
+            Err(e) => writeln!(out, "<{e}>")?,
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use googletest::prelude::*;
+
+    use super::*;
+    use crate::testing::NoopDriver;
+    use crate::{Device, Error, Lifecycle};
+
+    static A: Device<NoopDriver> = Device::new();
+    static B: Device<NoopDriver> = Device::new();
+
+    #[test]
+    fn it_should_init_isolated_registry() -> googletest::Result<()> {
+        static TABLE: [Descriptor; 2] = [Descriptor::new("/a", &A), Descriptor::new("/b", &B)];
+        let registry = Registry::from_slice(&TABLE);
+
+        registry.init()?;
+
+        verify_that!(A.lifecycle(), eq(Lifecycle::Initialized))?;
+        verify_that!(B.lifecycle(), eq(Lifecycle::Initialized))?;
+        verify_that!(
+            registry.find("/b").map(|d| d.path().as_str()),
+            ok(eq(&"/b"))
+        )?;
+        verify_that!(
+            Registry::devices().find("/b").map(|d| d.path()),
+            err(eq(&Error::DeviceNotFound))
+        )
+    }
+
+    #[test]
+    fn it_should_reject_unsorted_registry() -> googletest::Result<()> {
+        static TABLE: [Descriptor; 2] = [Descriptor::new("/b", &B), Descriptor::new("/a", &A)];
+
+        verify_that!(
+            Registry::from_slice(&TABLE).init(),
+            err(eq(&Error::UnsortedDescriptorTable { index: 1 }))
+        )
+    }
+
+    #[test]
+    fn it_should_display_device_table() -> googletest::Result<()> {
+        static C: Device<NoopDriver> = Device::new();
+        static TABLE: [Descriptor; 1] = [Descriptor::new("/uart0", &C)];
+
+        let table = DeviceTable::from(Registry::from_slice(&TABLE));
+        let driver = core::any::type_name::<NoopDriver>();
+
+        verify_that!(
+            table.to_string(),
+            eq(&format!(
+                "PATH    STATE          DRIVER\n/uart0  uninitialized  {driver}\n"
+            ))
+        )?;
+
+        C.init();
+        verify_that!(
+            format!("{table:?}"),
+            eq(&format!(
+                "[Device {{ path: \"/uart0\", lifecycle: Initialized, driver: \"{driver}\" }}]"
+            ))
+        )
+    }
+}
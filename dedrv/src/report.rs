@@ -0,0 +1,285 @@
+use core::fmt::{Debug, Display, Formatter};
+
+use crate::path::Path;
+use crate::{early_print, stage, Descriptor, DeviceFlags, Result};
+
+/// What to do when a device fails to initialize (see [`Driver::probe`](crate::Driver::probe)).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum InitPolicy {
+    /// Keep initializing the other devices, so a board with optional hardware boots degraded.
+    #[default]
+    Continue,
+
+    /// Stop at the first failure, so the remaining devices are left uninitialized.
+    ///
+    /// The failure of an optional device does not stop the init (see [`DeviceFlags::OPTIONAL`]).
+    Abort,
+}
+
+/// The outcome of the initialization of the devices of one or more registries, as returned by
+/// [`init`](crate::init).
+///
+/// The devices are listed in init order (i.e. early devices first, then in stage order), with the
+/// result of their init, or `None` if they have been skipped by [`InitPolicy::Abort`] or if they
+/// are disabled (see [`DeviceFlags::DISABLED`]).
+///
+/// The results are captured at init time, so the report does not change if a device is reset or
+/// enabled afterwards. Only the results of the first [`REPORT_LEN`] devices are kept though, so
+/// the other ones are listed as not recorded (i.e. `None`), while they are still counted by
+/// [`failed`](InitReport::failed).
+#[derive(Clone)]
+pub struct InitReport<const N: usize = 2> {
+    tables: [&'static [Descriptor]; N],
+    results: [Option<Result<()>>; REPORT_LEN],
+    attempted: usize,
+    failed: usize,
+}
+
+/// The number of device init results that are kept by an [`InitReport`].
+pub const REPORT_LEN: usize = 32;
+
+impl<const N: usize> InitReport<N> {
+    /// Get the number of devices that failed to initialize, except the optional ones.
+    pub fn failed(&self) -> usize {
+        self.failed
+    }
+
+    /// Check whether every device has been initialized, except the optional and the disabled ones.
+    pub fn is_complete(&self) -> bool {
+        self.failed == 0
+    }
+
+    /// Check whether the init has been aborted (see [`InitPolicy::Abort`]).
+    pub fn is_aborted(&self) -> bool {
+        let total: usize = self.tables.iter().map(|x| stage::ordered(x).count()).sum();
+        self.attempted < total
+    }
+
+    /// Iterate over the path of each device, with the result of its init (if any).
+    pub fn iter(&self) -> impl Iterator<Item = (&'static Path, Option<Result<()>>)> + '_ {
+        self.descriptors()
+            .map(|(desc, result)| (desc.path(), result))
+    }
+
+    /// Iterate over the descriptor of each device, with the result of its init (if any).
+    fn descriptors(&self) -> impl Iterator<Item = (&'static Descriptor, Option<Result<()>>)> + '_ {
+        self.tables
+            .iter()
+            .flat_map(|x| stage::ordered(x))
+            .enumerate()
+            .map(|(i, desc)| (desc, self.results.get(i).cloned().flatten()))
+    }
+
+    /// Iterate over the path of each device that is missing, i.e. that failed to initialize or
+    /// that has been skipped, except the disabled ones and the ones that are not recorded.
+    pub fn missing(&self) -> impl Iterator<Item = &'static Path> + '_ {
+        self.descriptors()
+            .enumerate()
+            .filter(|(i, (desc, result))| {
+                *i < REPORT_LEN && !matches!(result, Some(Ok(()))) && stage::is_enabled(desc)
+            })
+            .map(|(_, (desc, _))| desc.path())
+    }
+}
+
+impl<const N: usize> Display for InitReport<N> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        for (i, (desc, result)) in self.descriptors().enumerate() {
+            let path = desc.path();
+
+            match result {
+                Some(Ok(())) => writeln!(f, "{path}: ok")?,
+                Some(Err(e)) => writeln!(f, "{path}: {e}")?,
+                None if i >= REPORT_LEN => writeln!(f, "{path}: not recorded")?,
+                None if !stage::is_enabled(desc) => writeln!(f, "{path}: disabled")?,
+                None => writeln!(f, "{path}: skipped")?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<const N: usize> Debug for InitReport<N> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<const N: usize> InitReport<N> {
+    /// Make an empty report of the given tables, where no device has been attempted yet.
+    fn new(tables: [&'static [Descriptor]; N]) -> Self {
+        InitReport {
+            tables,
+            results: [const { None }; REPORT_LEN],
+            attempted: 0,
+            failed: 0,
+        }
+    }
+
+    /// Capture the init result of the next device in init order, which has been attempted.
+    fn record(&mut self, desc: &Descriptor) {
+        if let Some(result) = self.results.get_mut(self.attempted) {
+            *result = desc.init_result();
+        }
+
+        self.attempted += 1;
+        if is_failed(desc) {
+            self.failed += 1;
+        }
+    }
+
+    /// Build the report of the given tables from the recorded init results, where the first
+    /// `attempted` devices in init order have been attempted (e.g. by a parallel init across
+    /// cores).
+    pub(crate) fn from_results(tables: [&'static [Descriptor]; N], attempted: usize) -> Self {
+        let mut report = InitReport::new(tables);
+        for desc in tables
+            .iter()
+            .flat_map(|x| stage::ordered(x))
+            .take(attempted)
+        {
+            report.record(desc);
+        }
+
+        report
+    }
+}
+
+/// Check whether a device failed to initialize, except if it is optional or disabled.
+pub(crate) fn is_failed(desc: &Descriptor) -> bool {
+    stage::is_enabled(desc)
+        && !desc.flags().contains(DeviceFlags::OPTIONAL)
+        && matches!(desc.init_result(), Some(Err(_)))
+}
+
+/// Check the given validated tables before any of their devices is initialized, i.e. that the
+/// path ids are unique and that the dependencies are initialized beforehand.
+pub(crate) fn check_tables(tables: &[&[Descriptor]]) -> Result<()> {
+    #[cfg(feature = "path-id")]
+    crate::descriptor::check_ids(tables)?;
+    stage::check_dependencies(tables)
+}
+
+/// Initialize every device of the given checked tables, in order, according to `policy`.
+///
+/// The devices of each table are initialized in stage order (see [`stage`](crate::stage)), while
+/// the disabled devices are skipped.
+pub(crate) fn init_tables<const N: usize>(
+    tables: [&'static [Descriptor]; N],
+    policy: InitPolicy,
+) -> InitReport<N> {
+    init_tables_with(tables, policy, Descriptor::init)
+}
+
+/// Initialize every device of the given checked tables like [`init_tables`], where each device is
+/// initialized by `init` (e.g. to measure its init duration).
+pub(crate) fn init_tables_with<const N: usize>(
+    tables: [&'static [Descriptor]; N],
+    policy: InitPolicy,
+    mut init: impl FnMut(&'static Descriptor) -> Result<()>,
+) -> InitReport<N> {
+    let mut report = InitReport::new(tables);
+
+    for desc in tables.iter().flat_map(|x| stage::ordered(x)) {
+        if !stage::is_enabled(desc) {
+            report.record(desc);
+            continue;
+        }
+
+        early_print!("dedrv: init {}\n", desc.path());
+        let result = init(desc);
+        report.record(desc);
+
+        if let Err(e) = result {
+            early_print!("dedrv: init {} failed: {}\n", desc.path(), e);
+            if policy == InitPolicy::Abort && !desc.flags().contains(DeviceFlags::OPTIONAL) {
+                break;
+            }
+        }
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use googletest::prelude::*;
+
+    use super::*;
+    use crate::testing::OptionalDriver;
+    use crate::{Device, Lifecycle};
+
+    #[test]
+    fn it_should_abort_at_first_failure() -> googletest::Result<()> {
+        static A: Device<OptionalDriver> = Device::new();
+        static B: Device<OptionalDriver> = Device::new();
+        static TABLE: [Descriptor; 2] = [Descriptor::new("/a", &A), Descriptor::new("/b", &B)];
+
+        critical_section::with(|cs| *B.state_ref_mut(cs) = true);
+        let report = init_tables([&TABLE], InitPolicy::Abort);
+
+        verify_that!((report.failed(), report.is_aborted()), (eq(1), eq(true)))?;
+        verify_that!(B.lifecycle(), eq(Lifecycle::Uninitialized))?;
+        verify_that!(
+            report.to_string(),
+            eq("/a: device not found\n/b: skipped\n")
+        )
+    }
+
+    #[test]
+    fn it_should_honor_device_flags() -> googletest::Result<()> {
+        static A: Device<OptionalDriver> = Device::new();
+        static B: Device<OptionalDriver> = Device::new();
+        static C: Device<OptionalDriver> = Device::new();
+        static TABLE: [Descriptor; 3] = [
+            Descriptor::new("/a", &A).with_flags(DeviceFlags::OPTIONAL),
+            Descriptor::new("/b", &B).with_flags(DeviceFlags::DISABLED),
+            Descriptor::new("/c", &C),
+        ];
+
+        critical_section::with(|cs| *B.state_ref_mut(cs) = true);
+        critical_section::with(|cs| *C.state_ref_mut(cs) = true);
+        let report = init_tables([&TABLE], InitPolicy::Abort);
+
+        verify_that!(
+            (report.is_complete(), report.is_aborted()),
+            (eq(true), eq(false))
+        )?;
+        verify_that!(
+            report.missing().collect::<Vec<_>>(),
+            elements_are![eq(&"/a")]
+        )?;
+        verify_that!(
+            report.to_string(),
+            eq("/a: device not found\n/b: disabled\n/c: ok\n")
+        )?;
+
+        verify_that!(B.lifecycle(), eq(Lifecycle::Uninitialized))?;
+        TABLE[1].enable()?;
+        verify_that!(B.lifecycle(), eq(Lifecycle::Initialized))
+    }
+
+    #[test]
+    fn it_should_keep_results_of_init_time() -> googletest::Result<()> {
+        static A: Device<OptionalDriver> = Device::new();
+        static B: Device<OptionalDriver> = Device::new();
+        static TABLE: [Descriptor; 2] = [Descriptor::new("/a", &A), Descriptor::new("/b", &B)];
+
+        critical_section::with(|cs| *B.state_ref_mut(cs) = true);
+        let report = init_tables([&TABLE], InitPolicy::Continue);
+
+        // The missing hardware shows up afterwards, while the other device is reset.
+        critical_section::with(|cs| *A.state_ref_mut(cs) = true);
+        TABLE[0].init()?;
+        TABLE[1].reset()?;
+        verify_that!(A.init_result(), some(ok(eq(&()))))?;
+
+        verify_that!(report.failed(), eq(1))?;
+        verify_that!(
+            report.missing().collect::<Vec<_>>(),
+            elements_are![eq(&"/a")]
+        )?;
+        verify_that!(report.to_string(), eq("/a: device not found\n/b: ok\n"))
+    }
+}
@@ -0,0 +1,17 @@
+//! The sealed traits, which are only implemented by the [`class`](crate::class) attribute.
+
+/// A tag type, so that an [`Accessor`](crate::Accessor) may only be tagged by a device class.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` is not the tag of a device class",
+    label = "not a device class tag",
+    note = "the tags are generated by the `dedrv::class` attribute (e.g. `tag::Gpio`)"
+)]
+pub trait Tag {}
+
+/// A driver whose accessor without tag is given the class of tag `Tag`, which is never
+/// implemented, so that calling a class method on an accessor without tag is reported as a
+/// missing tag rather than a missing method.
+pub trait NeedsTag<Tag> {
+    /// Get a value that cannot exist, for the bodies of the class methods.
+    fn never() -> core::convert::Infallible;
+}
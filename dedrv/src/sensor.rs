@@ -0,0 +1,244 @@
+//! The sensor class, with its calibrated unit conversion.
+//!
+//! A [`Sensor`] driver (e.g. a temperature sensor, an IMU axis or an ADC frontend) only reads its
+//! raw samples, along with the [`Calibration`] and the [`Unit`] of the device. The framework then
+//! converts the raw samples into a [`Measurement`], which is a fixed-point value in milli-units,
+//! so application code handles every sensor uniformly:
+//!
+//! ```rust,ignore
+//! let celsius = TEMP0.sensor().read_scaled()?;
+//! info!("{}", celsius); // e.g. `23.438 °C`
+//! ```
+//!
+//! A sensor whose samples are signalled by an interrupt (e.g. a data-ready line) publishes its
+//! measurements in a [`Watch`](crate::watch::Watch) of its state, so tasks await the sample-ready
+//! events with `accessor.watch::<Measurement>()`.
+//!
+//! The sensors are iterated through the registry with [`read_all`], which is given a [`Reader`]
+//! for each sensor driver of the application, since the descriptors are type-erased.
+
+use core::fmt::{Display, Formatter};
+
+use crate::path::Path;
+use crate::{Accessor, Descriptor, DeviceTable, Result};
+
+/// The unit of a measurement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+    /// A temperature, in degrees Celsius.
+    Celsius,
+
+    /// A relative humidity, in percent.
+    Percent,
+
+    /// A pressure, in pascals.
+    Pascal,
+
+    /// A voltage, in volts.
+    Volt,
+
+    /// A current, in amperes.
+    Ampere,
+
+    /// An acceleration, in meters per second squared.
+    MeterPerSecondSquared,
+
+    /// An angular rate, in radians per second.
+    RadianPerSecond,
+
+    /// A magnetic field, in teslas.
+    Tesla,
+
+    /// An illuminance, in lux.
+    Lux,
+
+    /// A dimensionless value (e.g. a raw ratio).
+    None,
+}
+
+impl Unit {
+    /// Get the symbol of the unit.
+    pub const fn symbol(&self) -> &'static str {
+        match self {
+            Unit::Celsius => "°C",
+            Unit::Percent => "%",
+            Unit::Pascal => "Pa",
+            Unit::Volt => "V",
+            Unit::Ampere => "A",
+            Unit::MeterPerSecondSquared => "m/s²",
+            Unit::RadianPerSecond => "rad/s",
+            Unit::Tesla => "T",
+            Unit::Lux => "lx",
+            Unit::None => "",
+        }
+    }
+}
+
+/// The conversion of the raw samples of a sensor into milli-units, i.e. `(raw + offset) *
+/// numerator / denominator`.
+///
+/// For example, a sensor whose resolution is 7.8125 m°C per LSB is calibrated with
+/// `Calibration::new(0, 78125, 10000)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Calibration {
+    offset: i32,
+    numerator: i32,
+    denominator: i32,
+}
+
+impl Calibration {
+    /// The calibration of a sensor whose raw samples are already in milli-units.
+    pub const IDENTITY: Calibration = Calibration::new(0, 1, 1);
+
+    /// Create a new calibration.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the denominator is zero, which fails the build in a `const` context.
+    pub const fn new(offset: i32, numerator: i32, denominator: i32) -> Self {
+        assert!(denominator != 0, "zero calibration denominator");
+
+        Calibration {
+            offset,
+            numerator,
+            denominator,
+        }
+    }
+
+    /// Convert a raw sample into milli-units, where the result saturates at the bounds of `i32`.
+    pub const fn apply(&self, raw: i32) -> i32 {
+        let value = (raw as i64 + self.offset as i64) * self.numerator as i64;
+        let value = value / self.denominator as i64;
+
+        if value > i32::MAX as i64 {
+            i32::MAX
+        } else if value < i32::MIN as i64 {
+            i32::MIN
+        } else {
+            value as i32
+        }
+    }
+}
+
+impl Default for Calibration {
+    fn default() -> Self {
+        Calibration::IDENTITY
+    }
+}
+
+/// A measurement, as a fixed-point value in milli-units.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Measurement {
+    /// The value, in thousandths of the unit.
+    pub milli: i32,
+
+    /// The unit of the value.
+    pub unit: Unit,
+}
+
+impl Display for Measurement {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        let sign = if self.milli < 0 { "-" } else { "" };
+        let value = self.milli.unsigned_abs();
+
+        write!(f, "{sign}{}.{:03}", value / 1000, value % 1000)?;
+
+        match self.unit {
+            Unit::None => Ok(()),
+            unit => write!(f, " {}", unit.symbol()),
+        }
+    }
+}
+
+/// A sensor, which reads raw samples.
+#[crate::class]
+pub trait Sensor {
+    /// Read a raw sample of the sensor.
+    fn read_raw(&mut self) -> Result<i32>;
+
+    /// Get the calibration of the sensor (e.g. the factory trim of the device).
+    fn calibration(&self) -> Calibration;
+
+    /// Get the unit of the measurements.
+    fn unit(&self) -> Unit;
+}
+
+impl<D: driver::Sensor> Accessor<'_, D, tag::Sensor> {
+    /// Read a raw sample of the sensor, then convert it into a measurement with the calibration
+    /// of the sensor.
+    pub fn read_scaled(&mut self) -> Result<Measurement> {
+        let raw = Sensor::read_raw(self)?;
+
+        Ok(Measurement {
+            milli: Sensor::calibration(self).apply(raw),
+            unit: Sensor::unit(self),
+        })
+    }
+}
+
+/// The type-erased reader of the sensors of a driver, for iterating the sensors of the registry.
+#[derive(Debug, Clone, Copy)]
+pub struct Reader {
+    read: fn(&'static Descriptor) -> Option<Result<Measurement>>,
+}
+
+impl Reader {
+    /// Create the reader of the sensors whose driver is `D`.
+    pub const fn of<D: driver::Sensor + 'static>() -> Self {
+        Reader { read: read::<D> }
+    }
+}
+
+/// Read a measurement of the device described by `desc`, if its driver is `D`.
+fn read<D: driver::Sensor + 'static>(desc: &'static Descriptor) -> Option<Result<Measurement>> {
+    let mut sensor = desc.accessor::<D, tag::Sensor>()?;
+    Some(sensor.read_scaled())
+}
+
+/// Read a measurement of every sensor of `table` (e.g. [`table`](crate::table)), i.e. every device
+/// whose `Sensor` class has been recorded, then call `report` with its path and the result.
+///
+/// A sensor is read by the first of `readers` that matches its driver, or it is skipped if there
+/// is none. The invalid registries are skipped as well. This returns the number of sensors that
+/// have been read.
+pub fn read_all<const N: usize>(
+    table: &DeviceTable<N>,
+    readers: &[Reader],
+    mut report: impl FnMut(&'static Path, Result<Measurement>),
+) -> usize {
+    table
+        .tables()
+        .flat_map(|x| x.unwrap_or_default())
+        .filter(|desc| desc.supports::<tag::Sensor>())
+        .filter_map(|desc| {
+            let result = readers.iter().find_map(|x| (x.read)(desc))?;
+            report(desc.path(), result);
+            Some(())
+        })
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use googletest::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn it_should_calibrate_raw_samples() -> googletest::Result<()> {
+        const TMP: Calibration = Calibration::new(0, 78125, 10000);
+
+        verify_that!(TMP.apply(3000), eq(23437))?;
+        verify_that!(Calibration::new(-100, 1, 2).apply(0), eq(-50))?;
+        verify_that!(Calibration::new(0, 1000, 1).apply(i32::MAX), eq(i32::MAX))
+    }
+
+    #[test]
+    fn it_should_display_measurements() -> googletest::Result<()> {
+        let measurement = |milli, unit| Measurement { milli, unit }.to_string();
+
+        verify_that!(measurement(23437, Unit::Celsius), eq("23.437 °C"))?;
+        verify_that!(measurement(-1500, Unit::Volt), eq("-1.500 V"))?;
+        verify_that!(measurement(-5, Unit::None), eq("-0.005"))
+    }
+}
@@ -0,0 +1,339 @@
+//! The device shell, for bring-up debugging.
+//!
+//! With the `shell` feature, a [`Shell`] runs over any device of the [`CharDevice`] class (e.g. a
+//! UART), and it turns the registry into an interactive debugging tool:
+//!
+//! - `ls [path]` lists the registered devices (under `path`), with their lifecycle and driver;
+//! - `status <path>` shows the lifecycle, stage and driver of a device, and its commands;
+//! - `call <path> <command> [args...]` invokes a command on a device.
+//!
+//! The class methods are not exposed on their own, since the shell only knows the type-erased
+//! descriptors. Instead, the application registers a [`Command`] for each method that is worth
+//! calling from the shell, which is available on every device that supports its class:
+//!
+//! ```rust,ignore
+//! static COMMANDS: [Command; 1] = [Command::new::<tag::Led>("toggle", "toggle the led", |desc, _, out| {
+//!     let mut led = desc.accessor::<LedDriver, tag::Led>().ok_or(Error::DeviceNotFound)?;
+//!     writeln!(out, "{}", led.toggle())?;
+//!     Ok(())
+//! })];
+//!
+//! let mut shell: Shell = Shell::new(dedrv::table(), &COMMANDS);
+//! loop {
+//!     shell.poll(&mut console)?;
+//! }
+//! ```
+
+use core::fmt::Write;
+use core::str::{FromStr, SplitWhitespace};
+
+use crate::path::Path;
+use crate::{descriptor, Accessor, Class, ClassId, Descriptor, DeviceTable, Error, Result};
+
+/// A character device, which carries a shell session (e.g. a UART).
+#[crate::class]
+pub trait CharDevice {
+    /// Read the pending bytes into `buf` without blocking, then return their number.
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize>;
+
+    /// Write the bytes of `buf`, then return the number of bytes that have been written.
+    fn write(&mut self, buf: &[u8]) -> Result<usize>;
+}
+
+/// The function of a shell command, which is called with the descriptor of the device, the
+/// remaining arguments of the command line and the shell output.
+pub type CommandFn = fn(&'static Descriptor, &mut Args<'_>, &mut dyn Write) -> Result<()>;
+
+/// A command that is exposed to the shell for the devices of a class.
+#[derive(Debug, Clone, Copy)]
+pub struct Command {
+    class: ClassId,
+    class_name: &'static str,
+    name: &'static str,
+    help: &'static str,
+    call: CommandFn,
+}
+
+impl Command {
+    /// Create a new command for the class of tag `Tag`.
+    pub const fn new<Tag: Class>(name: &'static str, help: &'static str, call: CommandFn) -> Self {
+        Command {
+            class: Tag::ID,
+            class_name: Tag::NAME,
+            name,
+            help,
+            call,
+        }
+    }
+
+    /// The name of the command.
+    pub const fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Check whether the command is available on the device described by `desc`.
+    pub fn supports(&self, desc: &Descriptor) -> bool {
+        desc.classes().contains(&self.class)
+    }
+}
+
+/// The arguments of a command line.
+#[derive(Debug, Clone)]
+pub struct Args<'a>(SplitWhitespace<'a>);
+
+impl<'a> Args<'a> {
+    /// Split a command line into its arguments.
+    pub fn new(line: &'a str) -> Self {
+        Args(line.split_whitespace())
+    }
+
+    /// Get the next argument.
+    ///
+    /// This returns [`Error::InvalidArgument`] if there is no argument left.
+    pub fn next_arg(&mut self) -> Result<&'a str> {
+        self.0.next().ok_or(Error::InvalidArgument)
+    }
+
+    /// Parse the next argument (e.g. an integer).
+    ///
+    /// This returns [`Error::InvalidArgument`] if there is no argument left, or if it cannot be
+    /// parsed.
+    pub fn parse<T: FromStr>(&mut self) -> Result<T> {
+        self.next_arg()?.parse().map_err(|_| Error::InvalidArgument)
+    }
+}
+
+impl<'a> Iterator for Args<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        self.0.next()
+    }
+}
+
+/// The prompt that is printed before each command line.
+const PROMPT: &str = "> ";
+
+/// An interactive shell over the devices of a [`DeviceTable`], whose command line holds at most
+/// `N` bytes.
+pub struct Shell<const N: usize = 64> {
+    table: DeviceTable,
+    commands: &'static [Command],
+    line: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> Shell<N> {
+    /// Create a new shell over the devices of `table` (e.g. [`table`](crate::table)), with the
+    /// given commands.
+    pub const fn new(table: DeviceTable, commands: &'static [Command]) -> Self {
+        Shell {
+            table,
+            commands,
+            line: [0; N],
+            len: 0,
+        }
+    }
+
+    /// Print the prompt, which is otherwise printed after each command line.
+    pub fn prompt(&self, out: &mut dyn Write) -> Result<()> {
+        Ok(out.write_str(PROMPT)?)
+    }
+
+    /// Read the pending bytes of a character device, then reply to the completed command lines
+    /// through the same device.
+    pub fn poll<D: driver::CharDevice>(
+        &mut self,
+        console: &mut Accessor<'_, D, tag::CharDevice>,
+    ) -> Result<()> {
+        let mut buf = [0u8; 16];
+        let len = CharDevice::read(console, &mut buf)?;
+
+        for &byte in &buf[..len] {
+            self.feed(byte, &mut Output(&mut *console))?;
+        }
+
+        Ok(())
+    }
+
+    /// Feed a byte of input, which is echoed, then run the command line once it is completed.
+    ///
+    /// Only printable ASCII characters are kept, and the ones that overflow the command line are
+    /// dropped. A failed command is reported on the output.
+    pub fn feed(&mut self, byte: u8, out: &mut dyn Write) -> Result<()> {
+        match byte {
+            b'\r' | b'\n' => {
+                out.write_str("\n")?;
+
+                let len = core::mem::take(&mut self.len);
+                // SAFETY: The command line only holds ASCII characters.
+                let line = unsafe { core::str::from_utf8_unchecked(&self.line[..len]) };
+
+                if let Err(e) = self.execute(line, out) {
+                    writeln!(out, "error: {e}")?;
+                }
+
+                self.prompt(out)
+            }
+            0x08 | 0x7f if self.len > 0 => {
+                self.len -= 1;
+                Ok(out.write_str("\x08 \x08")?)
+            }
+            0x20..=0x7e if self.len < N => {
+                self.line[self.len] = byte;
+                self.len += 1;
+                Ok(out.write_char(byte as char)?)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Run a command line.
+    ///
+    /// This returns [`Error::InvalidCommand`] if the command does not exist, or if it is not
+    /// available on the device.
+    pub fn execute(&self, line: &str, out: &mut dyn Write) -> Result<()> {
+        let mut args = Args::new(line);
+
+        match args.next() {
+            None => Ok(()),
+            Some("help") => self.help(out),
+            Some("ls") => self.ls(args.next(), out),
+            Some("status") => self.status(self.find(args.next_arg()?)?, out),
+            Some("call") => {
+                let desc = self.find(args.next_arg()?)?;
+                let name = args.next_arg()?;
+
+                let command = self
+                    .commands
+                    .iter()
+                    .find(|x| x.name == name && x.supports(desc))
+                    .ok_or(Error::InvalidCommand)?;
+
+                (command.call)(desc, &mut args, out)
+            }
+            Some(_) => Err(Error::InvalidCommand),
+        }
+    }
+
+    /// Print the builtin commands, then the registered ones.
+    fn help(&self, out: &mut dyn Write) -> Result<()> {
+        out.write_str(
+            "help                             print this help\n\
+             ls [path]                        list the devices\n\
+             status <path>                    show the status of a device\n\
+             call <path> <command> [args...]  call a command on a device\n",
+        )?;
+
+        for command in self.commands {
+            writeln!(
+                out,
+                "  {:12}  {:12}  {}",
+                command.name, command.class_name, command.help
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Print the devices under `base`, or every device.
+    fn ls(&self, base: Option<&str>, out: &mut dyn Write) -> Result<()> {
+        let base = base.map(Path::new).transpose()?.unwrap_or(Path::ROOT);
+
+        for table in self.table.tables() {
+            for desc in table?.iter().filter(|x| x.path().starts_with(base)) {
+                writeln!(
+                    out,
+                    "{:24}  {:13}  {}",
+                    desc.path(),
+                    desc.lifecycle(),
+                    desc.driver()
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Print the status of a device, with its available commands.
+    fn status(&self, desc: &Descriptor, out: &mut dyn Write) -> Result<()> {
+        writeln!(out, "path:      {}", desc.path())?;
+        writeln!(out, "driver:    {}", desc.driver())?;
+        writeln!(out, "lifecycle: {}", desc.lifecycle())?;
+        writeln!(out, "stage:     {}", desc.stage())?;
+        out.write_str("commands: ")?;
+
+        for command in self.commands.iter().filter(|x| x.supports(desc)) {
+            write!(out, " {}", command.name)?;
+        }
+
+        Ok(out.write_str("\n")?)
+    }
+
+    /// Look up the descriptor of the device at `path`.
+    fn find(&self, path: &str) -> Result<&'static Descriptor> {
+        let path = Path::new(path)?;
+
+        self.table
+            .tables()
+            .find_map(|x| x.and_then(|table| descriptor::find(table, path)).ok())
+            .ok_or(Error::DeviceNotFound)
+    }
+}
+
+/// The output of a shell over a character device.
+struct Output<'a, 'd, D: driver::CharDevice + 'static>(&'a mut Accessor<'d, D, tag::CharDevice>);
+
+impl<D: driver::CharDevice> Write for Output<'_, '_, D> {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        let mut buf = s.as_bytes();
+
+        while !buf.is_empty() {
+            match CharDevice::write(self.0, buf) {
+                Ok(0) | Err(_) => return Err(core::fmt::Error),
+                Ok(n) => buf = &buf[n..],
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use googletest::prelude::*;
+
+    use super::*;
+    use crate::Registry;
+
+    #[test]
+    fn it_should_edit_command_line() -> googletest::Result<()> {
+        let mut shell: Shell<2> = Shell::new(DeviceTable::new([Registry::empty(); 2]), &[]);
+        let mut out = String::new();
+
+        for byte in *b"lx\x7fsss\x01\r" {
+            shell.feed(byte, &mut out)?;
+        }
+
+        verify_that!(out, eq("lx\x08 \x08s\n> "))
+    }
+
+    #[test]
+    fn it_should_reject_unknown_command() -> googletest::Result<()> {
+        let shell: Shell = Shell::new(DeviceTable::new([Registry::empty(); 2]), &[]);
+        let mut out = String::new();
+
+        verify_that!(
+            shell.execute("reboot now", &mut out),
+            err(eq(&Error::InvalidCommand))
+        )?;
+        verify_that!(
+            shell.execute("status", &mut out),
+            err(eq(&Error::InvalidArgument))
+        )?;
+        verify_that!(
+            shell.execute("status /uart0", &mut out),
+            err(eq(&Error::DeviceNotFound))
+        )
+    }
+}
@@ -0,0 +1,144 @@
+use core::fmt::Debug;
+use core::mem::MaybeUninit;
+use core::ops::{Deref, DerefMut};
+
+/// A driver state that is constructed at runtime.
+///
+/// A driver internal state is zeroed when the device is created (see [`Device::new`]), which is
+/// not valid for states that own HAL peripherals (e.g. a `&'static mut` from a `StaticCell`, or a
+/// pin singleton with a niche). Such a driver declares its state as `Slot<T>` instead, which is
+/// empty when zeroed, then the application moves the peripherals into the device with
+/// [`Device::init_with`]:
+///
+/// ```rust,ignore
+/// UART0.init_with(|slot| slot.write(UartState::new(p.USART1, p.PA9, p.PA10)));
+/// ```
+///
+/// The driver implementation reaches the state through [`Deref`], which panics if the slot is
+/// still empty, or through [`Slot::get`] and [`Slot::get_mut`].
+///
+/// [`Device::new`]: crate::Device::new
+/// [`Device::init_with`]: crate::Device::init_with
+pub struct Slot<T> {
+    init: bool,
+    value: MaybeUninit<T>,
+}
+
+impl<T> Slot<T> {
+    /// Create a new empty slot.
+    pub const fn new() -> Self {
+        Slot {
+            init: false,
+            value: MaybeUninit::uninit(),
+        }
+    }
+
+    /// Check whether the slot holds a value.
+    #[inline(always)]
+    pub const fn is_init(&self) -> bool {
+        self.init
+    }
+
+    /// Move a value into the slot, then return a mutable reference to it.
+    ///
+    /// The previous value, if any, is dropped.
+    pub fn write(&mut self, value: T) -> &mut T {
+        self.take();
+        self.init = true;
+        self.value.write(value)
+    }
+
+    /// Get a reference to the value, or `None` if the slot is empty.
+    pub fn get(&self) -> Option<&T> {
+        // SAFETY: The value is initialized as long as the flag is set.
+        self.init.then(|| unsafe { self.value.assume_init_ref() })
+    }
+
+    /// Get a mutable reference to the value, or `None` if the slot is empty.
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        // SAFETY: The value is initialized as long as the flag is set.
+        self.init.then(|| unsafe { self.value.assume_init_mut() })
+    }
+
+    /// Move the value out of the slot (e.g. in [`Driver::cleanup`](crate::Driver::cleanup)),
+    /// which leaves it empty.
+    pub fn take(&mut self) -> Option<T> {
+        // SAFETY: The value is initialized as long as the flag is set, and the flag is cleared
+        // right away, so it is only read once.
+        core::mem::take(&mut self.init).then(|| unsafe { self.value.assume_init_read() })
+    }
+}
+
+impl<T> Default for Slot<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Deref for Slot<T> {
+    type Target = T;
+
+    /// # Panics
+    ///
+    /// Panics if the slot is empty.
+    fn deref(&self) -> &T {
+        self.get().expect("uninitialized device state")
+    }
+}
+
+impl<T> DerefMut for Slot<T> {
+    /// # Panics
+    ///
+    /// Panics if the slot is empty.
+    fn deref_mut(&mut self) -> &mut T {
+        self.get_mut().expect("uninitialized device state")
+    }
+}
+
+impl<T: Debug> Debug for Slot<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self.get() {
+            Some(x) => f.debug_tuple("Slot").field(x).finish(),
+            None => f.write_str("Slot(<empty>)"),
+        }
+    }
+}
+
+impl<T> Drop for Slot<T> {
+    fn drop(&mut self) {
+        self.take();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use googletest::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn it_should_be_empty_when_zeroed() -> googletest::Result<()> {
+        let mut slot: Slot<&'static mut u32> = unsafe { core::mem::zeroed() };
+
+        verify_that!(slot.is_init(), eq(false))?;
+        verify_that!(slot.take(), none())
+    }
+
+    #[test]
+    fn it_should_drop_value_once() -> googletest::Result<()> {
+        let marker = std::rc::Rc::new(());
+
+        let mut slot: Slot<std::rc::Rc<()>> = Slot::new();
+        slot.write(marker.clone());
+        slot.write(marker.clone());
+        verify_that!(std::rc::Rc::strong_count(&marker), eq(2))?;
+
+        let value = slot.take();
+        verify_that!(slot.get(), none())?;
+        drop(value);
+
+        slot.write(marker.clone());
+        drop(slot);
+        verify_that!(std::rc::Rc::strong_count(&marker), eq(1))
+    }
+}
@@ -0,0 +1,241 @@
+use crate::{Descriptor, Error, Result};
+
+/// The magic number that starts every state snapshot (i.e. `DDRS` in ASCII).
+const SNAPSHOT_MAGIC: [u8; 4] = *b"DDRS";
+
+/// The size of the snapshot header, which is the magic number and the number of devices.
+const HEADER_SIZE: usize = 6;
+
+/// The size of the prefix of the state of each device, which is its path identifier and its
+/// length.
+const PREFIX_SIZE: usize = 6;
+
+/// Save the state of the given devices into `buf`, then return the size of the snapshot.
+///
+/// The snapshot starts with a header (i.e. magic number and number of devices as a little-endian
+/// `u16`), followed by the state of each device prefixed by the identifier of its path (see
+/// [`PathId`](crate::PathId)) as a little-endian `u32` and its length as a little-endian `u16`.
+pub(crate) fn save<'a>(
+    devices: impl IntoIterator<Item = &'a Descriptor>,
+    buf: &mut [u8],
+) -> Result<usize> {
+    if buf.len() < HEADER_SIZE {
+        return Err(Error::BufferTooSmall);
+    }
+
+    let mut count = 0u16;
+    let mut offset = HEADER_SIZE;
+
+    for desc in devices {
+        let data = buf
+            .get_mut(offset + PREFIX_SIZE..)
+            .ok_or(Error::BufferTooSmall)?;
+
+        // The length prefix limits the size of the state of each device.
+        let max = data.len().min(u16::MAX as usize);
+        let len = desc.save(&mut data[..max])?;
+
+        let id = desc.path().id().get();
+        buf[offset..offset + 4].copy_from_slice(&id.to_le_bytes());
+        buf[offset + 4..offset + PREFIX_SIZE].copy_from_slice(&(len as u16).to_le_bytes());
+        offset += PREFIX_SIZE + len;
+        count += 1;
+    }
+
+    buf[..4].copy_from_slice(&SNAPSHOT_MAGIC);
+    buf[4..HEADER_SIZE].copy_from_slice(&count.to_le_bytes());
+
+    Ok(offset)
+}
+
+/// Restore the state of the given devices out of a snapshot that has been taken by [`save`].
+///
+/// The whole snapshot is checked before any device is restored, including the path identifier of
+/// each device, so a snapshot of another firmware (e.g. whose devices have been reordered) is
+/// rejected.
+pub(crate) fn restore<'a, I>(devices: I, data: &[u8]) -> Result<()>
+where
+    I: IntoIterator<Item = &'a Descriptor>,
+    I::IntoIter: Clone,
+{
+    let devices = devices.into_iter();
+
+    if data.get(..4) != Some(&SNAPSHOT_MAGIC[..]) {
+        return Err(Error::InvalidSnapshot);
+    }
+
+    let count = data
+        .get(4..HEADER_SIZE)
+        .map(|x| u16::from_le_bytes([x[0], x[1]]))
+        .ok_or(Error::InvalidSnapshot)?;
+    if count as usize != devices.clone().count() {
+        return Err(Error::InvalidSnapshot);
+    }
+
+    // Split the snapshot into the states of each device, along with their path identifier.
+    let states = || {
+        let mut offset = HEADER_SIZE;
+        (0..count).map(move |_| {
+            let (id, len) = data
+                .get(offset..offset + PREFIX_SIZE)
+                .map(|x| {
+                    let id = u32::from_le_bytes([x[0], x[1], x[2], x[3]]);
+                    (id, u16::from_le_bytes([x[4], x[5]]) as usize)
+                })
+                .ok_or(Error::InvalidSnapshot)?;
+
+            let state = data
+                .get(offset + PREFIX_SIZE..offset + PREFIX_SIZE + len)
+                .ok_or(Error::InvalidSnapshot)?;
+
+            offset += PREFIX_SIZE + len;
+            Ok::<_, Error>((id, state))
+        })
+    };
+
+    for (desc, state) in devices.clone().zip(states()) {
+        if state?.0 != desc.path().id().get() {
+            return Err(Error::InvalidSnapshot);
+        }
+    }
+
+    for (desc, state) in devices.zip(states()) {
+        desc.restore(state?.1)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use googletest::prelude::*;
+
+    use super::*;
+    use crate::{Device, Driver, StateLock};
+
+    /// A driver that saves its whole state, which is a single `u32`.
+    struct RetainedDriver;
+
+    impl Driver for RetainedDriver {
+        type StateType = u32;
+
+        fn init(_: &StateLock<Self>) {}
+        fn cleanup(_: &StateLock<Self>) {}
+
+        fn save(state: &StateLock<Self>, buf: &mut [u8]) -> crate::Result<usize> {
+            let buf = buf.get_mut(..4).ok_or(Error::BufferTooSmall)?;
+            critical_section::with(|cs| buf.copy_from_slice(&state.borrow_ref(cs).to_le_bytes()));
+            Ok(4)
+        }
+
+        fn restore(state: &StateLock<Self>, data: &[u8]) -> crate::Result<()> {
+            let data: [u8; 4] = data.try_into().map_err(|_| Error::InvalidSnapshot)?;
+            critical_section::with(|cs| *state.borrow_ref_mut(cs) = u32::from_le_bytes(data));
+            Ok(())
+        }
+    }
+
+    /// A driver that does not save anything.
+    struct VolatileDriver;
+
+    impl Driver for VolatileDriver {
+        type StateType = ();
+
+        fn init(_: &StateLock<Self>) {}
+        fn cleanup(_: &StateLock<Self>) {}
+    }
+
+    fn set(device: &Device<RetainedDriver>, value: u32) {
+        critical_section::with(|cs| *device.state_ref_mut(cs) = value);
+    }
+
+    fn get(device: &Device<RetainedDriver>) -> u32 {
+        critical_section::with(|cs| *device.state_ref(cs))
+    }
+
+    #[test]
+    fn it_should_save_and_restore_states() -> googletest::Result<()> {
+        static A: Device<RetainedDriver> = Device::new();
+        static B: Device<VolatileDriver> = Device::new();
+        static C: Device<RetainedDriver> = Device::new();
+
+        let table = [
+            Descriptor::new("/a", &A),
+            Descriptor::new("/b", &B),
+            Descriptor::new("/c", &C),
+        ];
+
+        set(&A, 0x1234);
+        set(&C, 0xabcd);
+
+        let mut retained = [0u8; 32];
+        let len = save(&table, &mut retained)?;
+        verify_that!(len, eq(HEADER_SIZE + 3 * PREFIX_SIZE + 4 + 4))?;
+
+        // The peripheral registers are lost in deep-sleep mode.
+        set(&A, 0);
+        set(&C, 0);
+
+        restore(&table, &retained[..len])?;
+        verify_that!((get(&A), get(&C)), eq((0x1234, 0xabcd)))
+    }
+
+    #[test]
+    fn it_should_reject_too_small_buffer() -> googletest::Result<()> {
+        static A: Device<RetainedDriver> = Device::new();
+
+        let table = [Descriptor::new("/a", &A)];
+
+        let mut retained = [0u8; HEADER_SIZE + 4];
+        verify_that!(save(&table, &mut retained), err(eq(&Error::BufferTooSmall)))
+    }
+
+    #[test]
+    fn it_should_reject_invalid_snapshot() -> googletest::Result<()> {
+        static A: Device<RetainedDriver> = Device::new();
+
+        let table = [Descriptor::new("/a", &A)];
+        set(&A, 42);
+
+        // Uninitialized retained RAM after a cold boot.
+        verify_that!(restore(&table, &[0; 16]), err(eq(&Error::InvalidSnapshot)))?;
+
+        // Truncated snapshot, which must not restore anything.
+        let mut retained = [0u8; 16];
+        let len = save(&table, &mut retained)?;
+        set(&A, 0);
+
+        verify_that!(
+            restore(&table, &retained[..len - 1]),
+            err(eq(&Error::InvalidSnapshot))
+        )?;
+        verify_that!(get(&A), eq(0))?;
+
+        // Truncated header.
+        verify_that!(
+            restore(&table, &retained[..5]),
+            err(eq(&Error::InvalidSnapshot))
+        )
+    }
+
+    #[test]
+    fn it_should_reject_snapshot_of_other_devices() -> googletest::Result<()> {
+        static A: Device<RetainedDriver> = Device::new();
+        static B: Device<RetainedDriver> = Device::new();
+
+        let table = [Descriptor::new("/a", &A), Descriptor::new("/b", &B)];
+        let other = [Descriptor::new("/a", &A), Descriptor::new("/c", &B)];
+        set(&A, 1);
+        set(&B, 2);
+
+        let mut retained = [0u8; 32];
+        let len = save(&table, &mut retained)?;
+        set(&A, 0);
+
+        verify_that!(
+            restore(&other, &retained[..len]),
+            err(eq(&Error::InvalidSnapshot))
+        )?;
+        verify_that!(get(&A), eq(0))
+    }
+}
@@ -0,0 +1,356 @@
+//! The staged initialization of the devices.
+//!
+//! Each device belongs to an init stage, which is given by the `stage` argument of the
+//! [`device`](crate::device) attribute (i.e. stage 0 by default). The stages are initialized in
+//! increasing order, so a device may rely on every device of the previous stages, while the
+//! devices of the same stage must be independent from each other.
+//!
+//! On multicore targets, the devices of a stage may be initialized in parallel: each core calls
+//! [`init_on_core`] with the same [`Barrier`], then each core initializes its share of every
+//! stage and waits for the other cores at the stage boundary. This cuts the boot time of
+//! firmwares with many slow-to-init peripherals.
+//!
+//! The dependencies of each device (see [`Descriptor::with_dependencies`]) are checked before any
+//! device is initialized, sequentially or in parallel: a dependency on a device of the same (or a
+//! later) stage is rejected with [`Error::UnorderedDependency`].
+//!
+//! The deferred devices (see [`DeviceFlags::DEFER`]) are initialized after every other device of
+//! their registry, in stage order as well, while the disabled ones are skipped. The weak default
+//! devices that are overridden by another device at the same path are never initialized.
+
+use core::cell::Cell;
+
+use critical_section::Mutex;
+
+use crate::{
+    descriptor, early_print, report, Descriptor, DeviceFlags, Error, InitPolicy, InitReport,
+    Registry, Result,
+};
+
+/// A reusable barrier, which synchronizes a fixed number of cores at each stage boundary.
+///
+/// The barrier relies on the `critical-section` implementation, which must be multicore-safe
+/// (e.g. a hardware spinlock) on multicore targets.
+pub struct Barrier {
+    parties: usize,
+
+    // The number of arrived cores and the generation of the barrier.
+    state: Mutex<Cell<(usize, usize)>>,
+}
+
+impl Barrier {
+    /// Create a new barrier for the given number of cores.
+    pub const fn new(parties: usize) -> Self {
+        Barrier {
+            parties,
+            state: Mutex::new(Cell::new((0, 0))),
+        }
+    }
+
+    /// The number of cores that are synchronized by this barrier.
+    pub const fn parties(&self) -> usize {
+        self.parties
+    }
+
+    /// Wait until every core has reached the barrier.
+    pub fn wait(&self) {
+        let generation = critical_section::with(|cs| {
+            let state = self.state.borrow(cs);
+            let (arrived, generation) = state.get();
+
+            if arrived + 1 >= self.parties {
+                state.set((0, generation.wrapping_add(1)));
+            } else {
+                state.set((arrived + 1, generation));
+            }
+
+            generation
+        });
+
+        while critical_section::with(|cs| self.state.borrow(cs).get().1) == generation {
+            core::hint::spin_loop();
+        }
+    }
+}
+
+/// Iterate over the descriptors of a table in stage order, then in path order within a stage,
+/// where the deferred devices come last.
+///
+/// The weak default devices that are overridden are left out.
+pub(crate) fn ordered(table: &[Descriptor]) -> impl Iterator<Item = &Descriptor> + Clone {
+    stages(table).flat_map(move |stage| {
+        table
+            .iter()
+            .filter(move |x| key(x) == stage && !descriptor::is_overridden(table, x))
+    })
+}
+
+/// Iterate over the stages of a table, in increasing order, where each deferred stage comes after
+/// every other stage.
+fn stages(table: &[Descriptor]) -> impl Iterator<Item = (bool, u8)> + Clone + '_ {
+    [false, true]
+        .into_iter()
+        .flat_map(|deferred| (0..=u8::MAX).map(move |stage| (deferred, stage)))
+        .filter(move |&stage| table.iter().any(|x| key(x) == stage))
+}
+
+/// Get the stage of a device, along with whether it is deferred.
+fn key(desc: &Descriptor) -> (bool, u8) {
+    (desc.flags().contains(DeviceFlags::DEFER), desc.stage())
+}
+
+/// Check whether a device is initialized at boot.
+pub(crate) fn is_enabled(desc: &Descriptor) -> bool {
+    !desc.flags().contains(DeviceFlags::DISABLED)
+}
+
+/// Check that every dependency of the devices of the given validated tables, in init order, is
+/// initialized before the device, i.e. that it belongs to an earlier table or to an earlier stage
+/// of the same table.
+///
+/// A dependency that is not registered in any table is initialized when it is acquired.
+pub(crate) fn check_dependencies(tables: &[&[Descriptor]]) -> Result<()> {
+    for (i, table) in tables.iter().enumerate() {
+        for desc in table.iter() {
+            for dep in desc.dependencies() {
+                let later = tables[i + 1..]
+                    .iter()
+                    .any(|x| x.iter().any(|x| x.id() == dep.id()));
+                let unordered = table
+                    .iter()
+                    .any(|x| x.id() == dep.id() && key(x) >= key(desc));
+
+                if later || unordered {
+                    return Err(Error::UnorderedDependency);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Initialize the devices of a table on the given core, as part of a staged parallel init,
+/// according to `policy`.
+///
+/// This returns the number of devices of the stages that have been attempted, in init order.
+fn init_table_on_core(
+    table: &'static [Descriptor],
+    core: usize,
+    barrier: &Barrier,
+    policy: InitPolicy,
+) -> usize {
+    let mut attempted = 0;
+
+    for stage in stages(table) {
+        let devices = || {
+            table
+                .iter()
+                .filter(move |x| key(x) == stage && !descriptor::is_overridden(table, x))
+        };
+
+        let share = devices()
+            .filter(|x| is_enabled(x))
+            .skip(core)
+            .step_by(barrier.parties().max(1));
+
+        for desc in share {
+            early_print!("dedrv: init {} (core {})\n", desc.path(), core);
+            if let Err(e) = desc.init() {
+                early_print!("dedrv: init {} failed: {}\n", desc.path(), e);
+            }
+        }
+
+        barrier.wait();
+        attempted += devices().count();
+
+        // Every core sees the same results past the barrier, so they all stop at the same stage.
+        if policy == InitPolicy::Abort && devices().any(report::is_failed) {
+            break;
+        }
+    }
+
+    attempted
+}
+
+/// Initialize all devices like [`init`](crate::init), in parallel on `barrier.parties()` cores.
+///
+/// Each core must call this function with its own index (i.e. from 0) and the same barrier. The
+/// early devices are initialized by core 0, before any other device. Then, the devices of each
+/// stage are distributed across the cores, which all wait for each other before the next stage.
+/// This returns once every device has been initialized, with the same report on every core.
+pub fn init_on_core(core: usize, barrier: &Barrier) -> Result<InitReport> {
+    init_on_core_with_policy(core, barrier, InitPolicy::Continue)
+}
+
+/// Initialize all devices in parallel like [`init_on_core`], according to `policy`.
+///
+/// With [`InitPolicy::Abort`], the cores stop at the end of the stage where a device failed to
+/// initialize, since the other devices of the stage are initialized concurrently.
+pub fn init_on_core_with_policy(
+    core: usize,
+    barrier: &Barrier,
+    policy: InitPolicy,
+) -> Result<InitReport> {
+    let (early, devices) = (Registry::early().table()?, Registry::devices().table()?);
+    report::check_tables(&[early, devices])?;
+
+    if core == 0 {
+        report::init_tables([early], policy);
+    }
+
+    barrier.wait();
+
+    // The early init stops right after its first failure, if it is aborted.
+    let mut attempted = ordered(early).count();
+    if policy == InitPolicy::Abort {
+        if let Some(i) = ordered(early).position(report::is_failed) {
+            return Ok(InitReport::from_results([early, devices], i + 1));
+        }
+    }
+
+    attempted += init_table_on_core(devices, core, barrier, policy);
+    Ok(InitReport::from_results([early, devices], attempted))
+}
+
+#[cfg(test)]
+mod tests {
+    use googletest::prelude::*;
+
+    use super::*;
+    use crate::testing::NoopDriver;
+    use crate::{Device, DeviceRef, Driver, Lifecycle, StateLock};
+
+    // The parallel inits share the barrier, so they run one at a time.
+    static SERIAL: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+    /// A driver whose hardware is always missing.
+    struct MissingDriver;
+
+    impl Driver for MissingDriver {
+        type StateType = ();
+
+        fn init(_: &StateLock<Self>) {}
+        fn cleanup(_: &StateLock<Self>) {}
+
+        fn probe(_: &StateLock<Self>) -> crate::Result<()> {
+            Err(Error::DeviceNotFound)
+        }
+    }
+
+    static LINKED: Device<NoopDriver> = Device::new();
+
+    /// Initialize a table in parallel on two cores.
+    fn init_on_two_cores(table: &'static [Descriptor], policy: InitPolicy) -> usize {
+        static BARRIER: Barrier = Barrier::new(2);
+        let _serial = SERIAL.lock().unwrap_or_else(|x| x.into_inner());
+
+        std::thread::scope(|s| {
+            s.spawn(|| init_table_on_core(table, 1, &BARRIER, policy));
+            init_table_on_core(table, 0, &BARRIER, policy)
+        })
+    }
+
+    static A: Device<NoopDriver> = Device::new();
+    static B: Device<NoopDriver> = Device::new();
+    static C: Device<NoopDriver> = Device::new();
+    static D: Device<NoopDriver> = Device::new();
+
+    static TABLE: [Descriptor; 4] = [
+        Descriptor::new("/a", &A).with_stage(1),
+        Descriptor::new("/b", &B),
+        Descriptor::new("/c", &C).with_stage(1),
+        Descriptor::new("/d", &D),
+    ];
+
+    #[test]
+    fn it_should_order_descriptors_by_stage() -> googletest::Result<()> {
+        let paths: Vec<_> = ordered(&TABLE).map(|x| x.path()).collect();
+        verify_that!(
+            paths,
+            elements_are![eq(&"/b"), eq(&"/d"), eq(&"/a"), eq(&"/c")]
+        )
+    }
+
+    #[test]
+    fn it_should_order_deferred_descriptors_last() -> googletest::Result<()> {
+        static TABLE: [Descriptor; 3] = [
+            Descriptor::new("/a", &A).with_flags(DeviceFlags::DEFER),
+            Descriptor::new("/b", &B).with_stage(2),
+            Descriptor::new("/c", &C),
+        ];
+
+        let paths: Vec<_> = ordered(&TABLE).map(|x| x.path()).collect();
+        verify_that!(paths, elements_are![eq(&"/c"), eq(&"/b"), eq(&"/a")])
+    }
+
+    #[test]
+    fn it_should_init_stages_on_two_cores() -> googletest::Result<()> {
+        verify_that!(init_on_two_cores(&TABLE, InitPolicy::Continue), eq(4))?;
+
+        for device in [&A, &B, &C, &D] {
+            verify_that!(device.lifecycle(), eq(Lifecycle::Initialized))?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_abort_at_end_of_failed_stage() -> googletest::Result<()> {
+        static A: Device<MissingDriver> = Device::new();
+        static B: Device<NoopDriver> = Device::new();
+        static C: Device<NoopDriver> = Device::new();
+        static TABLE: [Descriptor; 3] = [
+            Descriptor::new("/a", &A),
+            Descriptor::new("/b", &B),
+            Descriptor::new("/c", &C).with_stage(1),
+        ];
+
+        let report =
+            InitReport::from_results([&TABLE], init_on_two_cores(&TABLE, InitPolicy::Abort));
+        verify_that!((report.failed(), report.is_aborted()), (eq(1), eq(true)))?;
+        verify_that!(B.lifecycle(), eq(Lifecycle::Initialized))?;
+        verify_that!(
+            report.to_string(),
+            eq("/a: device not found\n/b: ok\n/c: skipped\n")
+        )
+    }
+
+    #[test]
+    fn it_should_reject_same_stage_dependency() -> googletest::Result<()> {
+        static LINK: Device<NoopDriver> = Device::new();
+        static SAME_TABLE: [Descriptor; 2] = [
+            Descriptor::new("/link", &LINK).with_dependencies(&[DeviceRef::new(&LINKED)]),
+            Descriptor::new("/linked", &LINKED),
+        ];
+        static LATER_TABLE: [Descriptor; 2] = [
+            Descriptor::new("/link", &LINK)
+                .with_dependencies(&[DeviceRef::new(&LINKED)])
+                .with_stage(1),
+            Descriptor::new("/linked", &LINKED),
+        ];
+
+        verify_that!(
+            check_dependencies(&[&SAME_TABLE]),
+            err(eq(&Error::UnorderedDependency))
+        )?;
+        verify_that!(check_dependencies(&[&LATER_TABLE]), ok(eq(&())))
+    }
+
+    #[test]
+    fn it_should_reject_dependency_of_later_table() -> googletest::Result<()> {
+        static LINK: Device<NoopDriver> = Device::new();
+        static LINK_TABLE: [Descriptor; 1] =
+            [Descriptor::new("/link", &LINK).with_dependencies(&[DeviceRef::new(&LINKED)])];
+        static LINKED_TABLE: [Descriptor; 1] = [Descriptor::new("/linked", &LINKED)];
+
+        verify_that!(
+            check_dependencies(&[&LINK_TABLE, &LINKED_TABLE]),
+            err(eq(&Error::UnorderedDependency))
+        )?;
+        verify_that!(
+            check_dependencies(&[&LINKED_TABLE, &LINK_TABLE]),
+            ok(eq(&()))
+        )
+    }
+}
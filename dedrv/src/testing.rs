@@ -0,0 +1,63 @@
+//! The fixtures that are shared by the unit tests.
+
+#[cfg(any(feature = "poll", feature = "method-duration"))]
+use std::sync::Mutex;
+
+#[cfg(any(feature = "poll", feature = "method-duration"))]
+use crate::clock::{self, ClockFn};
+use crate::{Driver, Error, Result, StateLock};
+
+/// A driver that does nothing.
+pub(crate) struct NoopDriver;
+
+impl Driver for NoopDriver {
+    type StateType = ();
+
+    fn init(_: &StateLock<Self>) {}
+    fn cleanup(_: &StateLock<Self>) {}
+}
+
+/// A driver whose hardware is missing while its state is `false`.
+pub(crate) struct OptionalDriver;
+
+impl Driver for OptionalDriver {
+    type StateType = bool;
+
+    fn init(_: &StateLock<Self>) {}
+    fn cleanup(_: &StateLock<Self>) {}
+
+    fn probe(state: &StateLock<Self>) -> Result<()> {
+        match critical_section::with(|cs| *state.borrow_ref(cs)) {
+            true => Ok(()),
+            false => Err(Error::DeviceNotFound),
+        }
+    }
+}
+
+/// A driver whose state is a counter (see [`bump`]).
+pub(crate) struct CounterDriver;
+
+impl Driver for CounterDriver {
+    type StateType = u32;
+
+    fn init(_: &StateLock<Self>) {}
+    fn cleanup(_: &StateLock<Self>) {}
+}
+
+/// Increment the counter of a device.
+#[cfg(any(feature = "poll", feature = "work"))]
+pub(crate) fn bump(state: &StateLock<CounterDriver>) {
+    critical_section::with(|cs| *state.borrow_ref_mut(cs) += 1);
+}
+
+/// Call `f` while `clock` is the clock of the framework, which is shared by every test.
+#[cfg(any(feature = "poll", feature = "method-duration"))]
+pub(crate) fn with_clock<R>(clock: ClockFn, f: impl FnOnce() -> R) -> R {
+    static LOCK: Mutex<()> = Mutex::new(());
+    let _lock = LOCK.lock().unwrap_or_else(|x| x.into_inner());
+
+    clock::set_clock(clock);
+    let result = f();
+    clock::clear_clock();
+    result
+}
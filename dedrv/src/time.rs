@@ -0,0 +1,279 @@
+//! The real-time clock class, and the calendar time facade.
+//!
+//! The [`Rtc`] class is implemented by the drivers of calendar clocks (e.g. an on-chip RTC or an
+//! external one over I2C). The application installs the device that is the source of truth with
+//! [`set_clock`], then every component (e.g. logging, filesystems or network stacks) asks what
+//! time it is with [`now`], without knowing which device answers:
+//!
+//! ```rust,ignore
+//! dedrv::time::set_clock(&RTC0);
+//!
+//! let stamp = dedrv::time::now()?.to_unix();
+//! ```
+//!
+//! The time is expressed in UTC, and the calendar starts with the Unix epoch (i.e. 1970).
+
+use core::cell::Cell;
+use core::fmt::Display;
+
+use critical_section::Mutex;
+
+use crate::{Accessor, Device, Error, Result};
+
+/// The number of seconds per day.
+const SECS_PER_DAY: u64 = 86_400;
+
+/// A calendar date and time of day, in UTC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DateTime {
+    year: u16,
+    month: u8,
+    day: u8,
+    hour: u8,
+    minute: u8,
+    second: u8,
+}
+
+impl DateTime {
+    /// The Unix epoch (i.e. 1970-01-01T00:00:00).
+    pub const EPOCH: DateTime = DateTime {
+        year: 1970,
+        month: 1,
+        day: 1,
+        hour: 0,
+        minute: 0,
+        second: 0,
+    };
+
+    /// Create a new date and time.
+    ///
+    /// This returns [`Error::InvalidDateTime`] if a field is out of range, or if the date is
+    /// before the Unix epoch.
+    pub const fn new(
+        year: u16,
+        month: u8,
+        day: u8,
+        hour: u8,
+        minute: u8,
+        second: u8,
+    ) -> Result<Self> {
+        if year < 1970
+            || month < 1
+            || month > 12
+            || day < 1
+            || day > days_in_month(year, month)
+            || hour > 23
+            || minute > 59
+            || second > 59
+        {
+            return Err(Error::InvalidDateTime);
+        }
+
+        Ok(DateTime {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+        })
+    }
+
+    /// Create a date and time out of the number of seconds since the Unix epoch.
+    ///
+    /// This returns [`Error::InvalidDateTime`] if the year does not fit in 16 bits.
+    pub const fn from_unix(secs: u64) -> Result<Self> {
+        let days = secs / SECS_PER_DAY;
+        let rem = secs % SECS_PER_DAY;
+
+        // The civil date out of the number of days, where the years start in March so the leap
+        // day is the last one (see http://howardhinnant.github.io/date_algorithms.html).
+        let z = days + 719_468;
+        let era = z / 146_097;
+        let doe = z - era * 146_097;
+        let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+        if year > u16::MAX as u64 {
+            return Err(Error::InvalidDateTime);
+        }
+
+        Ok(DateTime {
+            year: year as u16,
+            month: month as u8,
+            day: day as u8,
+            hour: (rem / 3600) as u8,
+            minute: (rem / 60 % 60) as u8,
+            second: (rem % 60) as u8,
+        })
+    }
+
+    /// Get the number of seconds since the Unix epoch.
+    pub const fn to_unix(&self) -> u64 {
+        let month = self.month as u64;
+        let year = self.year as u64 - if month <= 2 { 1 } else { 0 };
+
+        let era = year / 400;
+        let yoe = year - era * 400;
+        let doy = (153 * ((month + 9) % 12) + 2) / 5 + self.day as u64 - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+        let days = era * 146_097 + doe - 719_468;
+
+        days * SECS_PER_DAY + self.hour as u64 * 3600 + self.minute as u64 * 60 + self.second as u64
+    }
+
+    /// The year (e.g. 2024).
+    pub const fn year(&self) -> u16 {
+        self.year
+    }
+
+    /// The month, from 1 (January) to 12 (December).
+    pub const fn month(&self) -> u8 {
+        self.month
+    }
+
+    /// The day of the month, from 1 to 31.
+    pub const fn day(&self) -> u8 {
+        self.day
+    }
+
+    /// The hour, from 0 to 23.
+    pub const fn hour(&self) -> u8 {
+        self.hour
+    }
+
+    /// The minute, from 0 to 59.
+    pub const fn minute(&self) -> u8 {
+        self.minute
+    }
+
+    /// The second, from 0 to 59.
+    pub const fn second(&self) -> u8 {
+        self.second
+    }
+
+    /// The day of the week, from 1 (Monday) to 7 (Sunday), as in ISO 8601.
+    pub const fn weekday(&self) -> u8 {
+        // The Unix epoch is a Thursday.
+        ((self.to_unix() / SECS_PER_DAY + 3) % 7 + 1) as u8
+    }
+}
+
+impl Default for DateTime {
+    fn default() -> Self {
+        Self::EPOCH
+    }
+}
+
+impl Display for DateTime {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+}
+
+/// Get the number of days of a month.
+const fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// A real-time clock, which keeps the calendar time and may raise an alarm.
+#[crate::class]
+pub trait Rtc {
+    /// Get the current date and time.
+    fn now(&self) -> Result<DateTime>;
+
+    /// Set the current date and time.
+    fn set(&mut self, time: DateTime) -> Result<()>;
+
+    /// Raise the alarm at the given date and time, which replaces any pending alarm.
+    fn set_alarm(&mut self, at: DateTime) -> Result<()>;
+
+    /// Cancel the pending alarm, if any.
+    fn cancel_alarm(&mut self);
+}
+
+/// A source of the calendar time, as installed by [`set_clock`].
+pub trait Clock: Sync {
+    /// Get the current date and time.
+    fn now(&self) -> Result<DateTime>;
+}
+
+impl<D: driver::Rtc> Clock for Device<D>
+where
+    Device<D>: Sync,
+{
+    fn now(&self) -> Result<DateTime> {
+        Rtc::now(&self.try_accessor::<tag::Rtc>()?)
+    }
+}
+
+/// The installed clock.
+static CLOCK: Mutex<Cell<Option<&'static dyn Clock>>> = Mutex::new(Cell::new(None));
+
+/// Install the clock that is queried by [`now`] (e.g. a device of the [`Rtc`] class).
+pub fn set_clock(clock: &'static dyn Clock) {
+    critical_section::with(|cs| CLOCK.borrow(cs).set(Some(clock)));
+}
+
+/// Get the current date and time from the installed clock.
+///
+/// This returns [`Error::NotReady`] if no clock is installed, or if the installed device has not
+/// been initialized yet.
+pub fn now() -> Result<DateTime> {
+    critical_section::with(|cs| CLOCK.borrow(cs).get())
+        .ok_or(Error::NotReady)?
+        .now()
+}
+
+#[cfg(test)]
+mod tests {
+    use googletest::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn it_should_convert_unix_time() -> googletest::Result<()> {
+        let leap = DateTime::new(2024, 2, 29, 13, 37, 42)?;
+
+        verify_that!(leap.to_unix(), eq(1_709_213_862))?;
+        verify_that!(DateTime::from_unix(1_709_213_862), ok(eq(&leap)))?;
+        verify_that!(DateTime::from_unix(0), ok(eq(&DateTime::EPOCH)))?;
+        verify_that!(leap.weekday(), eq(4))
+    }
+
+    #[test]
+    fn it_should_reject_invalid_date_time() -> googletest::Result<()> {
+        verify_that!(
+            DateTime::new(2023, 2, 29, 0, 0, 0),
+            err(eq(&Error::InvalidDateTime))
+        )?;
+        verify_that!(
+            DateTime::new(1969, 12, 31, 0, 0, 0),
+            err(eq(&Error::InvalidDateTime))
+        )?;
+        verify_that!(
+            DateTime::new(2000, 2, 29, 24, 0, 0),
+            err(eq(&Error::InvalidDateTime))
+        )?;
+        verify_that!(DateTime::new(2000, 2, 29, 0, 0, 0).is_ok(), eq(true))
+    }
+
+    #[test]
+    fn it_should_display_iso_8601() -> googletest::Result<()> {
+        let time = DateTime::new(2024, 3, 1, 9, 5, 0)?;
+        verify_that!(time.to_string(), eq("2024-03-01T09:05:00"))
+    }
+}
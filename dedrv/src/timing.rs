@@ -0,0 +1,113 @@
+use crate::{report, Descriptor, InitPolicy, InitReport, Path, Result};
+
+/// The timing of a device initialization, as measured by [`init_timed`](crate::init_timed).
+///
+/// The timestamps are given in the unit of the user-supplied timestamp source (e.g. cycles or
+/// microseconds).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InitTiming {
+    /// The path of the initialized device.
+    pub path: &'static Path,
+
+    /// The timestamp right before the driver init function is called.
+    pub start: u64,
+
+    /// The duration of the driver init function.
+    pub duration: u64,
+
+    /// The result of the init (see [`Descriptor::init_result`]).
+    pub result: Result<()>,
+}
+
+/// Initialize every device of the given checked tables like
+/// [`init_tables`](report::init_tables), while measuring each init duration.
+pub(crate) fn init_tables<const N: usize>(
+    tables: [&'static [Descriptor]; N],
+    policy: InitPolicy,
+    mut now: impl FnMut() -> u64,
+    mut trace: impl FnMut(InitTiming),
+) -> InitReport<N> {
+    report::init_tables_with(tables, policy, |desc| {
+        let start = now();
+        let result = desc.init();
+        let end = now();
+
+        trace(InitTiming {
+            path: desc.path(),
+            start,
+            duration: end.wrapping_sub(start),
+            result: result.clone(),
+        });
+        result
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::Cell;
+
+    use googletest::prelude::*;
+
+    use super::*;
+    use crate::testing::{NoopDriver, OptionalDriver};
+    use crate::{Device, Error, Lifecycle};
+
+    #[test]
+    fn it_should_trace_each_init_duration() -> googletest::Result<()> {
+        static A: Device<NoopDriver> = Device::new();
+        static B: Device<NoopDriver> = Device::new();
+        static TABLE: [Descriptor; 2] = [Descriptor::new("/a", &A), Descriptor::new("/b", &B)];
+
+        // A fake clock, which ticks by the number of calls so far.
+        let ticks = Cell::new(0u64);
+        let now = || {
+            ticks.set(ticks.get() + 1);
+            ticks.get() * ticks.get()
+        };
+
+        let mut timings = Vec::new();
+        let report = init_tables([&TABLE], InitPolicy::Continue, now, |t| timings.push(t));
+
+        verify_that!(report.is_complete(), eq(true))?;
+        verify_that!(
+            timings,
+            elements_are![
+                eq(&InitTiming {
+                    path: Path::from_static("/a"),
+                    start: 1,
+                    duration: 3,
+                    result: Ok(()),
+                }),
+                eq(&InitTiming {
+                    path: Path::from_static("/b"),
+                    start: 9,
+                    duration: 7,
+                    result: Ok(()),
+                }),
+            ]
+        )
+    }
+
+    #[test]
+    fn it_should_abort_timed_init_at_first_failure() -> googletest::Result<()> {
+        static A: Device<OptionalDriver> = Device::new();
+        static B: Device<OptionalDriver> = Device::new();
+        static TABLE: [Descriptor; 2] = [Descriptor::new("/a", &A), Descriptor::new("/b", &B)];
+
+        critical_section::with(|cs| *B.state_ref_mut(cs) = true);
+        let mut timings = Vec::new();
+        let report = init_tables([&TABLE], InitPolicy::Abort, || 0, |t| timings.push(t));
+
+        verify_that!((report.failed(), report.is_aborted()), (eq(1), eq(true)))?;
+        verify_that!(B.lifecycle(), eq(Lifecycle::Uninitialized))?;
+        verify_that!(
+            timings,
+            elements_are![eq(&InitTiming {
+                path: Path::from_static("/a"),
+                start: 0,
+                duration: 0,
+                result: Err(Error::DeviceNotFound),
+            })]
+        )
+    }
+}
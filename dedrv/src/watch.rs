@@ -0,0 +1,228 @@
+//! The watch channels, which broadcast the latest value of a device.
+//!
+//! A driver publishes the latest value of something (e.g. the link state of a PHY, or the last
+//! sample of an ADC) in a [`Watch`] of its state, then any number of tasks poll or await the
+//! changes through their accessor:
+//!
+//! ```rust,ignore
+//! let phy = PHY0.phy();
+//! let mut link = phy.watch::<Link>();
+//!
+//! loop {
+//!     match link.changed().await {
+//!         Link::Up => info!("link up"),
+//!         Link::Down => info!("link down"),
+//!     }
+//! }
+//! ```
+//!
+//! Only the latest value is kept, so a slow receiver skips the intermediate values rather than
+//! lagging behind.
+
+use core::future::poll_fn;
+use core::marker::PhantomData;
+use core::task::{Poll, Waker};
+
+use crate::{Accessor, Device, Driver, Slot};
+
+/// The latest value of a device, with the wakers of the tasks that await its changes.
+///
+/// A watch supports `N` waiting tasks at once. When more tasks are waiting, the registered ones
+/// are woken up, so they register again on their next poll. A zeroed watch is a valid empty
+/// watch.
+#[derive(Debug)]
+pub struct Watch<T, const N: usize = 4> {
+    value: Slot<T>,
+    version: u32,
+    wakers: [Slot<Waker>; N],
+}
+
+impl<T, const N: usize> Watch<T, N> {
+    /// Create a new watch without any value.
+    pub const fn new() -> Self {
+        Watch {
+            value: Slot::new(),
+            version: 0,
+            wakers: [const { Slot::new() }; N],
+        }
+    }
+
+    /// Publish a new value, then wake up the waiting tasks.
+    pub fn send(&mut self, value: T) {
+        self.value.write(value);
+
+        // The version zero is the one of an empty watch.
+        self.version = self.version.wrapping_add(1).max(1);
+
+        for waker in self.wakers.iter_mut().filter_map(Slot::take) {
+            waker.wake();
+        }
+    }
+
+    /// Get the latest value, if any.
+    pub fn get(&self) -> Option<&T> {
+        self.value.get()
+    }
+
+    /// Get the version of the latest value, which changes with every new value (or zero if there
+    /// is none).
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Register the waker of a task, which is woken up by the next value.
+    pub fn register(&mut self, waker: &Waker) {
+        if self
+            .wakers
+            .iter()
+            .any(|x| x.get().is_some_and(|x| x.will_wake(waker)))
+        {
+            return;
+        }
+
+        if let Some(slot) = self.wakers.iter_mut().find(|x| !x.is_init()) {
+            slot.write(waker.clone());
+            return;
+        }
+
+        // There is no free slot, so the waiting tasks are woken up to register again.
+        for waker in self.wakers.iter_mut().filter_map(Slot::take) {
+            waker.wake();
+        }
+        self.wakers[0].write(waker.clone());
+    }
+}
+
+impl<T, const N: usize> Default for Watch<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A driver whose state holds a watch of values of type `T`, for the default number of waiting
+/// tasks.
+pub trait Watched<T: Clone>: Driver {
+    /// Get the watch out of the driver state.
+    fn watch(state: &mut Self::StateType) -> &mut Watch<T>;
+}
+
+/// A receiver of the values of a watch, which tracks the last value it has seen.
+pub struct Receiver<'a, D: Watched<T> + 'static, T: Clone> {
+    device: &'a Device<D>,
+    seen: u32,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<'a, D: Watched<T>, T: Clone> Receiver<'a, D, T> {
+    /// Create a new receiver, which has not seen any value yet.
+    pub fn new<Tag>(accessor: &'a Accessor<'_, D, Tag>) -> Self {
+        Receiver {
+            device: accessor.inner(),
+            seen: 0,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Get the latest value, if any, whether it has been seen or not.
+    pub fn get(&self) -> Option<T> {
+        critical_section::with(|cs| {
+            D::watch(&mut self.device.state.borrow_ref_mut(cs))
+                .get()
+                .cloned()
+        })
+    }
+
+    /// Get the latest value if it has not been seen yet, without waiting.
+    pub fn try_changed(&mut self) -> Option<T> {
+        critical_section::with(|cs| {
+            let mut state = self.device.state.borrow_ref_mut(cs);
+            self.take(D::watch(&mut state))
+        })
+    }
+
+    /// Wait for a value that has not been seen yet, then return it.
+    pub async fn changed(&mut self) -> T {
+        poll_fn(|cx| {
+            critical_section::with(|cs| {
+                let mut state = self.device.state.borrow_ref_mut(cs);
+                let watch = D::watch(&mut state);
+
+                match self.take(watch) {
+                    Some(value) => Poll::Ready(value),
+                    None => {
+                        watch.register(cx.waker());
+                        Poll::Pending
+                    }
+                }
+            })
+        })
+        .await
+    }
+
+    /// Mark the latest value of the watch as seen, if it has not been seen yet.
+    fn take(&mut self, watch: &Watch<T>) -> Option<T> {
+        if watch.version == self.seen {
+            return None;
+        }
+
+        self.seen = watch.version;
+        watch.get().cloned()
+    }
+}
+
+impl<'d, D: Driver, Tag> Accessor<'d, D, Tag> {
+    /// Get a new receiver of the values of type `T` that are published by the driver of this
+    /// device (see [`Watched`]).
+    pub fn watch<T: Clone>(&self) -> Receiver<'_, D, T>
+    where
+        D: Watched<T>,
+    {
+        Receiver::new(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::task::Wake;
+
+    use googletest::prelude::*;
+
+    use super::*;
+
+    struct CountingWaker(AtomicUsize);
+
+    impl Wake for CountingWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn it_should_be_empty_when_zeroed() -> googletest::Result<()> {
+        let watch: Watch<u32> = unsafe { core::mem::zeroed() };
+
+        verify_that!(watch.get(), none())?;
+        verify_that!(watch.version(), eq(0))
+    }
+
+    #[test]
+    fn it_should_wake_waiting_tasks() -> googletest::Result<()> {
+        let counter = Arc::new(CountingWaker(AtomicUsize::new(0)));
+        let waker = Waker::from(counter.clone());
+
+        let mut watch: Watch<u32, 1> = Watch::new();
+        watch.register(&waker);
+        watch.register(&waker);
+        watch.send(1);
+        watch.send(2);
+        verify_that!(counter.0.load(Ordering::Relaxed), eq(1))?;
+
+        // A task that does not fit is registered in place of the waiting ones.
+        watch.register(&waker);
+        watch.register(Waker::noop());
+        verify_that!(counter.0.load(Ordering::Relaxed), eq(2))?;
+        verify_that!((watch.get(), watch.version()), (some(eq(&2)), eq(2)))
+    }
+}
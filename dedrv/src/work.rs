@@ -0,0 +1,133 @@
+//! The deferred work (i.e. bottom-half) queue.
+//!
+//! An interrupt handler should only do the minimum (e.g. acknowledging the interrupt and moving
+//! data into a queue), then defer the heavy part to thread context. A driver declares the deferred
+//! part as a static [`DeviceWork`] that is bound to a device, then the interrupt handler schedules
+//! it with [`schedule`]. The application drains the pending work items from thread context (e.g.
+//! its main loop) with [`run_pending`].
+//!
+//! The queue holds at most [`CAPACITY`] items, and an item that is already pending is not queued
+//! twice, so scheduling never allocates.
+
+use core::cell::{Cell, RefCell};
+
+use critical_section::Mutex;
+
+use crate::queue::Spsc;
+use crate::{Device, Driver, Error, Result, StateLock};
+
+/// The maximum number of pending work items.
+pub const CAPACITY: usize = 16;
+
+/// The queue of pending work items.
+static PENDING: Mutex<RefCell<Spsc<&'static dyn Work, CAPACITY>>> =
+    Mutex::new(RefCell::new(Spsc::new()));
+
+/// A work item that may be deferred to thread context.
+pub trait Work: Sync {
+    /// Run the work item.
+    fn run(&self);
+
+    /// Mark the work item as pending, then return whether it was already pending.
+    fn set_pending(&self, pending: bool) -> bool;
+}
+
+/// A work item that is bound to a device, which runs a function on the driver state.
+pub struct DeviceWork<D: Driver + 'static> {
+    device: &'static Device<D>,
+    func: fn(&StateLock<D>),
+    pending: Mutex<Cell<bool>>,
+}
+
+impl<D: Driver> DeviceWork<D> {
+    /// Create a new work item that runs `func` on the driver state of `device`.
+    pub const fn new(device: &'static Device<D>, func: fn(&StateLock<D>)) -> Self {
+        DeviceWork {
+            device,
+            func,
+            pending: Mutex::new(Cell::new(false)),
+        }
+    }
+
+    /// Check whether the work item is pending.
+    pub fn is_pending(&self) -> bool {
+        critical_section::with(|cs| self.pending.borrow(cs).get())
+    }
+}
+
+impl<D: Driver> Work for DeviceWork<D>
+where
+    Device<D>: Sync,
+{
+    fn run(&self) {
+        (self.func)(&self.device.state)
+    }
+
+    fn set_pending(&self, pending: bool) -> bool {
+        critical_section::with(|cs| self.pending.borrow(cs).replace(pending))
+    }
+}
+
+/// Schedule a work item, which is run by the next call to [`run_pending`].
+///
+/// This may be called from an interrupt handler. Scheduling an item that is already pending does
+/// nothing, and this returns [`Error::WorkQueueFull`] if [`CAPACITY`] items are already pending.
+pub fn schedule(work: &'static dyn Work) -> Result<()> {
+    critical_section::with(|cs| {
+        if work.set_pending(true) {
+            return Ok(());
+        }
+
+        PENDING.borrow_ref_mut(cs).push(work).map_err(|work| {
+            work.set_pending(false);
+            Error::WorkQueueFull
+        })
+    })
+}
+
+/// Run every pending work item from thread context, then return the number of items that have
+/// been run.
+///
+/// Each item is run outside of the critical section, so it may be scheduled again meanwhile (e.g.
+/// by an interrupt handler), in which case it is run again before this function returns.
+pub fn run_pending() -> usize {
+    let mut count = 0;
+
+    while let Some(work) = critical_section::with(|cs| {
+        let work = PENDING.borrow_ref_mut(cs).pop();
+        work.inspect(|x| {
+            x.set_pending(false);
+        })
+    }) {
+        work.run();
+        count += 1;
+    }
+
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use googletest::prelude::*;
+
+    use super::*;
+    use crate::testing::{bump, CounterDriver};
+
+    static DEVICE: Device<CounterDriver> = Device::new();
+
+    #[test]
+    fn it_should_run_pending_work_once() -> googletest::Result<()> {
+        static WORK: DeviceWork<CounterDriver> = DeviceWork::new(&DEVICE, bump);
+
+        verify_that!(schedule(&WORK), ok(eq(&())))?;
+        verify_that!(schedule(&WORK), ok(eq(&())))?;
+        verify_that!(WORK.is_pending(), eq(true))?;
+
+        verify_that!(run_pending(), eq(1))?;
+        verify_that!(WORK.is_pending(), eq(false))?;
+        verify_that!(
+            critical_section::with(|cs| *DEVICE.state.borrow_ref(cs)),
+            eq(1)
+        )
+    }
+}
@@ -0,0 +1,214 @@
+use dedrv::{Accessor, Device, Driver};
+
+/// Defines a peripheral class that cannot be shared between concurrent users.
+#[dedrv::class]
+pub trait Sequencer {
+    fn start(&mut self);
+}
+
+#[cfg(test)]
+mod tests {
+    use googletest::prelude::*;
+
+    use dedrv::{Error, StateLock};
+
+    use super::*;
+
+    struct SequencerDriver;
+
+    impl Driver for SequencerDriver {
+        type StateType = u32;
+
+        fn init(_state: &StateLock<Self>) {}
+        fn cleanup(_state: &StateLock<Self>) {}
+    }
+
+    impl driver::Sequencer for SequencerDriver {
+        fn start(state: &StateLock<Self>) {
+            critical_section::with(|cs| *state.borrow_ref_mut(cs) += 1);
+        }
+    }
+
+    /// A sequencer driver whose devices limit the number of open accessors.
+    struct OneShotDriver;
+
+    impl Driver for OneShotDriver {
+        type StateType = u32;
+
+        const LIMITED_ACCESSORS: bool = true;
+
+        fn init(_state: &StateLock<Self>) {}
+        fn cleanup(_state: &StateLock<Self>) {}
+
+        fn open(state: &StateLock<Self>) {
+            critical_section::with(|cs| *state.borrow_ref_mut(cs) += 1);
+        }
+    }
+
+    impl driver::Sequencer for OneShotDriver {
+        fn start(_state: &StateLock<Self>) {}
+    }
+
+    /// A driver that counts the calls to its open and close hooks.
+    struct HookedDriver;
+
+    impl Driver for HookedDriver {
+        type StateType = (u32, u32);
+
+        fn init(_state: &StateLock<Self>) {}
+        fn cleanup(_state: &StateLock<Self>) {}
+
+        fn open(state: &StateLock<Self>) {
+            critical_section::with(|cs| state.borrow_ref_mut(cs).0 += 1);
+        }
+
+        fn close(state: &StateLock<Self>) {
+            critical_section::with(|cs| state.borrow_ref_mut(cs).1 += 1);
+        }
+    }
+
+    impl driver::Sequencer for HookedDriver {
+        fn start(_state: &StateLock<Self>) {}
+    }
+
+    #[test]
+    fn it_should_count_open_accessors() -> googletest::Result<()> {
+        static DEVICE: Device<SequencerDriver> = Device::new();
+
+        let first = DEVICE.accessor::<tag::Sequencer>();
+        let second = DEVICE.accessor::<tag::Sequencer>();
+        verify_that!(DEVICE.accessors(), eq(2))?;
+
+        drop(first);
+        verify_that!(DEVICE.accessors(), eq(1))?;
+
+        drop(second);
+        verify_that!(DEVICE.accessors(), eq(0))
+    }
+
+    #[test]
+    fn it_should_reject_accessor_over_limit() -> googletest::Result<()> {
+        static DEVICE: Device<OneShotDriver> = Device::new().with_max_accessors(1);
+        DEVICE.init();
+
+        let mut seq = DEVICE.try_accessor::<tag::Sequencer>()?;
+        seq.start();
+
+        verify_that!(
+            DEVICE.try_accessor::<tag::Sequencer>().map(|_| ()),
+            err(eq(&Error::Busy))
+        )?;
+
+        drop(seq);
+        verify_that!(
+            DEVICE.try_accessor::<tag::Sequencer>().map(|_| ()),
+            ok(eq(&()))
+        )
+    }
+
+    #[test]
+    fn it_should_not_build_limit_of_unlimited_driver() {
+        let t = trybuild::TestCases::new();
+        t.compile_fail("tests/units/accessor_unlimited.rs");
+    }
+
+    #[test]
+    #[should_panic(expected = "an exclusive accessor of the device is open")]
+    fn it_should_panic_on_accessor_while_exclusive() {
+        static DEVICE: Device<SequencerDriver> = Device::new();
+        DEVICE.init();
+
+        let _setup = DEVICE.exclusive_accessor::<tag::Sequencer>();
+        let _ = DEVICE.accessor::<tag::Sequencer>();
+    }
+
+    #[test]
+    fn it_should_open_and_close_on_first_and_last_accessor() -> googletest::Result<()> {
+        static DEVICE: Device<HookedDriver> = Device::new();
+        let hooks = || critical_section::with(|cs| *DEVICE.state_ref(cs));
+
+        let first = DEVICE.accessor::<tag::Sequencer>();
+        verify_that!(hooks(), eq((1, 0)))?;
+
+        let second = DEVICE.accessor::<tag::Sequencer>();
+        drop(first);
+        verify_that!(hooks(), eq((1, 0)))?;
+
+        drop(second);
+        verify_that!(hooks(), eq((1, 1)))?;
+
+        let _again = DEVICE.accessor::<tag::Sequencer>();
+        verify_that!(hooks(), eq((2, 1)))
+    }
+
+    #[test]
+    fn it_should_not_open_busy_device() -> googletest::Result<()> {
+        static DEVICE: Device<OneShotDriver> = Device::new().with_max_accessors(0);
+        DEVICE.init();
+
+        verify_that!(
+            DEVICE.try_accessor::<tag::Sequencer>().map(|_| ()),
+            err(eq(&Error::Busy))
+        )?;
+        verify_that!(critical_section::with(|cs| *DEVICE.state_ref(cs)), eq(0))
+    }
+
+    #[test]
+    fn it_should_not_access_device_before_init() -> googletest::Result<()> {
+        static DEVICE: Device<SequencerDriver> = Device::new();
+
+        verify_that!(
+            DEVICE.try_accessor::<tag::Sequencer>().map(|_| ()),
+            err(eq(&Error::NotReady))
+        )?;
+
+        DEVICE.init();
+        verify_that!(
+            DEVICE.try_accessor::<tag::Sequencer>().map(|_| ()),
+            ok(eq(&()))
+        )?;
+
+        DEVICE.cleanup();
+        verify_that!(
+            DEVICE.try_accessor::<tag::Sequencer>().map(|_| ()),
+            err(eq(&Error::NotReady))
+        )
+    }
+
+    #[test]
+    fn it_should_downgrade_and_upgrade_exclusive_accessor() -> googletest::Result<()> {
+        static DEVICE: Device<SequencerDriver> = Device::new();
+        DEVICE.init();
+
+        let mut setup = DEVICE.exclusive_accessor::<tag::Sequencer>()?;
+        setup.start();
+        verify_that!(DEVICE.is_exclusive(), eq(true))?;
+        verify_that!(
+            DEVICE.try_accessor::<tag::Sequencer>().map(|_| ()),
+            err(eq(&Error::Busy))
+        )?;
+
+        let shared = setup.downgrade();
+        let monitor = DEVICE.try_accessor::<tag::Sequencer>()?;
+        verify_that!(
+            (DEVICE.is_exclusive(), DEVICE.accessors()),
+            (eq(false), eq(2))
+        )?;
+
+        // The upgrade fails while another accessor is open.
+        let shared = match shared.upgrade() {
+            Ok(_) => return fail!("upgraded a shared device"),
+            Err(x) => x,
+        };
+        drop(monitor);
+
+        let exclusive = shared.upgrade().map_err(|_| Error::Busy)?;
+        verify_that!(DEVICE.is_exclusive(), eq(true))?;
+        drop(exclusive);
+
+        verify_that!(
+            (DEVICE.is_exclusive(), DEVICE.accessors()),
+            (eq(false), eq(0))
+        )
+    }
+}
@@ -7,6 +7,12 @@ pub trait Gpio {
     fn set_value(&mut self, value: u32);
 }
 
+/// Defines another peripheral class in the same module, thanks to renamed items.
+#[dedrv::class(driver_mod = "led_driver", tag = "LedTag")]
+pub trait Led {
+    fn toggle(&mut self) -> bool;
+}
+
 #[cfg(test)]
 mod tests {
     use googletest::prelude::*;
@@ -40,18 +46,89 @@ mod tests {
         }
     }
 
+    // User implementaiton.
+    impl led_driver::Led for GpioDriver {
+        fn toggle(state: &StateLock<Self>) -> bool {
+            critical_section::with(|cs| {
+                let mut value = state.borrow_ref_mut(cs);
+                *value ^= 1;
+                *value != 0
+            })
+        }
+    }
+
     #[test]
     fn it_should_init_device() {
         static DEVICE: Device<GpioDriver> = Device::new();
         DEVICE.init();
     }
 
+    #[test]
+    fn it_should_init_empty_registry_on_host() {
+        assert_that!(dedrv::init().map(|x| x.is_complete()), ok(eq(&true)));
+    }
+
+    #[test]
+    fn it_should_not_find_device_on_host() {
+        assert_that!(
+            dedrv::find("/gpio0").map(|d| d.path()),
+            err(eq(&dedrv::Error::DeviceNotFound))
+        );
+    }
+
+    #[test]
+    fn it_should_not_find_invalid_path_on_host() {
+        assert_that!(
+            dedrv::find("gpio0").map(|d| d.path()),
+            err(eq(&dedrv::Error::InvalidPath))
+        );
+    }
+
+    #[test]
+    fn it_should_init_timed_empty_registry_on_host() {
+        let mut count = 0;
+        let report = dedrv::init_timed(dedrv::InitPolicy::Abort, || 0, |_| count += 1);
+        assert_that!(report.map(|x| x.is_complete()), ok(eq(&true)));
+        assert_that!(count, eq(0));
+    }
+
     #[test]
     fn it_should_not_compile_accessor_after_drop() {
         let t = trybuild::TestCases::new();
         t.compile_fail("tests/units/accessor_after_drop.rs");
     }
 
+    #[test]
+    fn it_should_not_compile_accessor_for_unsupported_class() {
+        let t = trybuild::TestCases::new();
+        t.compile_fail("tests/units/accessor_unsupported_class.rs");
+    }
+
+    #[test]
+    fn it_should_not_compile_accessor_with_foreign_tag() {
+        let t = trybuild::TestCases::new();
+        t.compile_fail("tests/units/accessor_foreign_tag.rs");
+    }
+
+    #[test]
+    fn it_should_not_compile_class_method_without_tag() {
+        let t = trybuild::TestCases::new();
+        t.compile_fail("tests/units/accessor_without_tag.rs");
+    }
+
+    #[test]
+    #[cfg(feature = "dma")]
+    fn it_should_report_invalid_class_methods() {
+        let t = trybuild::TestCases::new();
+        t.compile_fail("tests/units/class_invalid_method.rs");
+    }
+
+    #[test]
+    fn it_should_report_invalid_device_arguments() {
+        let t = trybuild::TestCases::new();
+        t.compile_fail("tests/units/device_invalid_args.rs");
+    }
+
     #[test]
     fn it_should_use_class_accessor_to_modify_state() {
         static DEVICE: Device<GpioDriver> = Device::new();
@@ -65,16 +142,62 @@ mod tests {
     }
 
     #[test]
-    fn it_should_populate_dedrv_linker_section() {
+    fn it_should_use_renamed_class_items() {
         static DEVICE: Device<GpioDriver> = Device::new();
+        DEVICE.init();
 
-        fn __dedrv_device_init(ptr: *const ()) {
-            let device: &'static _ = unsafe { &*(ptr as *const Device<GpioDriver>) };
-            device.init();
+        let mut led = DEVICE.accessor::<LedTag>();
+        assert_that!(led.toggle(), eq(true));
+        assert_that!(led.toggle(), eq(false));
+    }
+
+    #[test]
+    fn it_should_use_device_extension_accessor() {
+        static DEVICE: Device<GpioDriver> = Device::new();
+        DEVICE.init();
+
+        DEVICE.gpio().set_value(7);
+        assert_that!(DEVICE.gpio().get_value(), eq(7));
+        assert_that!(DEVICE.led().toggle(), eq(true));
+    }
+
+    #[test]
+    fn it_should_use_class_through_accessor_reference() {
+        static DEVICE: Device<GpioDriver> = Device::new();
+        DEVICE.init();
+
+        fn bump(mut gpio: impl Gpio) {
+            let value = gpio.get_value();
+            gpio.set_value(value + 1);
         }
 
+        let mut gpio = DEVICE.gpio();
+        bump(&mut gpio);
+        bump(&mut gpio);
+        assert_that!(gpio.get_value(), eq(2));
+    }
+
+    #[test]
+    fn it_should_populate_dedrv_linker_section() {
+        static DEVICE: Device<GpioDriver> = Device::new();
+
         #[allow(unused)]
         #[link_section = ".dedrv.device.gpio0"]
-        static DESCRIPTOR: Descriptor = Descriptor::new("/gpio0", &DEVICE, __dedrv_device_init);
+        static DESCRIPTOR: Descriptor = Descriptor::new("/gpio0", &DEVICE);
+    }
+
+    #[test]
+    fn it_should_discover_classes_from_descriptor() {
+        static DEVICE: Device<GpioDriver> = Device::new();
+        static CLASSES: [dedrv::ClassId; 1] = [dedrv::ClassId::of::<_, tag::Gpio>(&DEVICE)];
+
+        let desc = Descriptor::new("/gpio0", &DEVICE).with_classes(&CLASSES);
+
+        assert_that!(desc.supports::<tag::Gpio>(), eq(true));
+        assert_that!(desc.supports::<LedTag>(), eq(false));
+
+        let mut gpio = desc.accessor::<GpioDriver, tag::Gpio>().unwrap();
+        gpio.set_value(7);
+        assert_that!(gpio.get_value(), eq(7));
     }
 }
@@ -0,0 +1,78 @@
+#![cfg(feature = "board")]
+
+use dedrv::{Device, Driver, StateLock};
+
+/// A UART driver, which is the console of the board.
+pub struct UartDriver;
+
+impl Driver for UartDriver {
+    type StateType = ();
+
+    fn init(_state: &StateLock<Self>) {}
+    fn cleanup(_state: &StateLock<Self>) {}
+}
+
+/// A GPIO port driver, which holds the level of its lines.
+pub struct GpioDriver;
+
+impl Driver for GpioDriver {
+    type StateType = u32;
+
+    fn init(_state: &StateLock<Self>) {}
+    fn cleanup(_state: &StateLock<Self>) {}
+}
+
+dedrv::board! {
+    /// A board with a console and a GPIO port, whose first lines drive two LEDs.
+    pub mod nucleo {
+        use super::*;
+
+        #[dedrv::device(path = "/soc/usart3", early)]
+        pub static USART3: Device<UartDriver> = Device::new();
+
+        #[dedrv::device(path = "/soc/gpiob")]
+        pub static GPIOB: Device<GpioDriver> = Device::new();
+
+        pins! {
+            LED_GREEN = GPIOB[0],
+            LED_BLUE = GPIOB[7],
+        }
+
+        aliases! {
+            CONSOLE = USART3,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use googletest::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn it_should_list_the_inventory() -> googletest::Result<()> {
+        let board = nucleo::BOARD;
+
+        verify_that!(board.name(), eq("nucleo"))?;
+        verify_that!(
+            board.devices().collect::<Vec<_>>(),
+            elements_are![eq(&"/soc/usart3"), eq(&"/soc/gpiob")]
+        )?;
+        verify_that!(
+            board.pins().collect::<Vec<_>>(),
+            elements_are![
+                eq(&("LED_GREEN", "/soc/gpiob", 0)),
+                eq(&("LED_BLUE", "/soc/gpiob", 7))
+            ]
+        )?;
+        verify_that!(board.resolve("CONSOLE"), some(eq("/soc/usart3")))
+    }
+
+    #[test]
+    fn it_should_declare_pins_and_aliases() -> googletest::Result<()> {
+        verify_that!(nucleo::LED_BLUE.line(), eq(7))?;
+        verify_that!(nucleo::LED_BLUE.device().id(), eq(nucleo::GPIOB.id()))?;
+        verify_that!(nucleo::CONSOLE.id(), eq(nucleo::USART3.id()))
+    }
+}
@@ -0,0 +1,75 @@
+use dedrv::{Accessor, Device, Driver};
+
+/// Defines a peripheral class with buffer-based I/O.
+#[dedrv::class]
+pub trait Serial {
+    fn read(&self, buf: &mut [u8]) -> usize;
+    fn write(&mut self, buf: &[u8]) -> usize;
+}
+
+#[cfg(test)]
+mod tests {
+    use googletest::prelude::*;
+
+    use dedrv::StateLock;
+
+    use super::*;
+
+    /// A loopback serial driver, which reads back what has been written.
+    struct LoopbackDriver;
+
+    impl Driver for LoopbackDriver {
+        type StateType = ([u8; 8], usize);
+
+        fn init(_state: &StateLock<Self>) {}
+        fn cleanup(_state: &StateLock<Self>) {}
+    }
+
+    impl driver::Serial for LoopbackDriver {
+        fn read(state: &StateLock<Self>, buf: &mut [u8]) -> usize {
+            critical_section::with(|cs| {
+                let (data, len) = &mut *state.borrow_ref_mut(cs);
+                let n = buf.len().min(*len);
+                buf[..n].copy_from_slice(&data[..n]);
+                *len = 0;
+                n
+            })
+        }
+
+        fn write(state: &StateLock<Self>, buf: &[u8]) -> usize {
+            critical_section::with(|cs| {
+                let (data, len) = &mut *state.borrow_ref_mut(cs);
+                let n = buf.len().min(data.len());
+                data[..n].copy_from_slice(&buf[..n]);
+                *len = n;
+                n
+            })
+        }
+    }
+
+    #[test]
+    fn it_should_write_then_read_buffer() -> googletest::Result<()> {
+        static DEVICE: Device<LoopbackDriver> = Device::new();
+        DEVICE.init();
+
+        let mut serial = DEVICE.accessor::<tag::Serial>();
+        verify_that!(serial.write(b"hello"), eq(5))?;
+
+        let mut buf = [0u8; 16];
+        let n = serial.read(&mut buf);
+        verify_that!(&buf[..n], eq(b"hello"))
+    }
+
+    #[test]
+    fn it_should_truncate_oversized_buffer() -> googletest::Result<()> {
+        static DEVICE: Device<LoopbackDriver> = Device::new();
+        DEVICE.init();
+
+        let mut serial = DEVICE.accessor::<tag::Serial>();
+        verify_that!(serial.write(b"0123456789"), eq(8))?;
+
+        let mut buf = [0u8; 4];
+        verify_that!(serial.read(&mut buf), eq(4))?;
+        verify_that!(&buf, eq(b"0123"))
+    }
+}
@@ -0,0 +1,138 @@
+#![cfg(feature = "can")]
+
+use dedrv::can::{driver, Filter, FilterBanks, Filtered, Frame, Id};
+use dedrv::queue::{Introspect, Spsc, Streamed};
+use dedrv::{Driver, Error, Result, StateLock};
+
+/// A fake CAN controller with two filter banks, whose bus is looped back.
+#[derive(Default)]
+pub struct CanState {
+    filters: [Option<Filter>; 2],
+    banks: FilterBanks,
+    rx: Spsc<Frame, 4>,
+}
+
+pub struct CanDriver;
+
+impl Driver for CanDriver {
+    type StateType = CanState;
+
+    fn init(_state: &StateLock<Self>) {}
+    fn cleanup(_state: &StateLock<Self>) {}
+}
+
+impl driver::Can for CanDriver {
+    fn transmit(state: &StateLock<Self>, frame: &Frame) -> Result<()> {
+        critical_section::with(|cs| {
+            let mut state = state.borrow_ref_mut(cs);
+
+            if state.filters.iter().flatten().any(|x| x.matches(frame)) {
+                state.rx.push(*frame).map_err(|_| Error::Busy)?;
+            }
+
+            Ok(())
+        })
+    }
+
+    fn receive(state: &StateLock<Self>) -> Option<Frame> {
+        critical_section::with(|cs| state.borrow_ref_mut(cs).rx.pop())
+    }
+
+    fn configure_filter(
+        state: &StateLock<Self>,
+        bank: usize,
+        filter: Option<Filter>,
+    ) -> Result<()> {
+        critical_section::with(|cs| state.borrow_ref_mut(cs).filters[bank] = filter);
+        Ok(())
+    }
+}
+
+impl Filtered for CanDriver {
+    const BANKS: usize = 2;
+
+    fn banks(state: &mut CanState) -> &mut FilterBanks {
+        &mut state.banks
+    }
+}
+
+impl Streamed for CanDriver {
+    fn rx_queue(state: &mut CanState) -> &mut dyn Introspect {
+        &mut state.rx
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use googletest::prelude::*;
+
+    use dedrv::can::{tag, Can, CanExt};
+    use dedrv::queue::QueueStats;
+    use dedrv::{Accessor, Device};
+
+    use super::*;
+
+    static CAN0: Device<CanDriver> = Device::new();
+
+    #[test]
+    fn it_should_share_filter_banks_between_users() -> googletest::Result<()> {
+        let mut diag: Accessor<_, tag::Can> = CAN0.can();
+        let mut app = CAN0.can();
+
+        let request = diag.add_filter(Filter::new(Id::Standard(0x7df), 0x7ff)?)?;
+        let status = app.add_filter(Filter::new(Id::Extended(0x100), 0x1fff_ff00)?)?;
+        verify_that!((request.bank(), status.bank()), (eq(0), eq(1)))?;
+        verify_that!(
+            app.add_filter(Filter::new(Id::Standard(0), 0)?),
+            err(eq(&Error::NoFilterBank))
+        )?;
+
+        app.transmit(&Frame::new(Id::Standard(0x7df), &[0x02, 0x01, 0x0c])?)?;
+        app.transmit(&Frame::new(Id::Standard(0x7e8), &[])?)?;
+        app.transmit(&Frame::new(Id::Extended(0x142), &[1])?)?;
+
+        verify_that!(
+            diag.receive().map(|x| x.id()),
+            some(eq(Id::Standard(0x7df)))
+        )?;
+        verify_that!(
+            diag.receive().map(|x| x.id()),
+            some(eq(Id::Extended(0x142)))
+        )?;
+        verify_that!(diag.receive(), none())?;
+
+        diag.remove_filter(request)?;
+        verify_that!(
+            app.add_filter(Filter::new(Id::Standard(0), 0)?)
+                .map(|x| x.bank()),
+            ok(eq(&0))
+        )
+    }
+
+    #[test]
+    fn it_should_report_queue_overruns() -> googletest::Result<()> {
+        static CAN1: Device<CanDriver> = Device::new();
+
+        let mut can = CAN1.can();
+        can.add_filter(Filter::new(Id::Standard(0), 0)?)?;
+
+        for i in 0..6 {
+            let result = can.transmit(&Frame::new(Id::Standard(i), &[])?);
+            verify_that!(result.is_ok(), eq(i < 4))?;
+        }
+        can.receive();
+
+        verify_that!(
+            can.queue_stats(),
+            eq(QueueStats {
+                pending: 3,
+                capacity: 4,
+                high_watermark: 4,
+                overruns: 2,
+            })
+        )?;
+
+        can.reset_queue_stats();
+        verify_that!(can.queue_stats().overruns, eq(0))
+    }
+}
@@ -0,0 +1,77 @@
+use dedrv::{Accessor, Device, Driver, StateLock};
+
+/// Defines a GPIO class, whose pull-up and open-drain modes are optional.
+#[dedrv::class]
+#[dedrv::capability(pull_up, open_drain)]
+pub trait Gpio {
+    fn set(&mut self, high: bool);
+}
+
+/// A GPIO driver, which only supports the pull-up mode.
+pub struct GpioDriver;
+
+impl Driver for GpioDriver {
+    type StateType = bool;
+
+    fn init(_state: &StateLock<Self>) {}
+    fn cleanup(_state: &StateLock<Self>) {}
+}
+
+impl driver::Gpio for GpioDriver {
+    const CAPABILITIES: u32 = GpioCapability::PullUp.bit();
+
+    fn set(state: &StateLock<Self>, high: bool) {
+        critical_section::with(|cs| *state.borrow_ref_mut(cs) = high);
+    }
+}
+
+/// A GPIO driver, which does not advertise any capability.
+pub struct BasicGpioDriver;
+
+impl Driver for BasicGpioDriver {
+    type StateType = bool;
+
+    fn init(_state: &StateLock<Self>) {}
+    fn cleanup(_state: &StateLock<Self>) {}
+}
+
+impl driver::Gpio for BasicGpioDriver {
+    fn set(state: &StateLock<Self>, high: bool) {
+        critical_section::with(|cs| *state.borrow_ref_mut(cs) = high);
+    }
+}
+
+/// Release a line, which is driven low unless the hardware is open-drain.
+fn release<D: driver::Gpio>(mut pin: Accessor<'_, D, tag::Gpio>) -> bool {
+    let open_drain = pin.has_capability(GpioCapability::OpenDrain);
+    pin.set(open_drain);
+    open_drain
+}
+
+#[cfg(test)]
+mod tests {
+    use googletest::prelude::*;
+
+    use super::*;
+
+    static PIN0: Device<GpioDriver> = Device::new();
+    static PIN1: Device<BasicGpioDriver> = Device::new();
+
+    #[test]
+    fn it_should_query_driver_capabilities() -> googletest::Result<()> {
+        let pin = PIN0.gpio();
+
+        verify_that!(pin.has_capability(GpioCapability::PullUp), eq(true))?;
+        verify_that!(pin.has_capability(GpioCapability::OpenDrain), eq(false))?;
+        verify_that!(
+            PIN1.gpio().has_capability(GpioCapability::PullUp),
+            eq(false)
+        )
+    }
+
+    #[test]
+    fn it_should_adapt_generic_code() -> googletest::Result<()> {
+        verify_that!(release(PIN0.gpio()), eq(false))?;
+        verify_that!(release(PIN1.gpio()), eq(false))
+    }
+}
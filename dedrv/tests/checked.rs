@@ -0,0 +1,100 @@
+use dedrv::{Accessor, Device, Driver};
+
+/// The errors of the sensor class, which include the framework failures.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SensorError {
+    Framework(dedrv::Error),
+    OutOfRange,
+}
+
+impl From<dedrv::Error> for SensorError {
+    fn from(e: dedrv::Error) -> Self {
+        SensorError::Framework(e)
+    }
+}
+
+/// Defines a sensor class, whose framework failures are reported through its own error type.
+#[dedrv::class(checked)]
+pub trait Sensor {
+    fn sample(&self) -> Result<u16, SensorError>;
+    fn calibrate(&mut self, offset: u16) -> dedrv::Result<()>;
+}
+
+#[cfg(test)]
+mod tests {
+    use googletest::prelude::*;
+
+    use dedrv::{Error, StateGuard, StateGuardMut, StateLock};
+
+    use super::*;
+
+    /// A sensor driver, which uses the panicking borrow operations.
+    struct SensorDriver;
+
+    impl Driver for SensorDriver {
+        type StateType = u16;
+
+        fn init(_state: &StateLock<Self>) {}
+        fn cleanup(_state: &StateLock<Self>) {}
+    }
+
+    impl driver::Sensor for SensorDriver {
+        fn sample(state: &StateLock<Self>) -> core::result::Result<u16, SensorError> {
+            match critical_section::with(|cs| *state.borrow_ref(cs)) {
+                x if x > 1000 => Err(SensorError::OutOfRange),
+                x => Ok(x),
+            }
+        }
+
+        fn calibrate(state: &StateLock<Self>, offset: u16) -> dedrv::Result<()> {
+            // The borrow errors of the state are converted as well.
+            critical_section::with(|cs| {
+                *state.borrow(cs).try_borrow_mut()? = offset;
+                Ok(())
+            })
+        }
+    }
+
+    #[test]
+    fn it_should_report_uninitialized_device() -> googletest::Result<()> {
+        static DEVICE: Device<SensorDriver> = Device::new();
+
+        let mut sensor = DEVICE.sensor();
+        verify_that!(
+            sensor.sample(),
+            err(eq(&SensorError::Framework(Error::NotReady)))
+        )?;
+        verify_that!(sensor.calibrate(4), err(eq(&Error::NotReady)))?;
+
+        DEVICE.init();
+        verify_that!(sensor.calibrate(4), ok(eq(&())))?;
+        verify_that!(sensor.sample(), ok(eq(&4)))?;
+
+        sensor.calibrate(2000)?;
+        verify_that!(sensor.sample(), err(eq(&SensorError::OutOfRange)))
+    }
+
+    #[test]
+    fn it_should_report_busy_state() -> googletest::Result<()> {
+        static DEVICE: Device<SensorDriver> = Device::new();
+        DEVICE.init();
+
+        let mut sensor = DEVICE.sensor();
+
+        critical_section::with(|cs| {
+            let _guard = StateGuard::new(&DEVICE.state, cs);
+            verify_that!(sensor.sample(), ok(eq(&0)))?;
+            verify_that!(sensor.calibrate(1), err(eq(&Error::Busy)))
+        })?;
+
+        critical_section::with(|cs| {
+            let _guard = StateGuardMut::new(&DEVICE.state, cs);
+            verify_that!(
+                sensor.sample(),
+                err(eq(&SensorError::Framework(Error::Busy)))
+            )
+        })?;
+
+        verify_that!(sensor.calibrate(1), ok(eq(&())))
+    }
+}
@@ -0,0 +1,73 @@
+#![cfg(feature = "cleanup-on-drop")]
+
+use dedrv::{Device, Driver, Lifecycle};
+
+#[cfg(test)]
+mod tests {
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    use googletest::prelude::*;
+
+    use dedrv::StateLock;
+
+    use super::*;
+
+    // The number of calls to the cleanup function, which is per-test so tests may run in parallel.
+    macro_rules! counting_driver {
+        ($name:ident) => {
+            struct $name;
+
+            impl $name {
+                fn cleanups() -> &'static AtomicUsize {
+                    static CLEANUPS: AtomicUsize = AtomicUsize::new(0);
+                    &CLEANUPS
+                }
+            }
+
+            impl Driver for $name {
+                type StateType = u32;
+
+                fn init(_: &StateLock<Self>) {}
+
+                fn cleanup(_: &StateLock<Self>) {
+                    Self::cleanups().fetch_add(1, Ordering::SeqCst);
+                }
+            }
+        };
+    }
+
+    #[test]
+    fn it_should_cleanup_initialized_device_on_drop() {
+        counting_driver!(CountingDriver);
+
+        let device: Device<CountingDriver> = Device::new();
+        device.init();
+        assert_that!(device.lifecycle(), eq(Lifecycle::Initialized));
+
+        drop(device);
+        assert_that!(CountingDriver::cleanups().load(Ordering::SeqCst), eq(1));
+    }
+
+    #[test]
+    fn it_should_not_cleanup_uninitialized_device_on_drop() {
+        counting_driver!(CountingDriver);
+
+        let device: Device<CountingDriver> = Device::new();
+
+        drop(device);
+        assert_that!(CountingDriver::cleanups().load(Ordering::SeqCst), eq(0));
+    }
+
+    #[test]
+    fn it_should_cleanup_device_only_once() {
+        counting_driver!(CountingDriver);
+
+        let device: Device<CountingDriver> = Device::new();
+        device.init();
+        device.cleanup();
+        assert_that!(device.lifecycle(), eq(Lifecycle::Uninitialized));
+
+        drop(device);
+        assert_that!(CountingDriver::cleanups().load(Ordering::SeqCst), eq(1));
+    }
+}
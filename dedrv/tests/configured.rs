@@ -0,0 +1,91 @@
+use dedrv::{Accessor, Device, Driver};
+
+/// Defines a peripheral class whose behavior depends on the driver configuration.
+#[dedrv::class]
+pub trait Fifo {
+    fn push(&mut self, value: u8) -> bool;
+}
+
+#[cfg(test)]
+mod tests {
+    use googletest::prelude::*;
+
+    use dedrv::{DriverConfig, StateLock};
+
+    use super::*;
+
+    /// The driver-wide tuning parameters.
+    struct FifoConfig {
+        depth: usize,
+    }
+
+    /// A board description, which provides the driver configuration.
+    trait Board {
+        const FIFO: FifoConfig;
+    }
+
+    struct SmallBoard;
+
+    impl Board for SmallBoard {
+        const FIFO: FifoConfig = FifoConfig { depth: 2 };
+    }
+
+    struct LargeBoard;
+
+    impl Board for LargeBoard {
+        const FIFO: FifoConfig = FifoConfig { depth: 4 };
+    }
+
+    /// A driver that is generic over its configuration.
+    struct FifoDriver<B>(core::marker::PhantomData<B>);
+
+    impl<B: Board> Driver for FifoDriver<B> {
+        type StateType = usize;
+
+        const CHECK: () = assert!(
+            Self::CONFIG.depth.is_power_of_two(),
+            "the FIFO depth must be a power of two"
+        );
+
+        fn init(_state: &StateLock<Self>) {}
+        fn cleanup(_state: &StateLock<Self>) {}
+    }
+
+    impl<B: Board> DriverConfig for FifoDriver<B> {
+        type Config = FifoConfig;
+
+        const CONFIG: FifoConfig = B::FIFO;
+    }
+
+    impl<B: Board> driver::Fifo for FifoDriver<B> {
+        fn push(state: &StateLock<Self>, _value: u8) -> bool {
+            critical_section::with(|cs| {
+                let mut len = state.borrow_ref_mut(cs);
+                if *len == Self::CONFIG.depth {
+                    return false;
+                }
+
+                *len += 1;
+                true
+            })
+        }
+    }
+
+    #[test]
+    fn it_should_use_driver_config() -> googletest::Result<()> {
+        static SMALL: Device<FifoDriver<SmallBoard>> = Device::new();
+        static LARGE: Device<FifoDriver<LargeBoard>> = Device::new();
+
+        let mut small = SMALL.fifo();
+        let mut large = LARGE.fifo();
+
+        verify_that!((0..8).filter(|&x| small.push(x)).count(), eq(2))?;
+        verify_that!((0..8).filter(|&x| large.push(x)).count(), eq(4))
+    }
+
+    #[test]
+    fn it_should_not_compile_device_with_invalid_config() {
+        let t = trybuild::TestCases::new();
+        t.compile_fail("tests/units/driver_check_failed.rs");
+    }
+}
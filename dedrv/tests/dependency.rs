@@ -0,0 +1,137 @@
+use dedrv::{Accessor, Dependency, Driver, Result, StateLock};
+
+/// Defines a GPIO class, whose pin drives the reset line of the PHY.
+#[dedrv::class]
+pub trait Gpio {
+    fn set(&mut self, high: bool);
+}
+
+/// Defines a PHY class.
+pub mod phy {
+    use dedrv::Accessor;
+
+    #[dedrv::class]
+    pub trait Phy {
+        fn is_reset(&self) -> bool;
+    }
+}
+
+/// A GPIO driver, which counts its inits.
+pub struct GpioDriver;
+
+impl Driver for GpioDriver {
+    type StateType = (bool, u32);
+
+    fn init(state: &StateLock<Self>) {
+        critical_section::with(|cs| state.borrow_ref_mut(cs).1 += 1);
+    }
+
+    fn cleanup(_state: &StateLock<Self>) {}
+}
+
+impl driver::Gpio for GpioDriver {
+    fn set(state: &StateLock<Self>, high: bool) {
+        critical_section::with(|cs| state.borrow_ref_mut(cs).0 = high);
+    }
+}
+
+/// An external PHY, which holds the GPIO of its reset line.
+pub struct PhyDriver;
+
+impl Driver for PhyDriver {
+    type StateType = Dependency<GpioDriver, tag::Gpio>;
+
+    fn probe(state: &StateLock<Self>) -> Result<()> {
+        critical_section::with(|cs| state.borrow_ref_mut(cs).acquire(&tests::GPIO0))
+    }
+
+    fn init(state: &StateLock<Self>) {
+        critical_section::with(|cs| {
+            if let Ok(reset) = state.borrow_ref_mut(cs).get() {
+                reset.set(true);
+            }
+        });
+    }
+
+    fn cleanup(state: &StateLock<Self>) {
+        critical_section::with(|cs| state.borrow_ref_mut(cs).release());
+    }
+}
+
+impl phy::driver::Phy for PhyDriver {
+    fn is_reset(state: &StateLock<Self>) -> bool {
+        critical_section::with(|cs| state.borrow_ref(cs).is_acquired())
+    }
+}
+
+/// A device that wrongly depends on itself, which is being initialized when it is acquired.
+pub struct LoopDriver;
+
+impl Driver for LoopDriver {
+    type StateType = Dependency<LoopDriver>;
+
+    fn probe(state: &StateLock<Self>) -> Result<()> {
+        critical_section::with(|cs| state.borrow_ref_mut(cs).acquire(&tests::LOOP0))
+    }
+
+    fn init(_state: &StateLock<Self>) {}
+    fn cleanup(_state: &StateLock<Self>) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use googletest::prelude::*;
+
+    use dedrv::{Descriptor, Device, DeviceRef, Error, Lifecycle, Registry};
+
+    use super::*;
+
+    pub static GPIO0: Device<GpioDriver> = Device::new();
+    static PHY0: Device<PhyDriver> = Device::new();
+    pub static LOOP0: Device<LoopDriver> = Device::new();
+
+    #[test]
+    fn it_should_init_dependency_first() -> googletest::Result<()> {
+        // The PHY comes first in the table, so its dependency is initialized on demand.
+        static TABLE: [Descriptor; 2] = [
+            Descriptor::new("/eth0/phy", &PHY0),
+            Descriptor::new("/gpio0", &GPIO0),
+        ];
+
+        let report = Registry::from_slice(&TABLE).init()?;
+
+        verify_that!(report.is_complete(), eq(true))?;
+        verify_that!(GPIO0.lifecycle(), eq(Lifecycle::Initialized))?;
+        verify_that!(
+            critical_section::with(|cs| *GPIO0.state_ref(cs)),
+            eq((true, 1))
+        )?;
+        verify_that!(GPIO0.accessors(), eq(1))?;
+
+        PHY0.cleanup();
+        verify_that!(GPIO0.accessors(), eq(0))
+    }
+
+    #[test]
+    fn it_should_reject_same_stage_dependency() -> googletest::Result<()> {
+        static GPIO1: Device<GpioDriver> = Device::new();
+        static PHY1: Device<PhyDriver> = Device::new();
+        static TABLE: [Descriptor; 2] = [
+            Descriptor::new("/eth1/phy", &PHY1).with_dependencies(&[DeviceRef::new(&GPIO1)]),
+            Descriptor::new("/gpio1", &GPIO1),
+        ];
+
+        verify_that!(
+            Registry::from_slice(&TABLE).init().err(),
+            some(eq(&Error::UnorderedDependency))
+        )?;
+        verify_that!(PHY1.lifecycle(), eq(Lifecycle::Uninitialized))?;
+        verify_that!(GPIO1.lifecycle(), eq(Lifecycle::Uninitialized))
+    }
+
+    #[test]
+    fn it_should_not_init_device_being_initialized() -> googletest::Result<()> {
+        verify_that!(LOOP0.try_init(), err(eq(&Error::Busy)))?;
+        verify_that!(LOOP0.lifecycle(), eq(Lifecycle::Uninitialized))
+    }
+}
@@ -0,0 +1,91 @@
+#![cfg(feature = "dma")]
+
+use dedrv::dma::{Completion, Transferred};
+use dedrv::{Accessor, Driver, Error, Result, StateLock};
+
+/// Defines a UART class, whose reads are split transactions.
+#[dedrv::class]
+pub trait Uart {
+    fn start_read(&mut self, buf: &'static mut [u8])
+        -> dedrv::dma::Transfer<'_, &'static mut [u8]>;
+}
+
+/// A fake UART, whose DMA channel is the address and the length of the buffer being filled.
+pub struct UartState {
+    completion: Completion,
+    channel: Option<(usize, usize)>,
+}
+
+pub struct UartDriver;
+
+impl Driver for UartDriver {
+    type StateType = UartState;
+
+    fn init(_state: &StateLock<Self>) {}
+    fn cleanup(_state: &StateLock<Self>) {}
+}
+
+impl Transferred for UartDriver {
+    fn completion(state: &mut UartState) -> &mut Completion {
+        &mut state.completion
+    }
+}
+
+impl driver::Uart for UartDriver {
+    fn start_read(state: &StateLock<Self>, buf: &mut &'static mut [u8]) -> Result<()> {
+        if buf.is_empty() {
+            return Err(Error::InvalidArgument);
+        }
+
+        critical_section::with(|cs| {
+            state.borrow_ref_mut(cs).channel = Some((buf.as_mut_ptr() as usize, buf.len()));
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use googletest::prelude::*;
+
+    use dedrv::Device;
+
+    use super::*;
+
+    static UART0: Device<UartDriver> = Device::new();
+
+    /// The interrupt handler of the fake UART, which fills the buffer like the DMA channel.
+    fn on_interrupt(data: u8) {
+        critical_section::with(|cs| {
+            let mut state = UART0.state_ref_mut(cs);
+
+            if let Some((addr, len)) = state.channel.take() {
+                // SAFETY: The buffer is owned by the transfer until its completion.
+                unsafe { core::slice::from_raw_parts_mut(addr as *mut u8, len).fill(data) };
+                state.completion.complete(Ok(()));
+            }
+        })
+    }
+
+    #[test]
+    fn it_should_give_buffer_back_on_completion() -> googletest::Result<()> {
+        UART0.init();
+        let mut uart = UART0.uart();
+        let transfer = uart.start_read(Box::leak(Box::new([0; 4])));
+        verify_that!(transfer.is_done(), eq(false))?;
+
+        // Another transfer cannot be started while the first one is in progress.
+        let (result, _) = UART0.uart().start_read(Box::leak(Box::new([0; 1]))).wait();
+        verify_that!(result, err(eq(&Error::Busy)))?;
+
+        on_interrupt(0x55);
+        let (result, buf) = transfer.wait();
+        verify_that!(result, ok(eq(&())))?;
+        verify_that!(buf, eq(&[0x55; 4]))?;
+
+        // A transfer that the driver refuses to start is complete right away.
+        let (result, _) = uart.start_read(&mut []).wait();
+        verify_that!(result, err(eq(&Error::InvalidArgument)))?;
+        verify_that!(uart.start_read(buf).is_done(), eq(false))
+    }
+}
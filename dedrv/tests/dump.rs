@@ -0,0 +1,81 @@
+use core::fmt::Write;
+
+use dedrv::{Accessor, Device, Driver, StateLock};
+
+/// Defines a serial class, whose driver dumps its pending transfer.
+#[dedrv::class]
+pub trait Serial {
+    fn send(&mut self, byte: u8);
+}
+
+/// A serial driver, whose state is the last sent byte.
+pub struct SerialDriver;
+
+impl Driver for SerialDriver {
+    type StateType = u8;
+
+    fn init(_state: &StateLock<Self>) {}
+    fn cleanup(_state: &StateLock<Self>) {}
+
+    fn dump(state: &u8, out: &mut dyn Write) -> core::fmt::Result {
+        write!(out, "last={state:#04x}")
+    }
+}
+
+impl driver::Serial for SerialDriver {
+    fn send(state: &StateLock<Self>, byte: u8) {
+        critical_section::with(|cs| *state.borrow_ref_mut(cs) = byte)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use googletest::prelude::*;
+
+    use dedrv::{Descriptor, Registry};
+
+    use super::*;
+
+    #[test]
+    fn it_should_dump_registry() -> googletest::Result<()> {
+        static UART0: Device<SerialDriver> = Device::new();
+        static UART1: Device<SerialDriver> = Device::new();
+        static TABLE: [Descriptor; 2] = [
+            Descriptor::new("/uart0", &UART0),
+            Descriptor::new("/uart1", &UART1),
+        ];
+
+        UART0.init();
+        UART0.serial().send(0x2a);
+
+        let mut out = String::new();
+        Registry::from_slice(&TABLE).dump(&mut out)?;
+
+        verify_that!(
+            out,
+            eq("/uart0 (initialized): last=0x2a\n/uart1 (uninitialized): last=0x00\n")
+        )
+    }
+
+    #[test]
+    fn it_should_not_panic_on_borrowed_state() -> googletest::Result<()> {
+        static UART0: Device<SerialDriver> = Device::new();
+
+        let mut out = String::new();
+        critical_section::with(|cs| {
+            let _state = UART0.state_ref_mut(cs);
+            UART0.dump(&mut out)
+        })?;
+
+        verify_that!(out, eq("<busy>"))
+    }
+
+    #[test]
+    fn it_should_dump_empty_registry_on_host() -> googletest::Result<()> {
+        let mut out = String::new();
+        dedrv::dump(&mut out)?;
+        dedrv::dump_on_panic();
+
+        verify_that!(out, eq(""))
+    }
+}
@@ -0,0 +1,123 @@
+#![cfg(feature = "exti")]
+
+use dedrv::exti::{Edge, Handler, Lines};
+use dedrv::{Driver, Result, StateLock};
+
+/// A fake EXTI controller, whose pending flags are raised by the tests.
+#[derive(Default)]
+pub struct ExtiState {
+    lines: Lines,
+    rising: u32,
+    falling: u32,
+    pending: u32,
+}
+
+pub struct ExtiDriver;
+
+impl Driver for ExtiDriver {
+    type StateType = ExtiState;
+
+    fn init(_state: &StateLock<Self>) {}
+    fn cleanup(_state: &StateLock<Self>) {}
+}
+
+impl dedrv::exti::driver::ExtiController for ExtiDriver {
+    fn listen(state: &StateLock<Self>, line: u8, edge: Edge, handler: Handler) -> Result<()> {
+        critical_section::with(|cs| {
+            let mut state = state.borrow_ref_mut(cs);
+            state.lines.set(line, handler)?;
+
+            if edge != Edge::Falling {
+                state.rising |= 1 << line;
+            }
+            if edge != Edge::Rising {
+                state.falling |= 1 << line;
+            }
+
+            Ok(())
+        })
+    }
+
+    fn unlisten(state: &StateLock<Self>, line: u8) {
+        critical_section::with(|cs| {
+            let mut state = state.borrow_ref_mut(cs);
+            state.lines.clear(line);
+            state.rising &= !(1 << line);
+            state.falling &= !(1 << line);
+        })
+    }
+
+    fn take_pending(state: &StateLock<Self>, mask: u32) -> u32 {
+        critical_section::with(|cs| {
+            let mut state = state.borrow_ref_mut(cs);
+            let pending = state.pending & mask;
+            state.pending &= !mask;
+            pending
+        })
+    }
+
+    fn handler(state: &StateLock<Self>, line: u8) -> Option<Handler> {
+        critical_section::with(|cs| state.borrow_ref(cs).lines.get(line))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::sync::atomic::{AtomicU32, Ordering};
+
+    use googletest::prelude::*;
+
+    use dedrv::exti::{dispatch, ExtiController, ExtiControllerExt};
+    use dedrv::{Device, Error};
+
+    use super::*;
+
+    static EXTI: Device<ExtiDriver> = Device::new();
+
+    /// The lines that have been handled, as a mask.
+    static HANDLED: AtomicU32 = AtomicU32::new(0);
+
+    fn raise(mask: u32) {
+        critical_section::with(|cs| EXTI.state.borrow_ref_mut(cs).pending |= mask);
+    }
+
+    fn on_line(line: u8) {
+        HANDLED.fetch_or(1 << line, Ordering::Relaxed);
+    }
+
+    fn on_line_once(line: u8) {
+        on_line(line);
+        EXTI.exti_controller().unlisten(line);
+    }
+
+    #[test]
+    fn it_should_demux_shared_lines() -> googletest::Result<()> {
+        let mut exti = EXTI.exti_controller();
+
+        verify_that!(exti.listen(5, Edge::Rising, on_line), ok(eq(&())))?;
+        verify_that!(exti.listen(7, Edge::Both, on_line_once), ok(eq(&())))?;
+        verify_that!(
+            exti.listen(5, Edge::Falling, on_line),
+            err(eq(&Error::Busy))
+        )?;
+        verify_that!(
+            exti.listen(16, Edge::Rising, on_line),
+            err(eq(&Error::InvalidLine))
+        )?;
+
+        // The line 0 is out of the vector, and the line 6 has no handler.
+        raise(1 << 0 | 1 << 5 | 1 << 6 | 1 << 7);
+
+        verify_that!(dispatch(&exti, 0x03e0), eq(1 << 5 | 1 << 7))?;
+        verify_that!(HANDLED.load(Ordering::Relaxed), eq(1 << 5 | 1 << 7))?;
+        verify_that!(
+            critical_section::with(|cs| EXTI.state.borrow_ref(cs).pending),
+            eq(1 << 0)
+        )?;
+
+        // The handler of the line 7 stopped listening on its own.
+        raise(1 << 7);
+        verify_that!(dispatch(&exti, 0x03e0), eq(0))?;
+        verify_that!(exti.handler(7).is_none(), eq(true))
+    }
+}
@@ -0,0 +1,137 @@
+use dedrv::{Accessor, Device, Driver};
+
+/// Defines a peripheral class whose methods report a busy state instead of panicking.
+#[dedrv::class]
+pub trait Counter {
+    fn value(&self) -> dedrv::Result<u32>;
+    fn increment(&mut self) -> dedrv::Result<()>;
+}
+
+/// An error that borrows the input of the method.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseError<'a>(&'a str);
+
+/// Defines a peripheral class whose error type is not `'static`.
+#[dedrv::class(driver_mod = "parser_driver", tag = "ParserTag")]
+pub trait Parser {
+    fn parse<'a>(&self, input: &'a str) -> Result<u32, ParseError<'a>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use googletest::prelude::*;
+
+    use dedrv::{Error, StateGuard, StateGuardMut, StateLock, TryStateLock};
+
+    use super::*;
+
+    /// A driver that only uses panic-free borrow operations.
+    struct CounterDriver;
+
+    impl Driver for CounterDriver {
+        type StateType = u32;
+
+        fn init(_state: &StateLock<Self>) {}
+        fn cleanup(_state: &StateLock<Self>) {}
+    }
+
+    impl driver::Counter for CounterDriver {
+        fn value(state: &StateLock<Self>) -> dedrv::Result<u32> {
+            critical_section::with(|cs| Ok(*state.try_borrow_ref(cs)?))
+        }
+
+        fn increment(state: &StateLock<Self>) -> dedrv::Result<()> {
+            critical_section::with(|cs| {
+                *state.try_borrow_ref_mut(cs)? += 1;
+                Ok(())
+            })
+        }
+    }
+
+    impl parser_driver::Parser for CounterDriver {
+        fn parse<'a>(
+            _state: &StateLock<Self>,
+            input: &'a str,
+        ) -> core::result::Result<u32, ParseError<'a>> {
+            input.parse().map_err(|_| ParseError(input))
+        }
+    }
+
+    #[test]
+    fn it_should_return_borrowed_error() -> googletest::Result<()> {
+        static DEVICE: Device<CounterDriver> = Device::new();
+
+        let parser = DEVICE.parser();
+        verify_that!(parser.parse("12"), ok(eq(&12)))?;
+        verify_that!(parser.parse("x"), err(eq(&ParseError("x"))))
+    }
+
+    #[test]
+    fn it_should_access_free_state() -> googletest::Result<()> {
+        static DEVICE: Device<CounterDriver> = Device::new();
+
+        let mut counter = DEVICE.accessor::<tag::Counter>();
+        counter.increment()?;
+        verify_that!(counter.value(), ok(eq(&1)))
+    }
+
+    #[test]
+    fn it_should_report_busy_state() -> googletest::Result<()> {
+        static DEVICE: Device<CounterDriver> = Device::new();
+
+        let mut counter = DEVICE.accessor::<tag::Counter>();
+
+        critical_section::with(|cs| {
+            let _guard = StateGuardMut::new(&DEVICE.state, cs);
+            verify_that!(counter.value(), err(eq(&Error::Busy)))
+        })?;
+
+        critical_section::with(|cs| {
+            let _guard = StateGuard::new(&DEVICE.state, cs);
+            verify_that!(counter.increment(), err(eq(&Error::Busy)))?;
+            verify_that!(counter.value(), ok(eq(&0)))
+        })?;
+
+        verify_that!(counter.increment(), ok(eq(&())))
+    }
+
+    #[test]
+    fn it_should_not_guard_busy_state() -> googletest::Result<()> {
+        static DEVICE: Device<CounterDriver> = Device::new();
+
+        critical_section::with(|cs| {
+            let _guard = StateGuard::new(&DEVICE.state, cs);
+            verify_that!(
+                StateGuardMut::try_new(&DEVICE.state, cs).map(|_| ()),
+                err(eq(&Error::Busy))
+            )
+        })?;
+
+        verify_that!(
+            critical_section::with(|cs| DEVICE.try_state_ref(cs).map(|x| *x)),
+            ok(eq(&0))
+        )
+    }
+
+    #[test]
+    #[cfg(feature = "error-history")]
+    fn it_should_record_error_history() -> googletest::Result<()> {
+        static DEVICE: Device<CounterDriver> = Device::new();
+
+        let mut counter = DEVICE.counter();
+
+        critical_section::with(|cs| {
+            let _guard = StateGuardMut::new(&DEVICE.state, cs);
+            verify_that!(counter.value(), err(eq(&Error::Busy)))?;
+            verify_that!(counter.increment(), err(eq(&Error::Busy)))
+        })?;
+        verify_that!(counter.increment(), ok(eq(&())))?;
+
+        let history = counter.inner().error_history();
+        verify_that!(
+            history.iter().map(|x| x.method).collect::<Vec<_>>(),
+            elements_are![eq(&"value"), eq(&"increment")]
+        )?;
+        verify_that!(history.last().map(|x| &x.error), some(eq(&Error::Busy)))
+    }
+}
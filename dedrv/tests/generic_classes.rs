@@ -0,0 +1,71 @@
+use dedrv::{Accessor, Device, Driver};
+
+/// Defines a peripheral class that is generic over the width of its samples.
+#[dedrv::class]
+pub trait Dac<Word>
+where
+    Word: Copy,
+{
+    fn write(&mut self, word: Word);
+    fn last(&self) -> Word;
+}
+
+#[cfg(test)]
+mod tests {
+    use googletest::prelude::*;
+
+    use dedrv::StateLock;
+
+    use super::*;
+
+    /// A DAC that supports both 8-bit and 12-bit (i.e. `u16`) samples.
+    struct DacDriver;
+
+    impl Driver for DacDriver {
+        type StateType = u16;
+
+        fn init(_state: &StateLock<Self>) {}
+        fn cleanup(_state: &StateLock<Self>) {}
+    }
+
+    impl driver::Dac<u8> for DacDriver {
+        fn write(state: &StateLock<Self>, word: u8) {
+            critical_section::with(|cs| *state.borrow_ref_mut(cs) = u16::from(word) << 4)
+        }
+
+        fn last(state: &StateLock<Self>) -> u8 {
+            critical_section::with(|cs| (*state.borrow_ref(cs) >> 4) as u8)
+        }
+    }
+
+    impl driver::Dac<u16> for DacDriver {
+        fn write(state: &StateLock<Self>, word: u16) {
+            critical_section::with(|cs| *state.borrow_ref_mut(cs) = word & 0x0fff)
+        }
+
+        fn last(state: &StateLock<Self>) -> u16 {
+            critical_section::with(|cs| *state.borrow_ref(cs))
+        }
+    }
+
+    /// Any instantiation of the class may be taken generically.
+    fn ramp<Word: Copy>(dac: &mut impl Dac<Word>, words: &[Word]) -> Word {
+        for &word in words {
+            dac.write(word);
+        }
+        dac.last()
+    }
+
+    #[test]
+    fn it_should_access_each_class_instantiation() -> googletest::Result<()> {
+        static DEVICE: Device<DacDriver> = Device::new();
+
+        let mut wide = DEVICE.dac::<u16>();
+        verify_that!(ramp(&mut wide, &[0x123, 0xabcd]), eq(0x0bcd))?;
+
+        let mut narrow: Accessor<_, tag::Dac<u8>> = DEVICE.accessor();
+        verify_that!(narrow.last(), eq(0xbc))?;
+        verify_that!(ramp(&mut narrow, &[0x42]), eq(0x42))?;
+        verify_that!(wide.last(), eq(0x420))
+    }
+}
@@ -0,0 +1,68 @@
+use dedrv::{Accessor, Device, Driver};
+
+/// Defines a peripheral class with generic methods.
+#[dedrv::class]
+pub trait Eeprom {
+    fn read_exact<const N: usize>(&self, offset: usize, buf: &mut [u8; N]);
+    fn write_exact<const N: usize>(&mut self, offset: usize, buf: &[u8; N]);
+    fn page<const N: usize>(&self) -> [u8; N];
+    fn fill<T: Into<u8>, const N: usize>(&mut self, value: T);
+}
+
+#[cfg(test)]
+mod tests {
+    use googletest::prelude::*;
+
+    use dedrv::StateLock;
+
+    use super::*;
+
+    struct EepromDriver;
+
+    impl Driver for EepromDriver {
+        type StateType = [u8; 16];
+
+        fn init(_state: &StateLock<Self>) {}
+        fn cleanup(_state: &StateLock<Self>) {}
+    }
+
+    impl driver::Eeprom for EepromDriver {
+        fn read_exact<const N: usize>(state: &StateLock<Self>, offset: usize, buf: &mut [u8; N]) {
+            critical_section::with(|cs| {
+                buf.copy_from_slice(&state.borrow_ref(cs)[offset..offset + N]);
+            })
+        }
+
+        fn write_exact<const N: usize>(state: &StateLock<Self>, offset: usize, buf: &[u8; N]) {
+            critical_section::with(|cs| {
+                state.borrow_ref_mut(cs)[offset..offset + N].copy_from_slice(buf);
+            })
+        }
+
+        fn page<const N: usize>(state: &StateLock<Self>) -> [u8; N] {
+            let mut buf = [0; N];
+            Self::read_exact(state, 0, &mut buf);
+            buf
+        }
+
+        fn fill<T: Into<u8>, const N: usize>(state: &StateLock<Self>, value: T) {
+            let value = value.into();
+            critical_section::with(|cs| state.borrow_ref_mut(cs)[..N].fill(value))
+        }
+    }
+
+    #[test]
+    fn it_should_forward_const_generics() -> googletest::Result<()> {
+        static DEVICE: Device<EepromDriver> = Device::new();
+
+        let mut eeprom = DEVICE.eeprom();
+        eeprom.write_exact(2, b"abc");
+
+        let mut buf = [0u8; 2];
+        eeprom.read_exact(3, &mut buf);
+        verify_that!(&buf, eq(b"bc"))?;
+
+        eeprom.fill::<u8, 2>(b'z');
+        verify_that!(eeprom.page::<4>(), eq(*b"zzab"))
+    }
+}
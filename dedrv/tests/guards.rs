@@ -0,0 +1,98 @@
+use critical_section::CriticalSection;
+use dedrv::{Accessor, Device, Driver, StateGuard, StateGuardMut};
+
+/// Defines a peripheral class that exposes its driver buffers.
+#[dedrv::class]
+pub trait Buffered {
+    fn rx_buffer<'cs>(&'cs self, cs: CriticalSection<'cs>) -> StateGuard<'cs, [u8; 4]>;
+    fn tx_buffer<'cs>(&'cs mut self, cs: CriticalSection<'cs>) -> StateGuardMut<'cs, [u8; 4]>;
+}
+
+#[cfg(test)]
+mod tests {
+    use googletest::prelude::*;
+
+    use dedrv::StateLock;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct Buffers {
+        rx: [u8; 4],
+        tx: [u8; 4],
+    }
+
+    struct BufferedDriver;
+
+    impl Driver for BufferedDriver {
+        type StateType = Buffers;
+
+        fn init(state: &StateLock<Self>) {
+            critical_section::with(|cs| state.borrow_ref_mut(cs).rx = *b"ping");
+        }
+
+        fn cleanup(_state: &StateLock<Self>) {}
+    }
+
+    impl driver::Buffered for BufferedDriver {
+        fn rx_buffer<'cs>(
+            state: &'cs StateLock<Self>,
+            cs: CriticalSection<'cs>,
+        ) -> StateGuard<'cs, [u8; 4]> {
+            StateGuard::map(StateGuard::new(state, cs), |s| &s.rx)
+        }
+
+        fn tx_buffer<'cs>(
+            state: &'cs StateLock<Self>,
+            cs: CriticalSection<'cs>,
+        ) -> StateGuardMut<'cs, [u8; 4]> {
+            StateGuardMut::map(StateGuardMut::new(state, cs), |s| &mut s.tx)
+        }
+    }
+
+    #[test]
+    fn it_should_inspect_buffer_through_guard() -> googletest::Result<()> {
+        static DEVICE: Device<BufferedDriver> = Device::new();
+        DEVICE.init();
+
+        let buffered = DEVICE.accessor::<tag::Buffered>();
+        critical_section::with(|cs| {
+            let rx = buffered.rx_buffer(cs);
+            verify_that!(&*rx, eq(b"ping"))
+        })
+    }
+
+    #[test]
+    fn it_should_modify_buffer_through_guard() -> googletest::Result<()> {
+        static DEVICE: Device<BufferedDriver> = Device::new();
+        DEVICE.init();
+
+        let mut buffered = DEVICE.accessor::<tag::Buffered>();
+        critical_section::with(|cs| buffered.tx_buffer(cs).copy_from_slice(b"pong"));
+
+        let tx = critical_section::with(|cs| DEVICE.state_ref(cs).tx);
+        verify_that!(&tx, eq(b"pong"))
+    }
+
+    #[test]
+    fn it_should_release_borrow_on_drop() -> googletest::Result<()> {
+        static DEVICE: Device<BufferedDriver> = Device::new();
+        DEVICE.init();
+
+        let mut buffered = DEVICE.accessor::<tag::Buffered>();
+        let first = critical_section::with(|cs| {
+            drop(buffered.rx_buffer(cs));
+
+            // The state would already be borrowed otherwise, which panics.
+            buffered.tx_buffer(cs)[0] = b'x';
+            buffered.rx_buffer(cs)[0]
+        });
+        verify_that!(first, eq(b'p'))
+    }
+
+    #[test]
+    fn it_should_not_compile_guard_outside_critical_section() {
+        let t = trybuild::TestCases::new();
+        t.compile_fail("tests/units/guard_escape.rs");
+    }
+}
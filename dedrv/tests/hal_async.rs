@@ -0,0 +1,145 @@
+#![cfg(feature = "hal-async")]
+
+use core::future::Future;
+use core::pin::pin;
+use core::task::{Context, Poll, Waker};
+
+use dedrv::Driver;
+
+/// Poll a future to completion, which is enough for drivers that never wait.
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let mut cx = Context::from_waker(Waker::noop());
+
+    loop {
+        if let Poll::Ready(x) = future.as_mut().poll(&mut cx) {
+            return x;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_hal_async::delay::DelayNs;
+    use embedded_hal_async::i2c::{ErrorKind, I2c, NoAcknowledgeSource, Operation};
+
+    use googletest::prelude::*;
+
+    use dedrv::hal_async::delay::{self, DelayExt};
+    use dedrv::hal_async::i2c::{self, I2cExt};
+    use dedrv::{Device, StateLock};
+
+    use super::*;
+
+    /// The address of the fake sensor on the bus.
+    const ADDRESS: u8 = 0x76;
+
+    /// A fake I2C bus, where a single sensor exposes its register map.
+    #[derive(Default)]
+    struct BusState {
+        registers: [u8; 4],
+        pointer: usize,
+    }
+
+    struct BusDriver;
+
+    impl Driver for BusDriver {
+        type StateType = BusState;
+
+        fn init(state: &StateLock<Self>) {
+            critical_section::with(|cs| state.borrow_ref_mut(cs).registers = *b"\x58abc");
+        }
+
+        fn cleanup(_state: &StateLock<Self>) {}
+    }
+
+    impl i2c::driver::I2c for BusDriver {
+        async fn transaction(
+            state: &StateLock<Self>,
+            address: u8,
+            operations: &mut [Operation<'_>],
+        ) -> core::result::Result<(), ErrorKind> {
+            if address != ADDRESS {
+                return Err(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address));
+            }
+
+            critical_section::with(|cs| {
+                let mut state = state.borrow_ref_mut(cs);
+
+                for op in operations {
+                    match op {
+                        Operation::Write(bytes) => state.pointer = bytes[0] as usize,
+                        Operation::Read(buf) => {
+                            for x in buf.iter_mut() {
+                                *x = state.registers[state.pointer];
+                                state.pointer = (state.pointer + 1) % 4;
+                            }
+                        }
+                    }
+                }
+            });
+
+            Ok(())
+        }
+    }
+
+    /// A fake timer, which only accumulates the requested delays.
+    struct TimerDriver;
+
+    impl Driver for TimerDriver {
+        type StateType = u64;
+
+        fn init(_state: &StateLock<Self>) {}
+        fn cleanup(_state: &StateLock<Self>) {}
+    }
+
+    impl delay::driver::Delay for TimerDriver {
+        async fn delay_ns(state: &StateLock<Self>, ns: u32) {
+            critical_section::with(|cs| *state.borrow_ref_mut(cs) += ns as u64);
+        }
+    }
+
+    /// A sensor driver from the ecosystem, which is generic over the bus and the delay.
+    async fn read_chip_id<B: I2c, T: DelayNs>(
+        bus: &mut B,
+        timer: &mut T,
+    ) -> core::result::Result<u8, B::Error> {
+        timer.delay_ms(2).await;
+
+        let mut id = [0u8];
+        bus.write_read(ADDRESS, &[0], &mut id).await?;
+        Ok(id[0])
+    }
+
+    #[test]
+    fn it_should_run_ecosystem_driver_over_devices() -> googletest::Result<()> {
+        static BUS: Device<BusDriver> = Device::new();
+        static TIMER: Device<TimerDriver> = Device::new();
+        BUS.init();
+
+        let mut bus = BUS.i2c();
+        let mut timer = TIMER.delay();
+
+        verify_that!(block_on(read_chip_id(&mut bus, &mut timer)), ok(eq(0x58)))?;
+        verify_that!(
+            critical_section::with(|cs| *TIMER.state.borrow_ref(cs)),
+            eq(2_000_000)
+        )?;
+
+        let mut buf = [0u8; 2];
+        verify_that!(block_on(bus.read(ADDRESS, &mut buf)), ok(eq(())))?;
+        verify_that!(buf, eq(*b"ab"))
+    }
+
+    #[test]
+    fn it_should_report_bus_errors() -> googletest::Result<()> {
+        static BUS: Device<BusDriver> = Device::new();
+
+        let mut bus = BUS.i2c();
+
+        verify_that!(
+            block_on(bus.write(0x10, &[0])),
+            err(eq(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address)))
+        )
+    }
+}
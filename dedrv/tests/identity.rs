@@ -0,0 +1,75 @@
+use dedrv::{Accessor, Device, Driver};
+
+/// Defines two classes of the same peripheral.
+#[dedrv::class]
+pub trait Led {
+    fn set(&mut self, on: bool);
+}
+
+pub mod blink {
+    use dedrv::Accessor;
+
+    #[dedrv::class]
+    pub trait Blink {
+        fn period(&self) -> u32;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use googletest::prelude::*;
+
+    use dedrv::{Descriptor, StateLock};
+
+    use super::*;
+
+    struct LedDriver;
+
+    impl Driver for LedDriver {
+        type StateType = bool;
+
+        fn init(_state: &StateLock<Self>) {}
+        fn cleanup(_state: &StateLock<Self>) {}
+    }
+
+    impl driver::Led for LedDriver {
+        fn set(state: &StateLock<Self>, on: bool) {
+            critical_section::with(|cs| *state.borrow_ref_mut(cs) = on);
+        }
+    }
+
+    impl blink::driver::Blink for LedDriver {
+        fn period(_state: &StateLock<Self>) -> u32 {
+            500
+        }
+    }
+
+    static LED0: Device<LedDriver> = Device::new();
+    static LED1: Device<LedDriver> = Device::new();
+
+    #[test]
+    fn it_should_compare_accessors_by_device() -> googletest::Result<()> {
+        let led: Accessor<_, tag::Led> = LED0.accessor();
+        let blink: Accessor<_, blink::tag::Blink> = LED0.accessor();
+        let other: Accessor<_, tag::Led> = LED1.accessor();
+
+        verify_that!(led == blink, eq(true))?;
+        verify_that!(led == other, eq(false))?;
+        verify_that!(led.id(), eq(LED0.id()))?;
+        verify_that!(Descriptor::new("/led0", &LED0).id(), eq(LED0.id()))
+    }
+
+    #[test]
+    fn it_should_key_tables_by_device() -> googletest::Result<()> {
+        let mut counts = HashMap::new();
+
+        for device in [&LED0, &LED1, &LED0] {
+            *counts.entry(device.accessor::<tag::Led>()).or_insert(0) += 1;
+        }
+
+        verify_that!(counts.len(), eq(2))?;
+        verify_that!(counts[&LED0.accessor::<tag::Led>()], eq(2))
+    }
+}
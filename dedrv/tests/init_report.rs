@@ -0,0 +1,81 @@
+use dedrv::{Accessor, Device, Driver, Result, StateLock};
+
+/// Defines a sensor class, whose hardware is optional on the board.
+#[dedrv::class]
+pub trait Sensor {
+    fn sample(&self) -> u16;
+}
+
+/// A sensor driver, whose state is the identification register.
+pub struct SensorDriver;
+
+impl Driver for SensorDriver {
+    type StateType = u8;
+
+    fn init(_state: &StateLock<Self>) {}
+    fn cleanup(_state: &StateLock<Self>) {}
+
+    fn probe(state: &StateLock<Self>) -> Result<()> {
+        match critical_section::with(|cs| *state.borrow_ref(cs)) {
+            0x42 => Ok(()),
+            _ => Err(dedrv::Error::DeviceNotFound),
+        }
+    }
+}
+
+impl driver::Sensor for SensorDriver {
+    fn sample(_state: &StateLock<Self>) -> u16 {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use googletest::prelude::*;
+
+    use dedrv::{Descriptor, Error, Lifecycle, Path, Registry};
+
+    use super::*;
+
+    static PRESENT: Device<SensorDriver> = Device::new();
+    static MISSING: Device<SensorDriver> = Device::new();
+
+    static TABLE: [Descriptor; 2] = [
+        Descriptor::new("/i2c0/baro", &MISSING),
+        Descriptor::new("/i2c0/imu", &PRESENT),
+    ];
+
+    #[test]
+    fn it_should_boot_degraded() -> googletest::Result<()> {
+        critical_section::with(|cs| *PRESENT.state_ref_mut(cs) = 0x42);
+
+        let report = Registry::from_slice(&TABLE).init()?;
+
+        verify_that!((report.failed(), report.is_aborted()), (eq(1), eq(false)))?;
+        verify_that!(PRESENT.lifecycle(), eq(Lifecycle::Initialized))?;
+        verify_that!(MISSING.lifecycle(), eq(Lifecycle::Uninitialized))?;
+        verify_that!(MISSING.init_result(), some(err(eq(&Error::DeviceNotFound))))?;
+        verify_that!(
+            report.missing().collect::<Vec<_>>(),
+            elements_are![eq(&Path::from_static("/i2c0/baro"))]
+        )?;
+        verify_that!(
+            report.to_string(),
+            eq("/i2c0/baro: device not found\n/i2c0/imu: ok\n")
+        )
+    }
+
+    #[test]
+    fn it_should_report_device_initialized_beforehand() -> googletest::Result<()> {
+        static EARLY: Device<SensorDriver> = Device::new();
+        static TABLE: [Descriptor; 1] = [Descriptor::new("/i2c0/mag", &EARLY)];
+
+        // The device is brought up without a probe (e.g. as a dependency of another one).
+        EARLY.init();
+        let report = Registry::from_slice(&TABLE).init()?;
+
+        verify_that!(EARLY.init_result(), some(ok(eq(&()))))?;
+        verify_that!(report.missing().count(), eq(0))?;
+        verify_that!(report.to_string(), eq("/i2c0/mag: ok\n"))
+    }
+}
@@ -0,0 +1,67 @@
+use core::cell::RefCell;
+
+use critical_section::Mutex;
+use dedrv::irq::{self, Controller, Irq};
+use dedrv::{Device, Driver, StateLock};
+
+/// The interrupt enumeration, as provided by a PAC.
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Clone, Copy)]
+#[repr(u16)]
+pub enum Interrupt {
+    USART1 = 37,
+}
+
+/// A controller that records the calls, in order.
+struct Recorder(Mutex<RefCell<Vec<(&'static str, Irq)>>>);
+
+impl Controller for Recorder {
+    fn enable(&self, irq: Irq) {
+        critical_section::with(|cs| self.0.borrow_ref_mut(cs).push(("enable", irq)));
+    }
+
+    fn disable(&self, irq: Irq) {
+        critical_section::with(|cs| self.0.borrow_ref_mut(cs).push(("disable", irq)));
+    }
+}
+
+static RECORDER: Recorder = Recorder(Mutex::new(RefCell::new(Vec::new())));
+
+#[cfg(test)]
+mod tests {
+    use googletest::prelude::*;
+
+    use super::*;
+
+    pub struct UartDriver;
+
+    impl Driver for UartDriver {
+        type StateType = ();
+
+        fn init(_state: &StateLock<Self>) {}
+        fn cleanup(_state: &StateLock<Self>) {}
+    }
+
+    #[dedrv::device(path = "/uart0", irq = Interrupt::USART1, irq_priority = 32)]
+    static UART0: Device<UartDriver> = Device::new();
+
+    #[test]
+    fn it_should_manage_irq_line() -> googletest::Result<()> {
+        irq::set_controller(&RECORDER);
+
+        let line = Irq::new(37).with_priority(32);
+        verify_that!(UART0.irq(), some(eq(line)))?;
+
+        UART0.init();
+        verify_that!(
+            critical_section::with(|cs| RECORDER.0.borrow_ref(cs).clone()),
+            elements_are![eq(&("enable", line))]
+        )?;
+
+        UART0.cleanup();
+        verify_that!(
+            critical_section::with(|cs| RECORDER.0.borrow_ref(cs).clone()),
+            elements_are![eq(&("enable", line)), eq(&("disable", line))]
+        )
+    }
+}
@@ -0,0 +1,111 @@
+#![cfg(feature = "mailbox")]
+
+use core::future::Future;
+use core::pin::pin;
+use core::task::{Context, Poll, Waker};
+
+use dedrv::mailbox::{Mailbox, Mailboxed};
+use dedrv::{Accessor, Device, Driver, StateLock};
+
+/// A command of the bus.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Read { addr: u8 },
+}
+
+/// Defines a bus class, whose transactions are requested through a mailbox.
+#[dedrv::class]
+pub trait Bus {
+    fn speed(&self) -> u32;
+}
+
+/// A fake bus, whose transactions are completed by the tests (i.e. the interrupt handler).
+#[derive(Default)]
+pub struct BusState {
+    mailbox: Mailbox<Command, u16>,
+    posted: usize,
+}
+
+pub struct BusDriver;
+
+impl Driver for BusDriver {
+    type StateType = BusState;
+
+    fn init(_state: &StateLock<Self>) {}
+    fn cleanup(_state: &StateLock<Self>) {}
+}
+
+impl Mailboxed<Command, u16> for BusDriver {
+    fn mailbox(state: &mut BusState) -> &mut Mailbox<Command, u16> {
+        &mut state.mailbox
+    }
+
+    fn posted(state: &StateLock<Self>) {
+        critical_section::with(|cs| state.borrow_ref_mut(cs).posted += 1);
+    }
+}
+
+impl driver::Bus for BusDriver {
+    fn speed(_state: &StateLock<Self>) -> u32 {
+        400_000
+    }
+}
+
+static BUS0: Device<BusDriver> = Device::new();
+
+/// The interrupt handler of the fake bus, which completes the oldest transaction.
+fn on_interrupt() -> bool {
+    critical_section::with(|cs| {
+        let mailbox = &mut BUS0.state_ref_mut(cs).mailbox;
+
+        match mailbox.take() {
+            Some((ticket, Command::Read { addr })) => mailbox.reply(ticket, addr as u16 * 2),
+            None => false,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use googletest::prelude::*;
+
+    use dedrv::Error;
+
+    use super::*;
+
+    #[test]
+    fn it_should_answer_requests() -> googletest::Result<()> {
+        let bus = BUS0.bus();
+
+        let mut first = bus.request::<_, u16>(Command::Read { addr: 1 })?;
+        let second = bus.request::<_, u16>(Command::Read { addr: 2 })?;
+        verify_that!(
+            critical_section::with(|cs| BUS0.state_ref(cs).posted),
+            eq(2)
+        )?;
+        verify_that!(first.try_response(), none())?;
+
+        // The response is awaited by a task, which is polled again once it has been woken up.
+        let mut response = pin!(second.response());
+        let mut cx = Context::from_waker(Waker::noop());
+        verify_that!(response.as_mut().poll(&mut cx), eq(Poll::Pending))?;
+
+        verify_that!((on_interrupt(), on_interrupt()), (eq(true), eq(true)))?;
+        verify_that!(first.try_response(), some(eq(2)))?;
+        verify_that!(response.as_mut().poll(&mut cx), eq(Poll::Ready(4)))?;
+
+        // A dropped request frees its slot, so the mailbox never fills up.
+        for addr in 0..8 {
+            drop(bus.request::<_, u16>(Command::Read { addr })?);
+        }
+        verify_that!(on_interrupt(), eq(false))?;
+
+        let _requests: Vec<_> = (0..4)
+            .map(|addr| bus.request::<_, u16>(Command::Read { addr }))
+            .collect::<dedrv::Result<_>>()?;
+        verify_that!(
+            bus.request::<_, u16>(Command::Read { addr: 4 }).err(),
+            some(eq(&Error::Busy))
+        )
+    }
+}
@@ -0,0 +1,59 @@
+use dedrv::{Accessor, Device, Driver};
+
+/// Defines a watchdog class, whose refresh only pokes a hardware register.
+#[dedrv::class]
+pub trait Watchdog {
+    #[dedrv::no_lock]
+    fn refresh(&self);
+
+    fn timeouts(&self) -> u32;
+}
+
+#[cfg(test)]
+mod tests {
+    use core::sync::atomic::{AtomicU32, Ordering};
+
+    use googletest::prelude::*;
+
+    use dedrv::{StateGuardMut, StateLock};
+
+    use super::*;
+
+    /// A fake refresh register.
+    static REFRESH: AtomicU32 = AtomicU32::new(0);
+
+    struct WatchdogDriver;
+
+    impl Driver for WatchdogDriver {
+        type StateType = u32;
+
+        fn init(_state: &StateLock<Self>) {}
+        fn cleanup(_state: &StateLock<Self>) {}
+    }
+
+    impl driver::Watchdog for WatchdogDriver {
+        fn refresh() {
+            REFRESH.fetch_add(1, Ordering::Relaxed);
+        }
+
+        fn timeouts(state: &StateLock<Self>) -> u32 {
+            critical_section::with(|cs| *state.borrow_ref(cs))
+        }
+    }
+
+    #[test]
+    fn it_should_call_driver_without_lock() -> googletest::Result<()> {
+        static DEVICE: Device<WatchdogDriver> = Device::new();
+
+        let watchdog = DEVICE.watchdog();
+
+        // The state is mutably borrowed, so a method that touches it would panic.
+        critical_section::with(|cs| {
+            let _guard = StateGuardMut::new(&DEVICE.state, cs);
+            watchdog.refresh();
+        });
+
+        verify_that!(REFRESH.load(Ordering::Relaxed), eq(1))?;
+        verify_that!(watchdog.timeouts(), eq(0))
+    }
+}
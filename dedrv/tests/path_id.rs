@@ -0,0 +1,59 @@
+#![cfg(feature = "path-id")]
+
+use dedrv::{Device, Driver, StateLock};
+
+pub struct UartDriver;
+
+impl Driver for UartDriver {
+    type StateType = ();
+
+    fn init(_state: &StateLock<Self>) {}
+    fn cleanup(_state: &StateLock<Self>) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use googletest::prelude::*;
+
+    use dedrv::{Descriptor, Error, PathId, Registry};
+
+    use super::*;
+
+    static UART0: Device<UartDriver> = Device::new();
+    static UART1: Device<UartDriver> = Device::new();
+
+    #[test]
+    fn it_should_find_device_by_id() -> googletest::Result<()> {
+        static TABLE: [Descriptor; 2] = [
+            Descriptor::new("/uart0", &UART0),
+            Descriptor::new("/uart1", &UART1),
+        ];
+        const ID: PathId = PathId::of("/uart1");
+
+        let registry = Registry::from_slice(&TABLE);
+
+        verify_that!(
+            registry.find_id(ID).map(|x| x.path().as_str()),
+            ok(eq(&"/uart1"))
+        )?;
+        verify_that!(
+            registry.find_id(PathId::of("/uart2")).map(|x| x.path()),
+            err(eq(&Error::DeviceNotFound))
+        )
+    }
+
+    #[test]
+    fn it_should_report_colliding_ids() -> googletest::Result<()> {
+        // These paths share the same FNV-1a hash.
+        static TABLE: [Descriptor; 2] = [
+            Descriptor::new("/dev1399086", &UART0),
+            Descriptor::new("/dev64059", &UART1),
+        ];
+
+        verify_that!(PathId::of("/dev1399086"), eq(PathId::of("/dev64059")))?;
+        verify_that!(
+            Registry::from_slice(&TABLE).init().map(|_| ()),
+            err(eq(&Error::PathCollision))
+        )
+    }
+}
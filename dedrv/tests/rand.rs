@@ -0,0 +1,74 @@
+#![cfg(feature = "rand")]
+
+use dedrv::rand::driver;
+use dedrv::{Driver, Error, Result, StateLock};
+
+/// A fake RNG, which counts up from its state, and fails once it reaches 0xff.
+pub struct RngDriver;
+
+impl Driver for RngDriver {
+    type StateType = u8;
+
+    fn init(_state: &StateLock<Self>) {}
+    fn cleanup(_state: &StateLock<Self>) {}
+}
+
+impl driver::Entropy for RngDriver {
+    fn fill(state: &StateLock<Self>, buf: &mut [u8]) -> Result<()> {
+        critical_section::with(|cs| {
+            let mut state = state.borrow_ref_mut(cs);
+
+            for byte in buf {
+                if *state == 0xff {
+                    return Err(Error::NotReady);
+                }
+
+                *state += 1;
+                *byte = *state;
+            }
+
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use googletest::prelude::*;
+
+    use dedrv::Device;
+
+    use super::*;
+
+    static RNG0: Device<RngDriver> = Device::new();
+
+    // The source is global, so the facade is only tested here.
+    #[test]
+    fn it_should_fill_from_registered_source() -> googletest::Result<()> {
+        let mut buf = [0u8; 4];
+        verify_that!(
+            dedrv::rand::fill(&mut buf),
+            err(eq(&Error::NoEntropySource))
+        )?;
+
+        dedrv::rand::set_source(&RNG0);
+        verify_that!(dedrv::rand::fill(&mut buf), err(eq(&Error::NotReady)))?;
+
+        RNG0.init();
+        dedrv::rand::fill(&mut buf)?;
+        verify_that!(buf, eq([1, 2, 3, 4]))?;
+
+        #[cfg(feature = "rand-core")]
+        {
+            use rand_core::RngCore;
+
+            let mut rng = dedrv::rand::Rng;
+            verify_that!(rng.next_u32(), eq(u32::from_ne_bytes([5, 6, 7, 8])))?;
+
+            critical_section::with(|cs| *RNG0.state_ref_mut(cs) = 0xfe);
+            verify_that!(rng.try_fill_bytes(&mut buf).is_err(), eq(true))?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,129 @@
+use dedrv::{Accessor, Device, Driver, StateLock};
+
+/// Defines a bus class, whose peripheral may get stuck.
+#[dedrv::class]
+pub trait Bus {
+    fn transfer(&mut self) -> bool;
+}
+
+/// A fake bus, which counts the init and cleanup calls.
+#[derive(Default)]
+pub struct BusState {
+    stuck: bool,
+    inits: u32,
+    cleanups: u32,
+}
+
+pub struct BusDriver;
+
+impl Driver for BusDriver {
+    type StateType = BusState;
+
+    fn init(state: &StateLock<Self>) {
+        critical_section::with(|cs| {
+            let mut state = state.borrow_ref_mut(cs);
+            state.stuck = false;
+            state.inits += 1;
+        })
+    }
+
+    fn cleanup(state: &StateLock<Self>) {
+        critical_section::with(|cs| state.borrow_ref_mut(cs).cleanups += 1)
+    }
+}
+
+impl driver::Bus for BusDriver {
+    fn transfer(state: &StateLock<Self>) -> bool {
+        critical_section::with(|cs| !state.borrow_ref(cs).stuck)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use googletest::prelude::*;
+
+    use dedrv::{Descriptor, Error, Lifecycle, Registry, StateGuard};
+
+    use super::*;
+
+    fn counts(device: &Device<BusDriver>) -> (u32, u32) {
+        critical_section::with(|cs| {
+            let state = device.state_ref(cs);
+            (state.inits, state.cleanups)
+        })
+    }
+
+    #[test]
+    fn it_should_recover_wedged_device() -> googletest::Result<()> {
+        static BUS0: Device<BusDriver> = Device::new();
+
+        verify_that!(BUS0.reset(), err(eq(&Error::NotReady)))?;
+
+        BUS0.init();
+        let mut bus = BUS0.bus();
+        critical_section::with(|cs| BUS0.state_ref_mut(cs).stuck = true);
+        verify_that!(bus.transfer(), eq(false))?;
+
+        BUS0.reset()?;
+        verify_that!(bus.transfer(), eq(true))?;
+        verify_that!(counts(&BUS0), eq((2, 1)))?;
+        verify_that!(BUS0.lifecycle(), eq(Lifecycle::Initialized))
+    }
+
+    #[test]
+    fn it_should_refuse_held_state() -> googletest::Result<()> {
+        static BUS0: Device<BusDriver> = Device::new();
+        BUS0.init();
+
+        critical_section::with(|cs| {
+            let _guard = StateGuard::new(&BUS0.state, cs);
+            verify_that!(BUS0.reset(), err(eq(&Error::Busy)))
+        })?;
+
+        verify_that!(counts(&BUS0), eq((1, 0)))
+    }
+
+    #[test]
+    fn it_should_refuse_exclusive_device() -> googletest::Result<()> {
+        static BUS0: Device<BusDriver> = Device::new();
+        BUS0.init();
+
+        let mut bus = BUS0.exclusive_accessor::<tag::Bus>()?;
+        verify_that!(BUS0.reset(), err(eq(&Error::Busy)))?;
+        verify_that!(bus.transfer(), eq(true))?;
+        verify_that!(counts(&BUS0), eq((1, 0)))?;
+
+        // The device is left exclusive to the open accessor.
+        verify_that!(BUS0.is_exclusive(), eq(true))?;
+        drop(bus);
+
+        BUS0.reset()?;
+        verify_that!(counts(&BUS0), eq((2, 1)))?;
+        verify_that!(BUS0.is_exclusive(), eq(false))
+    }
+
+    #[test]
+    fn it_should_reset_registry() -> googletest::Result<()> {
+        static BUS0: Device<BusDriver> = Device::new();
+        static BUS1: Device<BusDriver> = Device::new();
+        static BUS2: Device<BusDriver> = Device::new();
+        static TABLE: [Descriptor; 3] = [
+            Descriptor::new("/bus0", &BUS0),
+            Descriptor::new("/bus1", &BUS1),
+            Descriptor::new("/bus2", &BUS2),
+        ];
+
+        BUS0.init();
+        BUS1.init();
+
+        critical_section::with(|cs| {
+            let _guard = StateGuard::new(&BUS1.state, cs);
+            verify_that!(Registry::from_slice(&TABLE).reset(), ok(eq(&1)))
+        })?;
+
+        verify_that!(counts(&BUS0), eq((2, 1)))?;
+        verify_that!(counts(&BUS1), eq((1, 0)))?;
+        verify_that!(BUS2.lifecycle(), eq(Lifecycle::Uninitialized))?;
+        verify_that!(dedrv::reset_all(), ok(eq(&0)))
+    }
+}
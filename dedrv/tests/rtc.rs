@@ -0,0 +1,88 @@
+#![cfg(feature = "time")]
+
+use dedrv::time::DateTime;
+use dedrv::{Driver, Result, StateLock};
+
+/// A fake RTC, whose counter is advanced by the tests.
+#[derive(Default)]
+pub struct RtcState {
+    secs: u64,
+    alarm: Option<u64>,
+}
+
+pub struct RtcDriver;
+
+impl Driver for RtcDriver {
+    type StateType = RtcState;
+
+    fn init(_state: &StateLock<Self>) {}
+    fn cleanup(_state: &StateLock<Self>) {}
+}
+
+impl dedrv::time::driver::Rtc for RtcDriver {
+    fn now(state: &StateLock<Self>) -> Result<DateTime> {
+        DateTime::from_unix(critical_section::with(|cs| state.borrow_ref(cs).secs))
+    }
+
+    fn set(state: &StateLock<Self>, time: DateTime) -> Result<()> {
+        critical_section::with(|cs| state.borrow_ref_mut(cs).secs = time.to_unix());
+        Ok(())
+    }
+
+    fn set_alarm(state: &StateLock<Self>, at: DateTime) -> Result<()> {
+        critical_section::with(|cs| state.borrow_ref_mut(cs).alarm = Some(at.to_unix()));
+        Ok(())
+    }
+
+    fn cancel_alarm(state: &StateLock<Self>) {
+        critical_section::with(|cs| state.borrow_ref_mut(cs).alarm = None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use googletest::prelude::*;
+
+    use dedrv::time::{self, Rtc, RtcExt};
+    use dedrv::{Device, Error};
+
+    use super::*;
+
+    #[test]
+    fn it_should_answer_time_through_facade() -> googletest::Result<()> {
+        static RTC: Device<RtcDriver> = Device::new();
+
+        verify_that!(time::now(), err(eq(&Error::NotReady)))?;
+
+        time::set_clock(&RTC);
+        verify_that!(time::now(), err(eq(&Error::NotReady)))?;
+
+        RTC.init();
+        let boot = DateTime::new(2024, 12, 31, 23, 59, 58)?;
+        RTC.rtc().set(boot)?;
+        critical_section::with(|cs| RTC.state.borrow_ref_mut(cs).secs += 3);
+
+        verify_that!(
+            time::now().map(|x| x.to_string()),
+            ok(eq("2025-01-01T00:00:01"))
+        )
+    }
+
+    #[test]
+    fn it_should_set_and_cancel_alarm() -> googletest::Result<()> {
+        static RTC: Device<RtcDriver> = Device::new();
+
+        let mut rtc = RTC.rtc();
+        rtc.set_alarm(DateTime::EPOCH)?;
+        verify_that!(
+            critical_section::with(|cs| RTC.state.borrow_ref(cs).alarm),
+            some(eq(0))
+        )?;
+
+        rtc.cancel_alarm();
+        verify_that!(
+            critical_section::with(|cs| RTC.state.borrow_ref(cs).alarm),
+            none()
+        )
+    }
+}
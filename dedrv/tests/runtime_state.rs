@@ -0,0 +1,66 @@
+use dedrv::{Accessor, Device, Driver};
+
+/// Defines a peripheral class, whose driver owns HAL resources that are moved in at runtime.
+#[dedrv::class]
+pub trait Counter {
+    fn increment(&mut self) -> u32;
+}
+
+#[cfg(test)]
+mod tests {
+    use core::num::NonZeroU32;
+
+    use googletest::prelude::*;
+
+    use dedrv::{Slot, StateLock};
+
+    use super::*;
+
+    /// A state that is not valid when zeroed, as it holds a reference and a niche.
+    struct CounterState {
+        register: &'static mut u32,
+        step: NonZeroU32,
+    }
+
+    struct CounterDriver;
+
+    impl Driver for CounterDriver {
+        type StateType = Slot<CounterState>;
+
+        fn init(state: &StateLock<Self>) {
+            critical_section::with(|cs| *state.borrow_ref_mut(cs).register = 10);
+        }
+
+        fn cleanup(state: &StateLock<Self>) {
+            critical_section::with(|cs| state.borrow_ref_mut(cs).take());
+        }
+    }
+
+    impl driver::Counter for CounterDriver {
+        fn increment(state: &StateLock<Self>) -> u32 {
+            critical_section::with(|cs| {
+                let mut state = state.borrow_ref_mut(cs);
+                *state.register += state.step.get();
+                *state.register
+            })
+        }
+    }
+
+    #[test]
+    fn it_should_init_device_with_runtime_state() -> googletest::Result<()> {
+        static DEVICE: Device<CounterDriver> = Device::new();
+
+        // A HAL resource, such as the one handed out by a `StaticCell`.
+        let register = Box::leak(Box::new(0u32));
+        let step = NonZeroU32::new(5).unwrap();
+
+        DEVICE.init_with(|slot| slot.write(CounterState { register, step }));
+        verify_that!(DEVICE.counter().increment(), eq(15))?;
+
+        DEVICE.cleanup();
+        verify_that!(
+            critical_section::with(|cs| DEVICE.state.borrow_ref(cs).is_init()),
+            eq(false)
+        )
+    }
+}
@@ -0,0 +1,75 @@
+use dedrv::{Accessor, Device, Driver};
+
+/// Defines a sensor class, whose driver checks its identification register.
+#[dedrv::class]
+pub trait Sensor {
+    fn sample(&self) -> u16;
+}
+
+#[cfg(test)]
+mod tests {
+    use googletest::prelude::*;
+
+    use dedrv::{Descriptor, Error, Registry, StateLock};
+
+    use super::*;
+
+    /// A sensor driver, whose state is the identification register.
+    struct SensorDriver;
+
+    impl Driver for SensorDriver {
+        type StateType = u8;
+
+        fn init(_state: &StateLock<Self>) {}
+        fn cleanup(_state: &StateLock<Self>) {}
+
+        fn selftest(state: &StateLock<Self>) -> dedrv::Result<()> {
+            match critical_section::with(|cs| *state.borrow_ref(cs)) {
+                0x42 => Ok(()),
+                _ => Err(Error::Undefined),
+            }
+        }
+    }
+
+    impl driver::Sensor for SensorDriver {
+        fn sample(_state: &StateLock<Self>) -> u16 {
+            0
+        }
+    }
+
+    #[test]
+    fn it_should_record_selftest_result() -> googletest::Result<()> {
+        static DEVICE: Device<SensorDriver> = Device::new();
+
+        verify_that!(DEVICE.selftest_result(), none())?;
+        verify_that!(DEVICE.selftest(), err(eq(&Error::NotReady)))?;
+
+        DEVICE.init();
+        critical_section::with(|cs| *DEVICE.state_ref_mut(cs) = 0x42);
+
+        verify_that!(DEVICE.selftest(), ok(eq(&())))?;
+        verify_that!(DEVICE.selftest_result(), some(ok(eq(&()))))
+    }
+
+    #[test]
+    fn it_should_selftest_through_descriptor() -> googletest::Result<()> {
+        static GOOD: Device<SensorDriver> = Device::new();
+        static BAD: Device<SensorDriver> = Device::new();
+        static TABLE: [Descriptor; 2] = [
+            Descriptor::new("/bad", &BAD),
+            Descriptor::new("/good", &GOOD),
+        ];
+
+        Registry::from_slice(&TABLE).init()?;
+        critical_section::with(|cs| *GOOD.state_ref_mut(cs) = 0x42);
+
+        verify_that!(TABLE[0].selftest(), err(eq(&Error::Undefined)))?;
+        verify_that!(TABLE[1].selftest(), ok(eq(&())))?;
+        verify_that!(BAD.selftest_result(), some(err(eq(&Error::Undefined))))
+    }
+
+    #[test]
+    fn it_should_selftest_empty_registry_on_host() -> googletest::Result<()> {
+        verify_that!(dedrv::selftest_all(|_, _| {}), ok(eq(&0)))
+    }
+}
@@ -0,0 +1,124 @@
+#![cfg(feature = "sensor")]
+
+use dedrv::sensor::{driver, Calibration, Unit};
+use dedrv::{Driver, Error, Result, StateLock};
+
+/// A fake temperature sensor, whose raw sample is set by the tests (0 if disconnected).
+pub struct TempDriver;
+
+impl Driver for TempDriver {
+    type StateType = i32;
+
+    fn init(_state: &StateLock<Self>) {}
+    fn cleanup(_state: &StateLock<Self>) {}
+}
+
+impl driver::Sensor for TempDriver {
+    fn read_raw(state: &StateLock<Self>) -> Result<i32> {
+        match critical_section::with(|cs| *state.borrow_ref(cs)) {
+            0 => Err(Error::NotReady),
+            raw => Ok(raw),
+        }
+    }
+
+    fn calibration(_state: &StateLock<Self>) -> Calibration {
+        Calibration::new(0, 78125, 10000)
+    }
+
+    fn unit(_state: &StateLock<Self>) -> Unit {
+        Unit::Celsius
+    }
+}
+
+/// A fake ADC frontend, whose offset is trimmed at runtime.
+pub struct AdcDriver;
+
+impl Driver for AdcDriver {
+    type StateType = i32;
+
+    fn init(_state: &StateLock<Self>) {}
+    fn cleanup(_state: &StateLock<Self>) {}
+}
+
+impl driver::Sensor for AdcDriver {
+    fn read_raw(_state: &StateLock<Self>) -> Result<i32> {
+        Ok(2048)
+    }
+
+    fn calibration(state: &StateLock<Self>) -> Calibration {
+        Calibration::new(
+            critical_section::with(|cs| *state.borrow_ref(cs)),
+            3300,
+            4096,
+        )
+    }
+
+    fn unit(_state: &StateLock<Self>) -> Unit {
+        Unit::Volt
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use googletest::prelude::*;
+
+    use dedrv::sensor::{self, tag, Measurement, Reader, SensorExt};
+    use dedrv::{ClassId, Descriptor, Device, DeviceTable, Registry};
+
+    use super::*;
+
+    static ADC0: Device<AdcDriver> = Device::new();
+    static TEMP0: Device<TempDriver> = Device::new();
+    static TEMP1: Device<TempDriver> = Device::new();
+
+    static ADC0_CLASSES: [ClassId; 1] = [ClassId::of::<_, tag::Sensor>(&ADC0)];
+    static TEMP0_CLASSES: [ClassId; 1] = [ClassId::of::<_, tag::Sensor>(&TEMP0)];
+    static TEMP1_CLASSES: [ClassId; 1] = [ClassId::of::<_, tag::Sensor>(&TEMP1)];
+
+    static TABLE: [Descriptor; 3] = [
+        Descriptor::new("/adc0", &ADC0).with_classes(&ADC0_CLASSES),
+        Descriptor::new("/temp0", &TEMP0).with_classes(&TEMP0_CLASSES),
+        Descriptor::new("/temp1", &TEMP1).with_classes(&TEMP1_CLASSES),
+    ];
+
+    #[test]
+    fn it_should_read_scaled_measurement() -> googletest::Result<()> {
+        critical_section::with(|cs| *TEMP0.state_ref_mut(cs) = 3000);
+        critical_section::with(|cs| *ADC0.state_ref_mut(cs) = -48);
+
+        verify_that!(
+            TEMP0.sensor().read_scaled(),
+            ok(eq(&Measurement {
+                milli: 23437,
+                unit: Unit::Celsius
+            }))
+        )?;
+        verify_that!(
+            ADC0.sensor().read_scaled().map(|x| x.to_string()),
+            ok(eq("1.611 V"))
+        )
+    }
+
+    #[test]
+    fn it_should_read_all_sensors_of_registry() -> googletest::Result<()> {
+        static READERS: [Reader; 1] = [Reader::of::<TempDriver>()];
+
+        critical_section::with(|cs| *TEMP0.state_ref_mut(cs) = 3000);
+
+        let mut readings = Vec::new();
+        let count = sensor::read_all(
+            &DeviceTable::from(Registry::from_slice(&TABLE)),
+            &READERS,
+            |path, result| readings.push((path.as_str(), result.map(|x| x.milli))),
+        );
+
+        verify_that!(count, eq(2))?;
+        verify_that!(
+            readings,
+            elements_are![
+                eq(&("/temp0", Ok(23437))),
+                eq(&("/temp1", Err(Error::NotReady)))
+            ]
+        )
+    }
+}
@@ -0,0 +1,169 @@
+#![cfg(feature = "shell")]
+
+use dedrv::{Accessor, Driver, Result, StateLock};
+
+/// Defines a peripheral class, whose method is exposed to the shell.
+#[dedrv::class]
+pub trait Led {
+    fn toggle(&mut self) -> bool;
+}
+
+/// A fake serial line, whose input is typed by the tests.
+pub struct SerialState {
+    input: [u8; 32],
+    input_len: usize,
+    output: [u8; 256],
+    output_len: usize,
+}
+
+pub struct SerialDriver;
+
+impl Driver for SerialDriver {
+    type StateType = SerialState;
+
+    fn init(_state: &StateLock<Self>) {}
+    fn cleanup(_state: &StateLock<Self>) {}
+}
+
+impl dedrv::shell::driver::CharDevice for SerialDriver {
+    fn read(state: &StateLock<Self>, buf: &mut [u8]) -> Result<usize> {
+        critical_section::with(|cs| {
+            let mut state = state.borrow_ref_mut(cs);
+            let len = state.input_len.min(buf.len());
+
+            buf[..len].copy_from_slice(&state.input[..len]);
+            state.input.copy_within(len.., 0);
+            state.input_len -= len;
+            Ok(len)
+        })
+    }
+
+    fn write(state: &StateLock<Self>, buf: &[u8]) -> Result<usize> {
+        critical_section::with(|cs| {
+            let mut state = state.borrow_ref_mut(cs);
+            let start = state.output_len;
+            let len = buf.len().min(state.output.len() - start);
+
+            state.output[start..start + len].copy_from_slice(&buf[..len]);
+            state.output_len += len;
+            Ok(len)
+        })
+    }
+}
+
+pub struct LedDriver;
+
+impl Driver for LedDriver {
+    type StateType = bool;
+
+    fn init(_state: &StateLock<Self>) {}
+    fn cleanup(_state: &StateLock<Self>) {}
+}
+
+impl driver::Led for LedDriver {
+    fn toggle(state: &StateLock<Self>) -> bool {
+        critical_section::with(|cs| {
+            let mut state = state.borrow_ref_mut(cs);
+            *state = !*state;
+            *state
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use googletest::prelude::*;
+
+    use dedrv::shell::{self, Command, Shell};
+    use dedrv::{ClassId, Descriptor, Device, DeviceTable, Error, Registry};
+
+    use super::*;
+
+    static LED0: Device<LedDriver> = Device::new();
+    static SERIAL0: Device<SerialDriver> = Device::new();
+
+    static LED0_CLASSES: [ClassId; 1] = [ClassId::of::<_, tag::Led>(&LED0)];
+    static SERIAL0_CLASSES: [ClassId; 1] = [ClassId::of::<_, shell::tag::CharDevice>(&SERIAL0)];
+
+    static TABLE: [Descriptor; 2] = [
+        Descriptor::new("/soc/led0", &LED0).with_classes(&LED0_CLASSES),
+        Descriptor::new("/soc/serial0", &SERIAL0).with_classes(&SERIAL0_CLASSES),
+    ];
+
+    static COMMANDS: [Command; 1] = [Command::new::<tag::Led>(
+        "toggle",
+        "toggle the led",
+        |desc, _, out| {
+            let mut led = desc
+                .accessor::<LedDriver, tag::Led>()
+                .ok_or(Error::DeviceNotFound)?;
+            writeln!(out, "{}", led.toggle())?;
+            Ok(())
+        },
+    )];
+
+    fn shell() -> Shell {
+        Shell::new(
+            DeviceTable::new([Registry::from_slice(&TABLE), Registry::empty()]),
+            &COMMANDS,
+        )
+    }
+
+    #[test]
+    fn it_should_list_devices() -> googletest::Result<()> {
+        let mut out = String::new();
+        shell().execute("ls /soc", &mut out)?;
+
+        verify_that!(
+            out,
+            all![
+                contains_substring("/soc/led0"),
+                contains_substring("/soc/serial0"),
+                contains_substring("uninitialized")
+            ]
+        )?;
+
+        let mut out = String::new();
+        shell().execute("status /soc/led0", &mut out)?;
+        verify_that!(out, contains_substring("commands:  toggle\n"))
+    }
+
+    #[test]
+    fn it_should_call_registered_command() -> googletest::Result<()> {
+        let mut out = String::new();
+        shell().execute("call /soc/led0 toggle", &mut out)?;
+        verify_that!(out, eq("true\n"))?;
+
+        verify_that!(
+            shell().execute("call /soc/serial0 toggle", &mut out),
+            err(eq(&Error::InvalidCommand))
+        )
+    }
+
+    #[test]
+    fn it_should_run_over_char_device() -> googletest::Result<()> {
+        let mut console: Accessor<_, shell::tag::CharDevice> = SERIAL0.accessor();
+
+        critical_section::with(|cs| {
+            let mut state = SERIAL0.state_ref_mut(cs);
+            let line = b"call /soc/nope toggle\r";
+
+            state.input[..line.len()].copy_from_slice(line);
+            state.input_len = line.len();
+        });
+
+        let mut shell = shell();
+        shell.poll(&mut console)?;
+        shell.poll(&mut console)?;
+
+        let output = critical_section::with(|cs| {
+            let state = SERIAL0.state_ref(cs);
+            String::from_utf8(state.output[..state.output_len].to_vec())
+        })?;
+
+        verify_that!(
+            output,
+            eq("call /soc/nope toggle\nerror: device not found\n> ")
+        )
+    }
+}
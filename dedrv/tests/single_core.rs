@@ -0,0 +1,56 @@
+#![cfg(all(feature = "single-core", target_os = "none"))]
+
+use core::marker::PhantomData;
+
+use dedrv::{Accessor, Device, Driver};
+
+/// Defines a peripheral class, whose driver owns a `!Send` HAL singleton.
+#[dedrv::class]
+pub trait Led {
+    fn toggle(&mut self) -> bool;
+}
+
+#[cfg(test)]
+mod tests {
+    use googletest::prelude::*;
+
+    use dedrv::StateLock;
+
+    use super::*;
+
+    /// A HAL pin singleton, which must not leave its execution context.
+    #[derive(Default)]
+    struct Pin {
+        high: bool,
+        _not_send: PhantomData<*const ()>,
+    }
+
+    struct LedDriver;
+
+    impl Driver for LedDriver {
+        type StateType = Pin;
+
+        fn init(_state: &StateLock<Self>) {}
+        fn cleanup(_state: &StateLock<Self>) {}
+    }
+
+    impl driver::Led for LedDriver {
+        fn toggle(state: &StateLock<Self>) -> bool {
+            critical_section::with(|cs| {
+                let mut pin = state.borrow_ref_mut(cs);
+                pin.high = !pin.high;
+                pin.high
+            })
+        }
+    }
+
+    #[test]
+    fn it_should_store_non_send_state_in_static_device() -> googletest::Result<()> {
+        static DEVICE: Device<LedDriver> = Device::new();
+
+        let mut led = DEVICE.led();
+
+        verify_that!(led.toggle(), eq(true))?;
+        verify_that!(led.toggle(), eq(false))
+    }
+}
@@ -0,0 +1,66 @@
+use dedrv::{Accessor, Device, Driver};
+
+/// Defines a peripheral class, whose device has exactly one owner.
+#[dedrv::class]
+pub trait Led {
+    fn toggle(&mut self) -> bool;
+}
+
+#[cfg(test)]
+mod tests {
+    use googletest::prelude::*;
+
+    use dedrv::StateLock;
+
+    use super::*;
+
+    pub struct LedDriver;
+
+    impl Driver for LedDriver {
+        type StateType = bool;
+
+        fn init(_state: &StateLock<Self>) {}
+        fn cleanup(_state: &StateLock<Self>) {}
+    }
+
+    impl driver::Led for LedDriver {
+        fn toggle(state: &StateLock<Self>) -> bool {
+            critical_section::with(|cs| {
+                let mut state = state.borrow_ref_mut(cs);
+                *state = !*state;
+                *state
+            })
+        }
+    }
+
+    #[dedrv::device(path = "/led0", singleton)]
+    static LED0: Device<LedDriver> = Device::new();
+
+    #[dedrv::device(path = "/led1", singleton)]
+    static LED1: Device<LedDriver> = Device::new();
+
+    #[test]
+    fn it_should_take_device_once() -> googletest::Result<()> {
+        let mut led = LED0::take::<tag::Led>().expect("first take");
+        verify_that!(led.toggle(), eq(true))?;
+
+        verify_that!(LED0::take::<tag::Led>().is_none(), eq(true))?;
+
+        // The device remains owned after the accessor is dropped.
+        drop(led);
+        verify_that!(LED0::take::<tag::Led>().is_none(), eq(true))
+    }
+
+    #[test]
+    fn it_should_not_take_device_while_exclusive() -> googletest::Result<()> {
+        LED1.init();
+
+        let exclusive = LED1.exclusive_accessor::<tag::Led>()?;
+        verify_that!(LED1::take::<tag::Led>().is_none(), eq(true))?;
+
+        // The device is not taken by the failed attempt.
+        drop(exclusive);
+        verify_that!(LED1::take::<tag::Led>().is_some(), eq(true))?;
+        verify_that!(LED1::take::<tag::Led>().is_none(), eq(true))
+    }
+}
@@ -0,0 +1,107 @@
+use dedrv::{Accessor, Device, Driver};
+
+/// Defines the input mode of a GPIO pin.
+#[dedrv::class]
+pub trait GpioInput {
+    fn is_high(&self) -> bool;
+    fn into_output(self, high: bool) -> dedrv::Accessor<'d, D, Output>;
+}
+
+/// Defines the output mode of a GPIO pin.
+#[dedrv::class(driver_mod = "output_driver", tag = "Output")]
+pub trait GpioOutput {
+    fn set_high(&mut self, high: bool);
+    fn into_input(self) -> dedrv::Accessor<'d, D, tag::GpioInput>;
+}
+
+#[cfg(test)]
+mod tests {
+    use googletest::prelude::*;
+
+    use dedrv::StateLock;
+
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Mode {
+        Input,
+        Output,
+    }
+
+    struct PinDriver;
+
+    impl Driver for PinDriver {
+        type StateType = (Option<Mode>, bool);
+
+        fn init(state: &StateLock<Self>) {
+            critical_section::with(|cs| *state.borrow_ref_mut(cs) = (Some(Mode::Input), false));
+        }
+
+        fn cleanup(_state: &StateLock<Self>) {}
+    }
+
+    impl driver::GpioInput for PinDriver {
+        fn is_high(state: &StateLock<Self>) -> bool {
+            critical_section::with(|cs| state.borrow_ref(cs).1)
+        }
+
+        fn into_output(state: &StateLock<Self>, high: bool) {
+            critical_section::with(|cs| *state.borrow_ref_mut(cs) = (Some(Mode::Output), high));
+        }
+    }
+
+    impl output_driver::GpioOutput for PinDriver {
+        fn set_high(state: &StateLock<Self>, high: bool) {
+            critical_section::with(|cs| state.borrow_ref_mut(cs).1 = high);
+        }
+
+        fn into_input(state: &StateLock<Self>) {
+            critical_section::with(|cs| state.borrow_ref_mut(cs).0 = Some(Mode::Input));
+        }
+    }
+
+    fn mode(device: &Device<PinDriver>) -> Option<Mode> {
+        critical_section::with(|cs| device.state_ref(cs).0)
+    }
+
+    #[test]
+    fn it_should_transition_between_tags() -> googletest::Result<()> {
+        static PIN: Device<PinDriver> = Device::new();
+        PIN.init();
+
+        let input = PIN.gpio_input();
+        verify_that!(input.is_high(), eq(false))?;
+
+        let mut output: Accessor<'_, PinDriver, Output> = input.into_output(true);
+        verify_that!(mode(&PIN), some(eq(Mode::Output)))?;
+        output.set_high(false);
+
+        let input = output.into_input();
+        verify_that!(mode(&PIN), some(eq(Mode::Input)))?;
+        verify_that!(input.is_high(), eq(false))
+    }
+
+    #[test]
+    fn it_should_keep_accessor_count_across_transitions() -> googletest::Result<()> {
+        static PIN: Device<PinDriver> = Device::new();
+        PIN.init();
+
+        let output = PIN.gpio_input().into_output(false);
+        verify_that!(PIN.accessors(), eq(1))?;
+
+        drop(output);
+        verify_that!(PIN.accessors(), eq(0))
+    }
+
+    #[test]
+    fn it_should_not_compile_input_method_on_output() {
+        let t = trybuild::TestCases::new();
+        t.compile_fail("tests/units/typestate_wrong_mode.rs");
+    }
+
+    #[test]
+    fn it_should_not_compile_transition_through_reference() {
+        let t = trybuild::TestCases::new();
+        t.compile_fail("tests/units/typestate_reference.rs");
+    }
+}
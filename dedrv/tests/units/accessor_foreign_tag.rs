@@ -0,0 +1,36 @@
+use dedrv::{Accessor, Device, Driver, StateLock};
+
+#[dedrv::class]
+pub trait Gpio {
+    fn get_value(&self) -> u32;
+}
+
+pub mod uart {
+    use dedrv::Accessor;
+
+    #[dedrv::class]
+    pub trait Uart {
+        fn write(&mut self, byte: u8);
+    }
+}
+
+struct GpioDriver;
+
+impl Driver for GpioDriver {
+    type StateType = u32;
+
+    fn init(_state: &StateLock<Self>) {}
+    fn cleanup(_state: &StateLock<Self>) {}
+}
+
+impl driver::Gpio for GpioDriver {
+    fn get_value(state: &StateLock<Self>) -> u32 {
+        critical_section::with(|cs| *state.borrow_ref(cs))
+    }
+}
+
+static GPIO0: Device<GpioDriver> = Device::new();
+
+fn main() {
+    let _ = Accessor::<_, uart::tag::Uart>::new(&GPIO0);
+}
@@ -0,0 +1,16 @@
+use dedrv::{Device, Driver, StateLock};
+
+struct SequencerDriver;
+
+impl Driver for SequencerDriver {
+    type StateType = u32;
+
+    fn init(_state: &StateLock<Self>) {}
+    fn cleanup(_state: &StateLock<Self>) {}
+}
+
+static SEQ0: Device<SequencerDriver> = Device::new().with_max_accessors(1);
+
+fn main() {
+    let _ = &SEQ0;
+}
@@ -0,0 +1,21 @@
+use dedrv::{Accessor, Device, Driver, StateLock};
+
+#[dedrv::class]
+pub trait Gpio {
+    fn get_value(&self) -> u32;
+}
+
+struct UartDriver;
+
+impl Driver for UartDriver {
+    type StateType = u32;
+
+    fn init(_state: &StateLock<Self>) {}
+    fn cleanup(_state: &StateLock<Self>) {}
+}
+
+static UART0: Device<UartDriver> = Device::new();
+
+fn main() {
+    let _ = UART0.accessor::<tag::Gpio>();
+}
@@ -0,0 +1,28 @@
+use dedrv::{Accessor, Device, Driver, StateLock};
+
+#[dedrv::class]
+pub trait Gpio {
+    fn get_value(&self) -> u32;
+}
+
+struct GpioDriver;
+
+impl Driver for GpioDriver {
+    type StateType = u32;
+
+    fn init(_state: &StateLock<Self>) {}
+    fn cleanup(_state: &StateLock<Self>) {}
+}
+
+impl driver::Gpio for GpioDriver {
+    fn get_value(state: &StateLock<Self>) -> u32 {
+        critical_section::with(|cs| *state.borrow_ref(cs))
+    }
+}
+
+static GPIO0: Device<GpioDriver> = Device::new();
+
+fn main() {
+    let gpio: Accessor<_> = GPIO0.accessor();
+    let _ = gpio.get_value();
+}
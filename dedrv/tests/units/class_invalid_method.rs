@@ -0,0 +1,21 @@
+mod gpio {
+    use dedrv::Accessor;
+
+    #[dedrv::class]
+    pub trait Gpio {
+        fn get_value(&self) -> u32;
+
+        fn set_value(value: u32);
+    }
+}
+
+mod uart {
+    use dedrv::Accessor;
+
+    #[dedrv::class(tag = "not a tag")]
+    pub trait Uart {
+        fn start_read(&self, buf: &'static mut [u8]) -> dedrv::dma::Transfer<'_, &'static mut [u8]>;
+    }
+}
+
+fn main() {}
@@ -0,0 +1,18 @@
+use dedrv::{Device, Driver, StateLock};
+
+struct GpioDriver;
+
+impl Driver for GpioDriver {
+    type StateType = u32;
+
+    fn init(_state: &StateLock<Self>) {}
+    fn cleanup(_state: &StateLock<Self>) {}
+}
+
+#[dedrv::device(path = "/gpio0", irq_priority = 32)]
+static GPIO0: Device<GpioDriver> = Device::new();
+
+#[dedrv::device(classes())]
+static GPIO1: Device<GpioDriver> = Device::new();
+
+fn main() {}
@@ -0,0 +1,30 @@
+use dedrv::{Device, Driver, DriverConfig, StateLock};
+
+struct FifoConfig {
+    depth: usize,
+}
+
+struct FifoDriver;
+
+impl Driver for FifoDriver {
+    type StateType = usize;
+
+    const CHECK: () = assert!(
+        Self::CONFIG.depth.is_power_of_two(),
+        "the FIFO depth must be a power of two"
+    );
+
+    fn init(_state: &StateLock<Self>) {}
+    fn cleanup(_state: &StateLock<Self>) {}
+}
+
+impl DriverConfig for FifoDriver {
+    type Config = FifoConfig;
+
+    const CONFIG: FifoConfig = FifoConfig { depth: 3 };
+}
+
+#[dedrv::device(path = "/fifo0")]
+static FIFO0: Device<FifoDriver> = Device::new();
+
+fn main() {}
@@ -0,0 +1,17 @@
+use dedrv::{Device, Driver, StateGuard, StateLock};
+
+struct CounterDriver;
+
+impl Driver for CounterDriver {
+    type StateType = u32;
+
+    fn init(_state: &StateLock<Self>) {}
+    fn cleanup(_state: &StateLock<Self>) {}
+}
+
+static DEVICE: Device<CounterDriver> = Device::new();
+
+fn main() {
+    let guard = critical_section::with(|cs| StateGuard::new(&DEVICE.state, cs));
+    let _ = *guard;
+}
@@ -0,0 +1,44 @@
+use dedrv::{Accessor, Device, Driver, StateLock};
+
+#[dedrv::class]
+pub trait GpioInput {
+    fn is_high(&self) -> bool;
+    fn into_output(self) -> dedrv::Accessor<'d, D, Output>;
+}
+
+#[dedrv::class(driver_mod = "output_driver", tag = "Output")]
+pub trait GpioOutput {
+    fn set_high(&mut self, high: bool);
+}
+
+struct PinDriver;
+
+impl Driver for PinDriver {
+    type StateType = bool;
+
+    fn init(_state: &StateLock<Self>) {}
+    fn cleanup(_state: &StateLock<Self>) {}
+}
+
+impl driver::GpioInput for PinDriver {
+    fn is_high(_state: &StateLock<Self>) -> bool {
+        false
+    }
+
+    fn into_output(_state: &StateLock<Self>) {}
+}
+
+impl output_driver::GpioOutput for PinDriver {
+    fn set_high(_state: &StateLock<Self>, _high: bool) {}
+}
+
+static PIN: Device<PinDriver> = Device::new();
+
+fn is_high(pin: impl GpioInput) -> bool {
+    pin.is_high()
+}
+
+fn main() {
+    let input = PIN.gpio_input();
+    is_high(&input);
+}
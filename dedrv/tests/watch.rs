@@ -0,0 +1,106 @@
+#![cfg(feature = "watch")]
+
+use core::future::Future;
+use core::pin::pin;
+use core::task::{Context, Poll, Waker};
+
+use dedrv::watch::{Watch, Watched};
+use dedrv::{Accessor, Device, Driver, StateLock};
+
+/// The link state of a PHY.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Link {
+    Down,
+    Up,
+}
+
+/// Defines a PHY class, whose driver publishes the link state.
+#[dedrv::class]
+pub trait Phy {
+    fn reset(&mut self);
+}
+
+/// A fake PHY, whose link is driven by the tests (i.e. the interrupt handler).
+#[derive(Default)]
+pub struct PhyState {
+    link: Watch<Link>,
+}
+
+pub struct PhyDriver;
+
+impl Driver for PhyDriver {
+    type StateType = PhyState;
+
+    fn init(_state: &StateLock<Self>) {}
+    fn cleanup(_state: &StateLock<Self>) {}
+}
+
+impl Watched<Link> for PhyDriver {
+    fn watch(state: &mut PhyState) -> &mut Watch<Link> {
+        &mut state.link
+    }
+}
+
+impl driver::Phy for PhyDriver {
+    fn reset(state: &StateLock<Self>) {
+        critical_section::with(|cs| state.borrow_ref_mut(cs).link.send(Link::Down))
+    }
+}
+
+/// Poll a future once.
+fn poll_once<F: Future>(future: &mut core::pin::Pin<&mut F>) -> Poll<F::Output> {
+    future
+        .as_mut()
+        .poll(&mut Context::from_waker(Waker::noop()))
+}
+
+#[cfg(test)]
+mod tests {
+    use googletest::prelude::*;
+
+    use super::*;
+
+    static PHY0: Device<PhyDriver> = Device::new();
+
+    fn interrupt(link: Link) {
+        critical_section::with(|cs| PHY0.state_ref_mut(cs).link.send(link));
+    }
+
+    #[test]
+    fn it_should_broadcast_latest_value() -> googletest::Result<()> {
+        PHY0.phy().reset();
+
+        let phy = PHY0.phy();
+        let mut first = phy.watch::<Link>();
+        verify_that!(first.try_changed(), some(eq(Link::Down)))?;
+        verify_that!(first.try_changed(), none())?;
+
+        interrupt(Link::Down);
+        interrupt(Link::Up);
+
+        let mut second = phy.watch::<Link>();
+        verify_that!(first.try_changed(), some(eq(Link::Up)))?;
+        verify_that!(first.try_changed(), none())?;
+        verify_that!(second.try_changed(), some(eq(Link::Up)))?;
+        verify_that!(first.get(), some(eq(Link::Up)))
+    }
+
+    #[test]
+    fn it_should_await_changes() -> googletest::Result<()> {
+        static PHY1: Device<PhyDriver> = Device::new();
+
+        let phy = PHY1.phy();
+        let mut link = phy.watch::<Link>();
+
+        {
+            let mut changed = pin!(link.changed());
+            verify_that!(poll_once(&mut changed).is_pending(), eq(true))?;
+
+            critical_section::with(|cs| PHY1.state_ref_mut(cs).link.send(Link::Up));
+            verify_that!(poll_once(&mut changed), eq(Poll::Ready(Link::Up)))?;
+        }
+
+        let mut changed = pin!(link.changed());
+        verify_that!(poll_once(&mut changed).is_pending(), eq(true))
+    }
+}
@@ -38,7 +38,7 @@ impl driver::Gpio for GpioDriver {
     }
 }
 
-#[dedrv::device(path = "/gpio0")]
+#[dedrv::device(path = "/gpio0", classes(tag::Gpio))]
 static GPIO0: Device<GpioDriver> = Device::new();
 
 #[cortex_m_rt::entry]
@@ -46,9 +46,9 @@ fn main() -> ! {
     info!("Hello, World from Rust!");
 
     // Init drivers.
-    dedrv::init();
+    dedrv::init().expect("invalid device descriptor table");
 
-    let gpio = GPIO0.accessor::<tag::Gpio>();
+    let gpio = GPIO0.gpio();
     gpio.configure(0 /* pin */, PinMode::Output);
 
     info!("init ok");